@@ -1,6 +1,9 @@
 BEGIN;
 
 DELETE FROM "migrations" WHERE name IN (
+    'NotificationNoteCount1792375200000',
+    'AntennaNotifyDigest1792371600000',
+    'AntennaMatchOptions1792368000000',
     'antennaLimit1712937600000',
     'SetEmojiPublicUrl1722346019160',
     'SetAccessTokenName1722134626110',
@@ -44,6 +47,17 @@ DELETE FROM "migrations" WHERE name IN (
     'SwSubscriptionAccessToken1709395223611'
 );
 
+-- notification-note-count
+ALTER TABLE "notification" DROP COLUMN "noteCount";
+
+-- antenna-notify-digest
+ALTER TABLE "antenna" DROP COLUMN "notifyDigest";
+
+-- antenna-match-options
+ALTER TABLE "antenna" DROP COLUMN "excludeLangs";
+ALTER TABLE "antenna" DROP COLUMN "langs";
+ALTER TABLE "antenna" DROP COLUMN "wholeWord";
+
 -- antenna-limit
 ALTER TABLE "meta" DROP COLUMN "antennaLimit";
 
@@ -109,6 +109,27 @@ export interface Antenna {
   instances: Array<string>
   keywords: Array<string>
   excludeKeywords: Array<string>
+  wholeWord: boolean
+  langs: Array<string>
+  excludeLangs: Array<string>
+  notifyDigest: boolean
+}
+
+/** `app` notification to be created for the antenna owner */
+export interface AntennaNotification {
+  /** ID of the antenna owner */
+  notifieeId: string
+  /** name of the antenna */
+  header: string
+  /**
+   * summary of the note, which is [None] for a digest of multiple notes
+   * or a deleted note so that the client shows the localized number of notes
+   */
+  body: string | null
+  /** number of notes added to the antenna */
+  noteCount: number
+  /** ID of the (latest) note, which is [None] if it has been deleted */
+  noteId: string | null
 }
 
 export type AntennaSrc =  'all'|
@@ -227,6 +248,22 @@ export interface AuthSession {
   appId: string
 }
 
+/**
+ * Evaluates recent notes against the antenna, adds the matched notes to its timeline,
+ * and returns the number of notes added.
+ *
+ * This is intended for newly created or edited antennas, and is run as a queued job
+ * (see [reserve_antenna_backfill] for the rate limit). Notes are evaluated from the
+ * newest one, up to [MAX_BACKFILL_NOTES] notes. The matched notes are merged into the
+ * timeline, so the existing entries and the notes pushed during the backfill are kept.
+ *
+ * # Arguments
+ *
+ * * `antenna_id` : ID of the antenna
+ * * `hours` : how many hours of notes to evaluate (up to 24)
+ */
+export declare function backfillAntenna(antennaId: string, hours: number): Promise<number>
+
 export interface Blocking {
   id: string
   createdAt: DateTimeWithTimeZone
@@ -268,6 +305,26 @@ export type ChatEvent =  'message'|
 export type ChatIndexEvent =  'message'|
 'read';
 
+/**
+ * Loads the config file and checks the values that are syntactically valid
+ * but semantically wrong (e.g., out-of-range numbers, conflicting options).
+ *
+ * The issues are also logged using [tracing].
+ */
+export declare function checkConfig(): Array<ConfigIssue>
+
+/**
+ * Checks the status of PostgreSQL (and its replicas), Redis, the cache server and
+ * the given Redis streams.
+ *
+ * This function doesn't return an error; failures are reported in [HealthReport].
+ *
+ * # Argument
+ *
+ * * `stream_keys` : keys (without the prefix) of the Redis streams to report
+ */
+export declare function checkHealth(streamKeys: Array<string>): Promise<HealthReport>
+
 /**
  * Returns whether `note` should be hard-muted.
  *
@@ -309,14 +366,17 @@ export interface Config {
   db: DbConfig
   redis: RedisConfig
   cacheServer?: RedisConfig
+  durableStreams: boolean
   proxy?: string
   proxySmtp?: string
   proxyBypassHosts?: Array<string>
   maxFileSize: number
   accessLog?: string
   clusterLimits: WorkerConfig
+  id?: string
   cuid?: IdConfig
   outgoingAddress?: string
+  ssrf?: SsrfConfig
   deliverJobConcurrency?: number
   inboxJobConcurrency?: number
   deliverJobPerSec?: number
@@ -326,7 +386,11 @@ export interface Config {
   /** deprecated */
   logLevel?: Array<string>
   maxLogLevel?: string
+  logFilter?: string
+  logFormat?: string
+  logFile?: LogFileConfig
   syslog?: SysLogConfig
+  telemetry?: TelemetryConfig
   proxyRemoteFiles?: boolean
   mediaProxy?: string
   summalyProxyUrl?: string
@@ -352,6 +416,16 @@ export interface Config {
   userAgent: string
 }
 
+export interface ConfigIssue {
+  severity: ConfigIssueSeverity
+  /** path of the field (e.g., `db.port`) */
+  path: string
+  message: string
+}
+
+export type ConfigIssueSeverity =  'warning'|
+'error';
+
 export declare function countLocalUsers(): Promise<number>
 
 export declare function countReactions(reactions: Record<string, number>): Record<string, number>
@@ -372,7 +446,42 @@ export interface DbConfig {
   user: string
   pass: string
   disableCache?: boolean
+  /**
+   * [node-postgres](https://node-postgres.com/apis/client) options, some of which
+   * (e.g., `statement_timeout`, `max`) are also used as fallbacks by the Rust backend
+   */
   extra?: any
+  /** maximum number of connections in the pool */
+  maxConnections?: number
+  /** minimum number of idle connections kept in the pool */
+  minConnections?: number
+  /** connection timeout (in milliseconds) */
+  connectTimeout?: number
+  /** timeout for acquiring a connection from the pool (in milliseconds) */
+  acquireTimeout?: number
+  /** idle connections are closed after this duration (in milliseconds) */
+  idleTimeout?: number
+  /** connections are closed after this duration (in milliseconds) */
+  maxLifetime?: number
+  /** statement timeout (in milliseconds, `0` to disable) */
+  statementTimeout?: number
+  /** `application_name` reported to PostgreSQL */
+  applicationName?: string
+  /** `disable`, `allow`, `prefer`, `require`, `verify-ca`, or `verify-full` */
+  sslMode?: string
+  /** read-only replica servers */
+  replicas?: Array<DbReplicaConfig>
+}
+
+export interface DbReplicaConfig {
+  host: string
+  port: number
+  /** defaults to the primary database name */
+  db?: string
+  /** defaults to the primary database user */
+  user?: string
+  /** defaults to the primary database password */
+  pass?: string
 }
 
 export interface DecodedReaction {
@@ -505,6 +614,12 @@ export declare function fetchMeta(): Promise<Meta>
 /** Fetches and returns the NodeInfo (version 2.0) of a remote server. */
 export declare function fetchNodeinfo(host: string): Promise<Nodeinfo>
 
+/**
+ * Decides whether `note` should be hidden from, soft-muted for, or shown to the user,
+ * based on their muted words, instances, users, renotes, replies, and threads.
+ */
+export declare function filterNote(viewerId: string, note: Note): Promise<NoteFilterResult>
+
 export interface Following {
   id: string
   createdAt: DateTimeWithTimeZone
@@ -561,7 +676,9 @@ export declare function generateSecureRandomString(length: number): string
 export declare function generateUserToken(): string
 
 /**
- * The generated ID results in the form of `[8 chars timestamp] + [cuid2]`.
+ * Generates an ID in the configured scheme.
+ *
+ * By default, the generated ID results in the form of `[8 chars timestamp] + [cuid2]`.
  * The minimum and maximum lengths are 16 and 24, respectively.
  * With the length of 16, namely 8 for cuid2, roughly 1427399 IDs are needed
  * in the same millisecond to reach 50% chance of collision.
@@ -575,6 +692,22 @@ export declare function genIdAt(date: Date): string
 
 export declare function genIdenticon(id: string): Promise<Buffer>
 
+/**
+ * Returns the IDs of notes in the antenna timeline.
+ *
+ * Notes are sorted in descending order, unless only `since_id` is given, in which
+ * case the oldest notes after `since_id` are returned in ascending order (as is the
+ * case with other timelines). Like database queries, the cursors are exclusive.
+ *
+ * # Arguments
+ *
+ * * `antenna_id` : ID of the antenna
+ * * `limit` : maximum number of notes to return
+ * * `since_id` : return notes newer than this
+ * * `until_id` : return notes older than this
+ */
+export declare function getAntennaNoteIds(antennaId: string, limit: number, sinceId?: string | undefined | null, untilId?: string | undefined | null): Promise<Array<string>>
+
 export declare function getFullApAccount(username: string, host?: string | undefined | null): string
 
 export declare function getImageSizeFromUrl(url: string): Promise<ImageSize>
@@ -585,6 +718,10 @@ export declare function getNoteSummary(fileIds: Array<string>, text: string | un
 
 export declare function getRelayActorId(): Promise<string>
 
+/** Returns the current values of the config fields that can be changed at runtime. */
+export declare function getRuntimeConfig(): RuntimeConfig
+
+/** Returns the timestamp (Unix time in milliseconds) contained in the ID of any supported scheme. */
 export declare function getTimestamp(id: string): number
 
 /** Prints the greeting message and the Firefish version to stdout. */
@@ -610,6 +747,28 @@ export interface Hashtag {
   attachedRemoteUsersCount: number
 }
 
+/** Returns whether any antenna of the user has unread notes. */
+export declare function hasUnreadAntenna(userId: string): Promise<boolean>
+
+/** Returns whether the antenna timeline has notes that are not marked as read. */
+export declare function hasUnreadAntennaNotes(antennaId: string): Promise<boolean>
+
+export interface HealthReport {
+  /** the worst status among the components */
+  status: HealthStatus
+  postgres: PostgresHealth
+  /** read replicas of PostgreSQL */
+  replicas: Array<ReplicaHealth>
+  redis: RedisHealth
+  /** this is `None` if the cache server is not configured */
+  cacheServer: RedisHealth | null
+  streams: Array<StreamHealth>
+}
+
+export type HealthStatus =  'healthy'|
+'degraded'|
+'unhealthy';
+
 export interface Icon {
   type: Activity
   mediaType: string
@@ -683,6 +842,14 @@ export declare enum InternalEvent {
   AntennaDeleted = 11
 }
 
+/**
+ * Drops the cached mute settings of the user.
+ *
+ * This must be called after the user's mutes, renote/reply/thread mutes,
+ * muted words, or muted instances are changed.
+ */
+export declare function invalidateMuteCache(userId: string): Promise<void>
+
 /**
  * Checks if a server is allowlisted.
  * Returns `Ok(true)` if private mode is disabled.
@@ -757,6 +924,9 @@ export declare function isSilencedServer(host: string): Promise<boolean>
 
 export declare function isUnicodeEmoji(s: string): boolean
 
+/** Returns whether the string is an ID of any supported scheme. */
+export declare function isValidId(id: string): boolean
+
 /** Returns the latest Firefish version. */
 export declare function latestVersion(): Promise<string>
 
@@ -768,6 +938,27 @@ export interface LibreTranslateConfig {
 
 export declare function loadConfig(): Config
 
+export interface LogFileConfig {
+  path: string
+  /** `minutely`, `hourly`, `daily` (default), or `never` */
+  rotation?: string
+  /** maximum number of log files to keep (unlimited if not set) */
+  maxFiles?: number
+}
+
+/**
+ * Marks the notes in the antenna timeline as read.
+ *
+ * # Arguments
+ *
+ * * `antenna_id` : ID of the antenna
+ * * `until_id` : mark notes up to this note as read, or all notes if [None]
+ */
+export declare function markAntennaRead(antennaId: string, untilId?: string | undefined | null): Promise<void>
+
+/** Returns the largest possible ID (in the configured scheme) generated at the datetime. */
+export declare function maxIdAt(date: Date): string
+
 export interface Memory {
   /** Total memory amount in bytes */
   total: number
@@ -888,6 +1079,9 @@ export interface Migrations {
   name: string
 }
 
+/** Returns the smallest possible ID (in the configured scheme) generated at the datetime. */
+export declare function minIdAt(date: Date): string
+
 export interface ModerationLog {
   id: string
   createdAt: DateTimeWithTimeZone
@@ -896,6 +1090,15 @@ export interface ModerationLog {
   info: Json
 }
 
+/** Explanation of why a note is muted */
+export interface MutedMatch {
+  kind: MuteRuleKind
+  /** the muted words or pattern as configured by the user */
+  rule: string
+  /** parts of the text that matched the rule (one for each word) */
+  fragments: Array<string>
+}
+
 export interface MutedNote {
   id: string
   noteId: string
@@ -908,6 +1111,16 @@ export type MutedNoteReason =  'manual'|
 'spam'|
 'word';
 
+export type MuteReason =  'word'|
+'user'|
+'instance'|
+'renote'|
+'reply'|
+'thread';
+
+export type MuteRuleKind =  'words'|
+'pattern';
+
 export interface Muting {
   id: string
   createdAt: DateTimeWithTimeZone
@@ -1005,6 +1218,13 @@ export interface NoteFile {
   fileId: string
 }
 
+export interface NoteFilterResult {
+  verdict: NoteVerdict
+  reason: MuteReason | null
+  /** user ID, host, thread ID, or word mute rule that caused the verdict */
+  subject: string | null
+}
+
 export interface NoteLikeForIsQuote {
   renoteId: string | null
   text: string | null
@@ -1037,6 +1257,10 @@ export interface NoteUnread {
   noteChannelId: string | null
 }
 
+export type NoteVerdict =  'visible'|
+'softMuted'|
+'hidden';
+
 export type NoteVisibility =  'followers'|
 'hidden'|
 'home'|
@@ -1067,6 +1291,7 @@ export interface Notification {
   customHeader: string | null
   customIcon: string | null
   appAccessTokenId: string | null
+  noteCount: number | null
 }
 
 export type NotificationType =  'app'|
@@ -1233,6 +1458,23 @@ export interface PollVote {
   choice: number
 }
 
+export interface PoolStats {
+  /** number of connections managed by the pool */
+  connections: number
+  /** number of idle connections */
+  idleConnections: number
+}
+
+export interface PostgresHealth {
+  status: HealthStatus
+  /** round-trip time of a ping (in milliseconds) */
+  latency: number | null
+  /** maximum replay lag of the streaming replicas (in seconds) */
+  replicationLag: number | null
+  pool: PoolStats | null
+  error: string | null
+}
+
 export interface PromoNote {
   noteId: string
   expiresAt: DateTimeWithTimeZone
@@ -1275,6 +1517,13 @@ export declare function publishToGroupChatStream(groupId: string, kind: ChatEven
 
 export declare function publishToInternalStream(kind: InternalEvent, object: any): Promise<void>
 
+/**
+ * Publishes the event to the main stream of the user, which is called by the TypeScript backend.
+ *
+ * `kind` and `object` are separate arguments because napi-rs can't take [MainEvent] from
+ * JavaScript (<https://github.com/napi-rs/napi-rs/issues/2036>); the conversion checks
+ * that the object matches the kind for the typed bodies.
+ */
 export declare function publishToMainStream(userId: string, kind: Event, object: any): Promise<void>
 
 export declare function publishToModerationStream(moderatorId: string, report: AbuseUserReportLike): Promise<void>
@@ -1318,6 +1567,9 @@ export type PushSubscriptionType =  'adminReport'|
 'status'|
 'update';
 
+/** Records an HTTP request handled by the server. */
+export declare function recordHttpRequest(method: string, status: number, durationMs: number): void
+
 export interface RedisConfig {
   host: string
   port: number
@@ -1327,6 +1579,48 @@ export interface RedisConfig {
   tls?: TlsConfig
   db: number
   prefix?: string
+  /** Unix socket path (`host` and `port` are ignored if this is set) */
+  path?: string
+  /** Redis Sentinel configurations (`host` and `port` are ignored if this is set) */
+  sentinel?: RedisSentinelConfig
+  /** maximum number of connections in the pool */
+  maxConnections?: number
+  /** minimum number of idle connections kept in the pool */
+  minConnections?: number
+  /** connection timeout (in milliseconds) */
+  connectTimeout?: number
+  /** timeout for acquiring a connection from the pool (in milliseconds) */
+  acquireTimeout?: number
+  /** idle connections are closed after this duration (in milliseconds) */
+  idleTimeout?: number
+  /** connections are closed after this duration (in milliseconds) */
+  maxLifetime?: number
+}
+
+export interface RedisHealth {
+  status: HealthStatus
+  /** round-trip time of a `PING` command (in milliseconds) */
+  latency: number | null
+  /** memory used by the server (in bytes) */
+  usedMemory: number | null
+  /** this is `None` if the server is not accessed via the connection pool */
+  pool: PoolStats | null
+  error: string | null
+}
+
+export interface RedisSentinelConfig {
+  /** name of the master group */
+  name: string
+  nodes: Array<RedisSentinelNode>
+  /** username used to connect to the Sentinel nodes */
+  user?: string
+  /** password used to connect to the Sentinel nodes */
+  pass?: string
+}
+
+export interface RedisSentinelNode {
+  host: string
+  port: number
 }
 
 export interface RegistrationTicket {
@@ -1356,6 +1650,17 @@ export type RelayStatus =  'accepted'|
 'rejected'|
 'requesting';
 
+/**
+ * Re-reads the config file and applies the fields that can be changed at runtime
+ * (see [RuntimeConfig]). The config is not applied if it has semantic errors.
+ *
+ * This only affects the current process. The TypeScript backend calls this on SIGHUP
+ * and forwards the signal from the master process to the workers.
+ *
+ * Returns the paths of the changed fields that require a restart to take effect.
+ */
+export declare function reloadConfig(): Array<string>
+
 /** Delete all entries in the [attestation_challenge] table created at more than 5 minutes ago */
 export declare function removeOldAttestationChallenges(): Promise<void>
 
@@ -1377,6 +1682,9 @@ export declare function renderLike(reaction: Model): Promise<ApLike>
 
 export declare function renderMention(user: UserLike): ApMention
 
+/** Returns the metrics in the Prometheus text format. */
+export declare function renderMetrics(): string
+
 export declare function renderRead(userId: string, messageUri: string): ApRead
 
 export declare function renderReject(userId: string, followObject: ApFollow): ApReject
@@ -1392,6 +1700,44 @@ export interface RenoteMuting {
   muterId: string
 }
 
+/**
+ * Returns the messages published to the channel in the durable mode after the given
+ * stream entry.
+ *
+ * Only the main, chat, and chat index streams of the viewer and the chat streams of
+ * the groups that the viewer has joined can be replayed.
+ *
+ * A client should subscribe to the channel before calling this, and ignore the messages
+ * received with IDs up to the ID of the last event returned.
+ *
+ * # Arguments
+ *
+ * * `viewer_id` : ID of the user who replays the channel
+ * * `channel` : name of the channel (e.g., `mainStream:9tr87i03euwphnsw`)
+ * * `since_id` : ID of the last stream entry received by the client
+ * * `limit` : maximum number of messages to return (up to 100)
+ */
+export declare function replayStream(viewerId: string, channel: string, sinceId: string, limit: number): Promise<StreamReplay>
+
+export interface ReplicaHealth {
+  /** `host:port` of the replica server */
+  name: string
+  /**
+   * this is at most [HealthStatus::Degraded], as the primary server is used
+   * while the replica is unavailable
+   */
+  status: HealthStatus
+  /** round-trip time of a ping (in milliseconds) */
+  latency: number | null
+  /**
+   * time since the last replayed transaction (in seconds), which is zero if the
+   * replica has replayed everything it has received
+   */
+  replicationLag: number | null
+  pool: PoolStats | null
+  error: string | null
+}
+
 export interface ReplyMuting {
   id: string
   createdAt: DateTimeWithTimeZone
@@ -1399,6 +1745,23 @@ export interface ReplyMuting {
   muterId: string
 }
 
+/** Counts a backfill requested by the user and returns whether it is within the rate limit. */
+export declare function reserveAntennaBackfill(userId: string): Promise<boolean>
+
+/**
+ * Config fields that can be changed without restarting the server
+ *
+ * The job concurrency (`deliverJobConcurrency` and `inboxJobConcurrency`) is not
+ * included, as it is passed to the queue workers when they start and can't be changed
+ * afterwards. Changing it is reported as requiring a restart.
+ */
+export interface RuntimeConfig {
+  maxLogLevel?: string
+  logFilter?: string
+  proxyBypassHosts?: Array<string>
+  reservedUsernames?: Array<string>
+}
+
 /** Returns `true` if `src` does not contain suspicious characters like `%`. */
 export declare function safeForSql(src: string): boolean
 
@@ -1416,6 +1779,11 @@ export interface ServerConfig {
   redis: RedisConfig
   /** secondary Redis server configurations */
   cacheServer?: RedisConfig
+  /**
+   * keep recent events of the main and chat streams in Redis for 24 hours, so that
+   * reconnecting clients can catch up with them (default: false)
+   */
+  durableStreams?: boolean
   /** proxy host used for HTTP requests */
   proxy?: string
   /** proxy host used for SMTP requests */
@@ -1426,8 +1794,12 @@ export interface ServerConfig {
   maxFileSize?: number
   accessLog?: string
   clusterLimits?: WorkerConfigInternal
+  /** scheme of new IDs. `cuid` (default), `aid`, `aidx`, `meid`, `ulid`, or `objectid` */
+  id?: string
   cuid?: IdConfig
   outgoingAddress?: string
+  /** destinations that outgoing requests to remote servers may reach */
+  ssrf?: SsrfConfig
   deliverJobConcurrency?: number
   inboxJobConcurrency?: number
   deliverJobPerSec?: number
@@ -1436,9 +1808,20 @@ export interface ServerConfig {
   inboxJobMaxAttempts?: number
   /** deprecated in favor of `max_log_level` */
   logLevel?: Array<string>
-  /** verbosity of the server log. `error`, `warn`, `info`, `debug`, or `trace` */
+  /** verbosity of the server log. `error`, `warning`, `info`, `debug`, or `trace` */
   maxLogLevel?: string
+  /**
+   * per-module log levels in the `RUST_LOG` syntax (e.g., `backend_rs::federation=debug,sea_orm=warn`).
+   * these take precedence over `max_log_level`, and `RUST_LOG` takes precedence over these
+   */
+  logFilter?: string
+  /** format of the server log. `compact` (default), `pretty`, or `json` */
+  logFormat?: string
+  /** file to write the server log to */
+  logFile?: LogFileConfig
   syslog?: SysLogConfig
+  /** OpenTelemetry traces and Prometheus metrics */
+  telemetry?: TelemetryConfig
   proxyRemoteFiles?: boolean
   mediaProxy?: string
   summalyProxyUrl?: string
@@ -1461,11 +1844,17 @@ export interface Services {
   outbound: Array<Outbound>
 }
 
+/** Records the number of jobs in the `state` (e.g., `waiting`, `active`) of the job queue. */
+export declare function setQueueJobs(queue: string, state: string, count: number): void
+
 export declare function shouldNyaify(readerUserId: string): Promise<boolean>
 
 /** Prints the server hardware information as the server info log. */
 export declare function showServerInfo(): void
 
+/** Exports the remaining spans and stops the trace exporter. */
+export declare function shutdownTelemetry(): void
+
 export interface Signin {
   id: string
   createdAt: DateTimeWithTimeZone
@@ -1488,6 +1877,15 @@ export declare function sqlLikeEscape(src: string): string
 
 export declare function sqlRegexEscape(src: string): string
 
+export interface SsrfConfig {
+  /** networks (in CIDR notation) that are allowed even if they are private (e.g., `10.1.0.0/16`) */
+  allowedNetworks?: Array<string>
+  /** networks (in CIDR notation) to reject in addition to the private and reserved ones */
+  deniedNetworks?: Array<string>
+  /** ports that may be accessed. any port is allowed if not set */
+  allowedPorts?: Array<number>
+}
+
 export interface Storage {
   /** Total storage space in bytes */
   total: number
@@ -1497,6 +1895,33 @@ export interface Storage {
 
 export declare function storageUsage(): Storage | null
 
+/** Message in an event log */
+export interface StreamEvent {
+  /** ID of the stream entry */
+  id: string
+  /** message published to the channel */
+  message: any
+}
+
+export interface StreamHealth {
+  /** stream key (without the prefix) */
+  key: string
+  /** number of entries in the stream */
+  length: number
+  /** number of entries delivered to consumer groups but not yet acknowledged */
+  pending: number
+}
+
+export interface StreamReplay {
+  /** messages published after the given entry in ascending order */
+  events: Array<StreamEvent>
+  /**
+   * whether some messages after the given entry have already been removed from
+   * the event log, in which case the client should reload everything
+   */
+  missed: boolean
+}
+
 export declare function stringToAcct(acct: string): Acct
 
 export interface SwSubscription {
@@ -1514,6 +1939,30 @@ export interface SwSubscription {
 export interface SysLogConfig {
   host: string
   port: number
+  /** `udp` (default) or `tcp` */
+  protocol?: string
+  /** syslog facility (e.g., `daemon`, `local0`). defaults to `user` */
+  facility?: string
+}
+
+export interface TelemetryConfig {
+  /**
+   * OTLP/HTTP endpoint to export traces to (e.g., `http://localhost:4318/v1/traces`).
+   * traces are not exported if not set
+   */
+  otlpEndpoint?: string
+  /** service name reported to the collector. defaults to `firefish` */
+  serviceName?: string
+  /** ratio of the traces to export (0.0 to 1.0). defaults to 1.0 */
+  sampleRatio?: number
+  /**
+   * serve Prometheus metrics of each worker process at `/metrics` on port
+   * `metrics_port + i`, where `i` is the index of the worker (starting from 0).
+   * metrics are not served if not set
+   */
+  metricsPort?: number
+  /** address to serve metrics on. defaults to `127.0.0.1` */
+  metricsBind?: string
 }
 
 export interface TlsConfig {
@@ -1521,6 +1970,12 @@ export interface TlsConfig {
   rejectUnauthorized: boolean
 }
 
+/**
+ * Takes the notes batched since the last digest and returns a notification to each
+ * antenna owner about them, which is supposed to be called every hour.
+ */
+export declare function takeAntennaDigests(): Promise<Array<AntennaNotification>>
+
 export declare function toDbReaction(reaction?: string | undefined | null, host?: string | undefined | null): Promise<string>
 
 export declare function toPuny(host: string): string
@@ -1536,7 +1991,11 @@ export declare function unwatchNote(watcherId: string, noteId: string): Promise<
 
 export declare function updateAntennaCache(): Promise<void>
 
-export declare function updateAntennasOnNewNote(note: Note, noteAuthor: Acct, noteMutedUsers: Array<string>): Promise<void>
+/**
+ * Adds the note to the antennas that accept it, and returns the notifications to create
+ * for the owners of the antennas with `notify` enabled.
+ */
+export declare function updateAntennasOnNewNote(note: Note, noteAuthor: Acct, noteMutedUsers: Array<string>): Promise<Array<AntennaNotification>>
 
 export declare function updateMetaCache(): Promise<void>
 
@@ -1766,6 +2225,21 @@ export interface UserSecurityKey {
   name: string
 }
 
+/**
+ * Checks whether `expression` is a valid antenna expression.
+ *
+ * The error message contains the position (in characters) of the problem.
+ */
+export declare function validateAntennaExpression(expression: string): void
+
+/**
+ * Checks whether `pattern` can be used as a muted pattern (e.g., `/foo/i`).
+ *
+ * JavaScript regular expressions are supported except for lookaround assertions
+ * and backreferences.
+ */
+export declare function validateMutedPattern(pattern: string): void
+
 /** Checks whether the given password and hash match. */
 export declare function verifyPassword(password: string, hash: string): boolean
 
@@ -1784,6 +2258,12 @@ export interface Webhook {
   latestStatus: number | null
 }
 
+/**
+ * Returns the rule that makes `note` hard-muted and the matched parts of the note,
+ * or [None] if the note is not muted (see [check_word_mute]).
+ */
+export declare function whyMuted(note: PartialNoteToCheckWordMute, mutedWords: Array<string>, mutedPatterns: Array<string>): Promise<MutedMatch | null>
+
 export interface WorkerConfig {
   web: number
   queue: number
@@ -367,9 +367,13 @@ if (!nativeBinding) {
 module.exports.acctToString = nativeBinding.acctToString
 module.exports.Activity = nativeBinding.Activity
 module.exports.AntennaSrc = nativeBinding.AntennaSrc
+module.exports.backfillAntenna = nativeBinding.backfillAntenna
 module.exports.ChatEvent = nativeBinding.ChatEvent
 module.exports.ChatIndexEvent = nativeBinding.ChatIndexEvent
+module.exports.checkConfig = nativeBinding.checkConfig
+module.exports.checkHealth = nativeBinding.checkHealth
 module.exports.checkWordMute = nativeBinding.checkWordMute
+module.exports.ConfigIssueSeverity = nativeBinding.ConfigIssueSeverity
 module.exports.countLocalUsers = nativeBinding.countLocalUsers
 module.exports.countReactions = nativeBinding.countReactions
 module.exports.cpuInfo = nativeBinding.cpuInfo
@@ -382,23 +386,30 @@ module.exports.Event = nativeBinding.Event
 module.exports.extractHost = nativeBinding.extractHost
 module.exports.fetchMeta = nativeBinding.fetchMeta
 module.exports.fetchNodeinfo = nativeBinding.fetchNodeinfo
+module.exports.filterNote = nativeBinding.filterNote
 module.exports.formatMilliseconds = nativeBinding.formatMilliseconds
 module.exports.generateSecureRandomString = nativeBinding.generateSecureRandomString
 module.exports.generateUserToken = nativeBinding.generateUserToken
 module.exports.genId = nativeBinding.genId
 module.exports.genIdAt = nativeBinding.genIdAt
 module.exports.genIdenticon = nativeBinding.genIdenticon
+module.exports.getAntennaNoteIds = nativeBinding.getAntennaNoteIds
 module.exports.getFullApAccount = nativeBinding.getFullApAccount
 module.exports.getImageSizeFromUrl = nativeBinding.getImageSizeFromUrl
 module.exports.getInstanceActor = nativeBinding.getInstanceActor
 module.exports.getNoteSummary = nativeBinding.getNoteSummary
 module.exports.getRelayActorId = nativeBinding.getRelayActorId
+module.exports.getRuntimeConfig = nativeBinding.getRuntimeConfig
 module.exports.getTimestamp = nativeBinding.getTimestamp
 module.exports.greet = nativeBinding.greet
 module.exports.hashPassword = nativeBinding.hashPassword
+module.exports.hasUnreadAntenna = nativeBinding.hasUnreadAntenna
+module.exports.hasUnreadAntennaNotes = nativeBinding.hasUnreadAntennaNotes
+module.exports.HealthStatus = nativeBinding.HealthStatus
 module.exports.Inbound = nativeBinding.Inbound
 module.exports.initializeRustLogger = nativeBinding.initializeRustLogger
 module.exports.InternalEvent = nativeBinding.InternalEvent
+module.exports.invalidateMuteCache = nativeBinding.invalidateMuteCache
 module.exports.isAllowedServer = nativeBinding.isAllowedServer
 module.exports.isBlockedServer = nativeBinding.isBlockedServer
 module.exports.isOldPasswordAlgorithm = nativeBinding.isOldPasswordAlgorithm
@@ -408,14 +419,21 @@ module.exports.isSameOrigin = nativeBinding.isSameOrigin
 module.exports.isSelfHost = nativeBinding.isSelfHost
 module.exports.isSilencedServer = nativeBinding.isSilencedServer
 module.exports.isUnicodeEmoji = nativeBinding.isUnicodeEmoji
+module.exports.isValidId = nativeBinding.isValidId
 module.exports.latestVersion = nativeBinding.latestVersion
 module.exports.loadConfig = nativeBinding.loadConfig
+module.exports.markAntennaRead = nativeBinding.markAntennaRead
+module.exports.maxIdAt = nativeBinding.maxIdAt
 module.exports.memoryUsage = nativeBinding.memoryUsage
 module.exports.metaToPugArgs = nativeBinding.metaToPugArgs
+module.exports.minIdAt = nativeBinding.minIdAt
 module.exports.MutedNoteReason = nativeBinding.MutedNoteReason
+module.exports.MuteReason = nativeBinding.MuteReason
+module.exports.MuteRuleKind = nativeBinding.MuteRuleKind
 module.exports.nodeinfo_2_0 = nativeBinding.nodeinfo_2_0
 module.exports.nodeinfo_2_1 = nativeBinding.nodeinfo_2_1
 module.exports.NoteEvent = nativeBinding.NoteEvent
+module.exports.NoteVerdict = nativeBinding.NoteVerdict
 module.exports.NoteVisibility = nativeBinding.NoteVisibility
 module.exports.NotificationType = nativeBinding.NotificationType
 module.exports.nyaify = nativeBinding.nyaify
@@ -439,7 +457,9 @@ module.exports.publishToNoteUpdatesStream = nativeBinding.publishToNoteUpdatesSt
 module.exports.publishToUserStream = nativeBinding.publishToUserStream
 module.exports.PushNotificationKind = nativeBinding.PushNotificationKind
 module.exports.PushSubscriptionType = nativeBinding.PushSubscriptionType
+module.exports.recordHttpRequest = nativeBinding.recordHttpRequest
 module.exports.RelayStatus = nativeBinding.RelayStatus
+module.exports.reloadConfig = nativeBinding.reloadConfig
 module.exports.removeOldAttestationChallenges = nativeBinding.removeOldAttestationChallenges
 module.exports.renderAccept = nativeBinding.renderAccept
 module.exports.renderAdd = nativeBinding.renderAdd
@@ -450,18 +470,24 @@ module.exports.renderFollowRelay = nativeBinding.renderFollowRelay
 module.exports.renderHashtag = nativeBinding.renderHashtag
 module.exports.renderLike = nativeBinding.renderLike
 module.exports.renderMention = nativeBinding.renderMention
+module.exports.renderMetrics = nativeBinding.renderMetrics
 module.exports.renderRead = nativeBinding.renderRead
 module.exports.renderReject = nativeBinding.renderReject
 module.exports.renderRemove = nativeBinding.renderRemove
 module.exports.renderTombstone = nativeBinding.renderTombstone
+module.exports.replayStream = nativeBinding.replayStream
+module.exports.reserveAntennaBackfill = nativeBinding.reserveAntennaBackfill
 module.exports.safeForSql = nativeBinding.safeForSql
 module.exports.sendPushNotification = nativeBinding.sendPushNotification
+module.exports.setQueueJobs = nativeBinding.setQueueJobs
 module.exports.shouldNyaify = nativeBinding.shouldNyaify
 module.exports.showServerInfo = nativeBinding.showServerInfo
+module.exports.shutdownTelemetry = nativeBinding.shutdownTelemetry
 module.exports.sqlLikeEscape = nativeBinding.sqlLikeEscape
 module.exports.sqlRegexEscape = nativeBinding.sqlRegexEscape
 module.exports.storageUsage = nativeBinding.storageUsage
 module.exports.stringToAcct = nativeBinding.stringToAcct
+module.exports.takeAntennaDigests = nativeBinding.takeAntennaDigests
 module.exports.toDbReaction = nativeBinding.toDbReaction
 module.exports.toPuny = nativeBinding.toPuny
 module.exports.translate = nativeBinding.translate
@@ -473,5 +499,8 @@ module.exports.UserEmojiModPerm = nativeBinding.UserEmojiModPerm
 module.exports.UserEvent = nativeBinding.UserEvent
 module.exports.UserProfileFfvisibility = nativeBinding.UserProfileFfvisibility
 module.exports.UserProfileMutingNotificationTypes = nativeBinding.UserProfileMutingNotificationTypes
+module.exports.validateAntennaExpression = nativeBinding.validateAntennaExpression
+module.exports.validateMutedPattern = nativeBinding.validateMutedPattern
 module.exports.verifyPassword = nativeBinding.verifyPassword
 module.exports.watchNote = nativeBinding.watchNote
+module.exports.whyMuted = nativeBinding.whyMuted
@@ -1,5 +1,6 @@
 //! In-memory cache handler
 
+use crate::util::metrics;
 use chrono::{DateTime, Duration, Utc};
 use std::sync::Mutex;
 
@@ -116,9 +117,12 @@ impl<T: Clone> Cache<T> {
 
         if let Some(ttl) = self.valid_duration {
             if data.last_updated + ttl < Utc::now() {
+                metrics::count_cache_request("memory", false);
                 return None;
             }
         }
+        metrics::count_cache_request("memory", data.value.is_some());
+
         data.value.to_owned()
     }
 }
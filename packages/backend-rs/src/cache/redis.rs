@@ -1,6 +1,9 @@
 //! Utilities for using Redis cache
 
-use crate::database::{redis_conn, redis_key, RedisConnError};
+use crate::{
+    database::{redis_conn, redis_key, RedisConnError},
+    util::metrics,
+};
 use chrono::Duration;
 use redis::{AsyncCommands, RedisError};
 use serde::{Deserialize, Serialize};
@@ -12,6 +15,7 @@ pub enum Category {
     Follow,
     CatLang,
     RandomIcon,
+    Mute,
     #[cfg(test)]
     Test,
 }
@@ -40,6 +44,7 @@ fn categorize(category: Category, key: &str) -> String {
         Category::Follow => "following",
         Category::CatLang => "catlang",
         Category::RandomIcon => "randomIcon",
+        Category::Mute => "muting",
         #[cfg(test)]
         Category::Test => "usedOnlyForTesting",
     };
@@ -130,10 +135,13 @@ pub async fn set<V: for<'a> Deserialize<'a> + Serialize>(
 /// ```
 pub async fn get<V: for<'a> Deserialize<'a> + Serialize>(key: &str) -> Result<Option<V>, Error> {
     let serialized_value: Option<Vec<u8>> = redis_conn().await?.get(prefix_key(key)).await?;
-    Ok(match serialized_value {
+    let value = match serialized_value {
         Some(v) => rmp_serde::from_slice::<V>(v.as_ref()).ok(),
         None => None,
-    })
+    };
+    metrics::count_cache_request("redis", value.is_some());
+
+    Ok(value)
 }
 
 /// Deletes a Redis cache.
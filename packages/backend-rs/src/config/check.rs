@@ -0,0 +1,756 @@
+//! Semantic validation of the server configuration
+
+use super::server::{read_config_file, ConfigError, RedisConfig, ServerConfig};
+use crate::misc::ssrf_policy::parse_network;
+use serde::Serialize;
+
+const LOG_LEVELS: [&str; 5] = ["error", "warning", "info", "debug", "trace"];
+const LOG_FORMATS: [&str; 3] = ["compact", "pretty", "json"];
+const LOG_ROTATIONS: [&str; 4] = ["minutely", "hourly", "daily", "never"];
+const SYSLOG_PROTOCOLS: [&str; 2] = ["udp", "tcp"];
+const SYSLOG_FACILITIES: [&str; 20] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv",
+    "ftp", "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7",
+];
+const ID_SCHEMES: [&str; 6] = ["cuid", "aid", "aidx", "meid", "ulid", "objectid"];
+const SSL_MODES: [&str; 6] = [
+    "disable",
+    "allow",
+    "prefer",
+    "require",
+    "verify-ca",
+    "verify-full",
+];
+const PROXY_SCHEMES: [&str; 6] = ["http", "https", "socks4", "socks4a", "socks5", "socks5h"];
+const SMTP_PROXY_SCHEMES: [&str; 3] = ["http", "socks4", "socks5"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(string_enum = "camelCase")]
+pub enum ConfigIssueSeverity {
+    /// the server works, but probably not as intended
+    Warning,
+    /// the server doesn't work properly
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object)]
+pub struct ConfigIssue {
+    pub severity: ConfigIssueSeverity,
+    /// path of the field (e.g., `db.port`)
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            ConfigIssueSeverity::Warning => "warning",
+            ConfigIssueSeverity::Error => "error",
+        };
+        write!(f, "{} ({}): {}", self.path, severity, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Issues(Vec<ConfigIssue>);
+
+impl Issues {
+    fn push(&mut self, severity: ConfigIssueSeverity, path: &str, message: String) {
+        self.0.push(ConfigIssue {
+            severity,
+            path: path.to_owned(),
+            message,
+        });
+    }
+
+    fn warn(&mut self, path: &str, message: impl Into<String>) {
+        self.push(ConfigIssueSeverity::Warning, path, message.into());
+    }
+
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.push(ConfigIssueSeverity::Error, path, message.into());
+    }
+
+    fn check_port(&mut self, path: &str, port: u16) {
+        if port == 0 {
+            self.error(path, "port must be between 1 and 65535");
+        }
+    }
+
+    fn check_positive(&mut self, path: &str, value: Option<u32>) {
+        if value == Some(0) {
+            self.error(path, "must be greater than 0");
+        }
+    }
+
+    fn check_url(&mut self, path: &str, value: Option<&str>, schemes: &[&str]) {
+        let Some(value) = value else {
+            return;
+        };
+
+        match url::Url::parse(value) {
+            Ok(url) if !schemes.contains(&url.scheme()) => self.error(
+                path,
+                format!(
+                    "unsupported scheme `{}` (expected one of {})",
+                    url.scheme(),
+                    list(schemes)
+                ),
+            ),
+            Ok(url) if url.host_str().is_none() => self.error(path, "hostname is missing"),
+            Ok(_) => {}
+            Err(err) => self.error(path, format!("invalid URL ({})", err)),
+        }
+    }
+
+    fn check_required(&mut self, path: &str, value: Option<&str>, reason: &str) {
+        if value.map_or(true, str::is_empty) {
+            self.error(path, format!("this field is required {}", reason));
+        }
+    }
+
+    fn check_pool(&mut self, path: &str, max: Option<u32>, min: Option<u32>) {
+        self.check_positive(&format!("{}.maxConnections", path), max);
+
+        if let (Some(max), Some(min)) = (max, min) {
+            if min > max {
+                self.error(
+                    &format!("{}.minConnections", path),
+                    format!("must not exceed `{}.maxConnections` ({})", path, max),
+                );
+            }
+        }
+    }
+
+    fn check_redis(&mut self, path: &str, config: &RedisConfig) {
+        self.check_port(&format!("{}.port", path), config.port);
+
+        if let Some(family) = config.family {
+            if ![0, 4, 6].contains(&family) {
+                self.error(
+                    &format!("{}.family", path),
+                    format!("unknown IP address family {} (expected 0, 4 or 6)", family),
+                );
+            }
+        }
+
+        if config.path.is_some() && config.sentinel.is_some() {
+            self.error(
+                &format!("{}.sentinel", path),
+                format!("`{0}.path` and `{0}.sentinel` are mutually exclusive", path),
+            );
+        }
+        if config.path.is_some() && config.tls.is_some() {
+            self.warn(
+                &format!("{}.tls", path),
+                "TLS is not used for Unix socket connections",
+            );
+        }
+
+        if let Some(sentinel) = &config.sentinel {
+            if sentinel.nodes.is_empty() {
+                self.error(
+                    &format!("{}.sentinel.nodes", path),
+                    "at least one Sentinel node is required",
+                );
+            }
+            for (i, node) in sentinel.nodes.iter().enumerate() {
+                self.check_port(&format!("{}.sentinel.nodes[{}].port", path, i), node.port);
+            }
+        }
+
+        self.check_pool(path, config.max_connections, config.min_connections);
+    }
+}
+
+fn list(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|value| format!("`{}`", value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Checks the values that are syntactically valid but semantically wrong.
+pub(super) fn validate(config: &ServerConfig) -> Vec<ConfigIssue> {
+    let mut issues = Issues::default();
+
+    // server
+    issues.check_url("url", Some(&config.url), &["http", "https"]);
+    if let Ok(url) = url::Url::parse(&config.url) {
+        if url.path() != "/" || url.query().is_some() || url.fragment().is_some() {
+            issues.warn(
+                "url",
+                format!(
+                    "path, query and fragment are ignored (`{}` is used)",
+                    url.origin().ascii_serialization()
+                ),
+            );
+        }
+    }
+    issues.check_port("port", config.port);
+
+    // database
+    issues.check_port("db.port", config.db.port);
+    issues.check_pool("db", config.db.max_connections, config.db.min_connections);
+    issues.check_positive("db.connectTimeout", config.db.connect_timeout);
+    issues.check_positive("db.acquireTimeout", config.db.acquire_timeout);
+    if let Some(ssl_mode) = &config.db.ssl_mode {
+        if !SSL_MODES.contains(&ssl_mode.as_str()) {
+            issues.error(
+                "db.sslMode",
+                format!(
+                    "unknown SSL mode `{}` (expected one of {})",
+                    ssl_mode,
+                    list(&SSL_MODES)
+                ),
+            );
+        }
+    }
+    for (i, replica) in config.db.replicas.iter().flatten().enumerate() {
+        issues.check_port(&format!("db.replicas[{}].port", i), replica.port);
+    }
+
+    // Redis
+    issues.check_redis("redis", &config.redis);
+    if let Some(cache_server) = &config.cache_server {
+        issues.check_redis("cacheServer", cache_server);
+    }
+
+    // proxies
+    issues.check_url("proxy", config.proxy.as_deref(), &PROXY_SCHEMES);
+    issues.check_url(
+        "proxySmtp",
+        config.proxy_smtp.as_deref(),
+        &SMTP_PROXY_SCHEMES,
+    );
+    for (i, host) in config.proxy_bypass_hosts.iter().flatten().enumerate() {
+        let path = format!("proxyBypassHosts[{}]", i);
+        if host.trim().is_empty() {
+            issues.error(&path, "empty host");
+        } else if host.contains('/') {
+            issues.warn(&path, "must be a hostname or an IP address, not a URL");
+        }
+    }
+    if config.proxy.is_none() && config.proxy_bypass_hosts.is_some() {
+        issues.warn(
+            "proxyBypassHosts",
+            "this is ignored because `proxy` is not set",
+        );
+    }
+    issues.check_url(
+        "mediaProxy",
+        config.media_proxy.as_deref(),
+        &["http", "https"],
+    );
+    issues.check_url(
+        "summalyProxyUrl",
+        config.summaly_proxy_url.as_deref(),
+        &["http", "https"],
+    );
+
+    // limits
+    if config.max_file_size.is_some_and(|size| size <= 0) {
+        issues.error("maxFileSize", "must be greater than 0");
+    }
+    issues.check_positive("maxNoteLength", config.max_note_length);
+    if config
+        .max_note_length
+        .is_some_and(|length| length > 100_000)
+    {
+        issues.warn(
+            "maxNoteLength",
+            "values greater than 100000 are not supported",
+        );
+    }
+    issues.check_positive("maxCaptionLength", config.max_caption_length);
+    if config
+        .max_caption_length
+        .is_some_and(|length| length > 8192)
+    {
+        issues.warn(
+            "maxCaptionLength",
+            "values greater than 8192 are not supported",
+        );
+    }
+    if let Some(cluster_limits) = &config.cluster_limits {
+        issues.check_positive("clusterLimits.web", cluster_limits.web);
+        issues.check_positive("clusterLimits.queue", cluster_limits.queue);
+    }
+    if let Some(id) = &config.id {
+        if !ID_SCHEMES.contains(&id.as_str()) {
+            issues.error(
+                "id",
+                format!("must be one of {} (got `{}`)", list(&ID_SCHEMES), id),
+            );
+        } else if id != "cuid" && config.cuid.is_some() {
+            issues.warn("cuid", "this is ignored because `id` is not `cuid`");
+        }
+    }
+    if let Some(length) = config.cuid.as_ref().and_then(|cuid| cuid.length) {
+        if !(16..=24).contains(&length) {
+            issues.warn(
+                "cuid.length",
+                format!(
+                    "must be between 16 and 24 (clamped to {})",
+                    length.clamp(16, 24)
+                ),
+            );
+        }
+    }
+
+    // jobs
+    issues.check_positive("deliverJobConcurrency", config.deliver_job_concurrency);
+    issues.check_positive("inboxJobConcurrency", config.inbox_job_concurrency);
+    issues.check_positive("deliverJobPerSec", config.deliver_job_per_sec);
+    issues.check_positive("inboxJobPerSec", config.inbox_job_per_sec);
+    issues.check_positive("deliverJobMaxAttempts", config.deliver_job_max_attempts);
+    issues.check_positive("inboxJobMaxAttempts", config.inbox_job_max_attempts);
+
+    // logging
+    if let Some(max_log_level) = &config.max_log_level {
+        if !LOG_LEVELS.contains(&max_log_level.as_str()) && max_log_level != "warn" {
+            issues.error(
+                "maxLogLevel",
+                format!(
+                    "unknown log level `{}` (expected one of {}; falls back to `info`)",
+                    max_log_level,
+                    list(&LOG_LEVELS)
+                ),
+            );
+        }
+    }
+    if config.log_level.is_some() {
+        match config.max_log_level {
+            Some(_) => issues.warn(
+                "logLevel",
+                "this is ignored because `maxLogLevel` is set (deprecated)",
+            ),
+            None => issues.warn("logLevel", "deprecated in favor of `maxLogLevel`"),
+        }
+    }
+    if let Some(log_filter) = &config.log_filter {
+        for directive in log_filter.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+            if let Err(err) = directive.parse::<tracing_subscriber::filter::Directive>() {
+                issues.warn(
+                    "logFilter",
+                    format!("invalid directive `{}` is ignored ({})", directive, err),
+                );
+            }
+        }
+    }
+    if let Some(log_format) = &config.log_format {
+        if !LOG_FORMATS.contains(&log_format.as_str()) {
+            issues.error(
+                "logFormat",
+                format!(
+                    "unknown log format `{}` (expected one of {}; falls back to `compact`)",
+                    log_format,
+                    list(&LOG_FORMATS)
+                ),
+            );
+        }
+    }
+    if let Some(log_file) = &config.log_file {
+        if log_file.path.is_empty() {
+            issues.error("logFile.path", "must not be empty");
+        }
+        if let Some(rotation) = &log_file.rotation {
+            if !LOG_ROTATIONS.contains(&rotation.as_str()) {
+                issues.error(
+                    "logFile.rotation",
+                    format!(
+                        "unknown rotation `{}` (expected one of {})",
+                        rotation,
+                        list(&LOG_ROTATIONS)
+                    ),
+                );
+            }
+        }
+        issues.check_positive("logFile.maxFiles", log_file.max_files);
+    }
+    if let Some(syslog) = &config.syslog {
+        issues.check_port("syslog.port", syslog.port);
+        if let Some(protocol) = &syslog.protocol {
+            if !SYSLOG_PROTOCOLS.contains(&protocol.as_str()) {
+                issues.error(
+                    "syslog.protocol",
+                    format!(
+                        "unknown protocol `{}` (expected one of {})",
+                        protocol,
+                        list(&SYSLOG_PROTOCOLS)
+                    ),
+                );
+            }
+        }
+        if let Some(facility) = &syslog.facility {
+            if !SYSLOG_FACILITIES.contains(&facility.as_str()) {
+                issues.error(
+                    "syslog.facility",
+                    format!("unknown facility `{}`", facility),
+                );
+            }
+        }
+    }
+
+    // telemetry
+    if let Some(telemetry) = &config.telemetry {
+        issues.check_url(
+            "telemetry.otlpEndpoint",
+            telemetry.otlp_endpoint.as_deref(),
+            &["http"],
+        );
+        if let Some(ratio) = telemetry.sample_ratio {
+            if !(0.0..=1.0).contains(&ratio) {
+                issues.error("telemetry.sampleRatio", "must be between 0.0 and 1.0");
+            }
+        }
+        if telemetry.otlp_endpoint.is_none() && telemetry.sample_ratio.is_some() {
+            issues.warn(
+                "telemetry.sampleRatio",
+                "this is ignored because `telemetry.otlpEndpoint` is not set",
+            );
+        }
+        match telemetry.metrics_port {
+            Some(port) => {
+                issues.check_port("telemetry.metricsPort", port);
+
+                // each worker uses its own port
+                let workers = config.cluster_limits.as_ref().map_or(2, |limits| {
+                    limits.web.unwrap_or(1) + limits.queue.unwrap_or(1)
+                });
+                if (u32::from(port)..u32::from(port) + workers).contains(&config.port.into()) {
+                    issues.error(
+                        "telemetry.metricsPort",
+                        format!(
+                            "ports {}-{} are used by the worker processes, which include `port`",
+                            port,
+                            u32::from(port) + workers - 1
+                        ),
+                    );
+                }
+            }
+            None if telemetry.metrics_bind.is_some() => issues.warn(
+                "telemetry.metricsBind",
+                "this is ignored because `telemetry.metricsPort` is not set",
+            ),
+            None => {}
+        }
+    }
+
+    // SSRF policy
+    if let Some(ssrf) = &config.ssrf {
+        for (path, networks) in [
+            ("ssrf.allowedNetworks", &ssrf.allowed_networks),
+            ("ssrf.deniedNetworks", &ssrf.denied_networks),
+        ] {
+            for network in networks.iter().flatten() {
+                if let Err(err) = parse_network(network) {
+                    issues.error(path, err.to_string());
+                }
+            }
+        }
+        if ssrf
+            .allowed_ports
+            .as_ref()
+            .is_some_and(|ports| ports.is_empty())
+        {
+            issues.error(
+                "ssrf.allowedPorts",
+                "no port is allowed, so all outgoing requests will fail",
+            );
+        }
+    }
+
+    // managed hosting
+    let deepl_managed = config
+        .deepl
+        .as_ref()
+        .is_some_and(|deepl| deepl.managed == Some(true));
+    let libre_translate_managed = config
+        .libre_translate
+        .as_ref()
+        .is_some_and(|libre_translate| libre_translate.managed == Some(true));
+    let email_managed = config
+        .email
+        .as_ref()
+        .is_some_and(|email| email.managed == Some(true));
+    let object_storage_managed = config
+        .object_storage
+        .as_ref()
+        .is_some_and(|object_storage| object_storage.managed == Some(true));
+
+    if config.is_managed_hosting != Some(true)
+        && (deepl_managed || libre_translate_managed || email_managed || object_storage_managed)
+    {
+        issues.warn(
+            "isManagedHosting",
+            "managed settings are ignored unless this is set to `true`",
+        );
+    }
+
+    if deepl_managed && libre_translate_managed {
+        issues.error(
+            "libreTranslate.managed",
+            "`deepl.managed` and `libreTranslate.managed` are mutually exclusive",
+        );
+    }
+
+    let reason = "when this setting is managed";
+
+    if let Some(deepl) = config.deepl.as_ref().filter(|_| deepl_managed) {
+        issues.check_required("deepl.authKey", deepl.auth_key.as_deref(), reason);
+    }
+    if let Some(libre_translate) = config
+        .libre_translate
+        .as_ref()
+        .filter(|_| libre_translate_managed)
+    {
+        issues.check_required(
+            "libreTranslate.apiUrl",
+            libre_translate.api_url.as_deref(),
+            reason,
+        );
+        issues.check_url(
+            "libreTranslate.apiUrl",
+            libre_translate
+                .api_url
+                .as_deref()
+                .filter(|url| !url.is_empty()),
+            &["http", "https"],
+        );
+    }
+    if let Some(email) = config.email.as_ref().filter(|_| email_managed) {
+        issues.check_required("email.address", email.address.as_deref(), reason);
+        issues.check_required("email.host", email.host.as_deref(), reason);
+        match email.port {
+            Some(port) => issues.check_port("email.port", port),
+            None => issues.error("email.port", format!("this field is required {}", reason)),
+        }
+    }
+    if let Some(object_storage) = config
+        .object_storage
+        .as_ref()
+        .filter(|_| object_storage_managed)
+    {
+        issues.check_required(
+            "objectStorage.bucket",
+            object_storage.bucket.as_deref(),
+            reason,
+        );
+        issues.check_required(
+            "objectStorage.accessKey",
+            object_storage.access_key.as_deref(),
+            reason,
+        );
+        issues.check_required(
+            "objectStorage.secretKey",
+            object_storage.secret_key.as_deref(),
+            reason,
+        );
+        issues.check_url(
+            "objectStorage.baseUrl",
+            object_storage
+                .base_url
+                .as_deref()
+                .filter(|url| !url.is_empty()),
+            &["http", "https"],
+        );
+        if object_storage
+            .endpoint
+            .as_deref()
+            .is_some_and(|endpoint| endpoint.contains("://"))
+        {
+            issues.error(
+                "objectStorage.endpoint",
+                "must be a hostname without a scheme (use `objectStorage.useSsl` instead)",
+            );
+        }
+    }
+
+    issues.0
+}
+
+/// Loads the config file and checks the values that are syntactically valid
+/// but semantically wrong (e.g., out-of-range numbers, conflicting options).
+///
+/// The issues are also logged using [tracing].
+#[macros::export]
+pub fn check_config() -> Result<Vec<ConfigIssue>, ConfigError> {
+    let issues = validate(&read_config_file()?);
+
+    for issue in &issues {
+        match issue.severity {
+            ConfigIssueSeverity::Warning => {
+                tracing::warn!("{}: {}", issue.path, issue.message)
+            }
+            ConfigIssueSeverity::Error => tracing::error!("{}: {}", issue.path, issue.message),
+        }
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{validate, ConfigIssue, ConfigIssueSeverity};
+    use crate::config::server::ServerConfig;
+    use pretty_assertions::assert_eq;
+
+    fn check(yaml: &str) -> Vec<(ConfigIssueSeverity, String)> {
+        let base = r#"
+            url: https://example.com
+            port: 3000
+            db:
+              host: localhost
+              port: 5432
+              db: firefish_db
+              user: firefish
+              pass: password
+            redis:
+              host: localhost
+              port: 6379
+            "#;
+        let mut config: serde_yaml::Value = serde_yaml::from_str(base).unwrap();
+        let serde_yaml::Value::Mapping(extra) = serde_yaml::from_str(yaml).unwrap() else {
+            panic!("test config must be a mapping");
+        };
+        config.as_mapping_mut().unwrap().extend(extra);
+
+        let config: ServerConfig = serde_yaml::from_value(config).unwrap();
+
+        validate(&config)
+            .into_iter()
+            .map(|ConfigIssue { severity, path, .. }| (severity, path))
+            .collect()
+    }
+
+    #[test]
+    fn valid_config() {
+        assert_eq!(check("maxLogLevel: info"), vec![]);
+    }
+
+    #[test]
+    fn invalid_values() {
+        use ConfigIssueSeverity::*;
+
+        assert_eq!(
+            check(
+                r#"
+                url: https://example.com/firefish
+                maxLogLevel: verbose
+                logLevel: [info]
+                logFilter: backend_rs=debug,sqlx=loud
+                logFormat: xml
+                logFile:
+                  path: /var/log/firefish/server.log
+                  rotation: weekly
+                syslog:
+                  host: localhost
+                  port: 514
+                  protocol: tls
+                  facility: local9
+                telemetry:
+                  otlpEndpoint: https://collector.example.com/v1/traces
+                  sampleRatio: 2
+                  metricsPort: 3000
+                ssrf:
+                  allowedNetworks: [10.1.0.0/16, 10.2.0.0/33]
+                  deniedNetworks: [example.com]
+                  allowedPorts: []
+                id: snowflake
+                cuid:
+                  length: 32
+                maxNoteLength: 0
+                clusterLimits:
+                  web: 0
+                "#
+            ),
+            vec![
+                (Warning, "url".to_owned()),
+                (Error, "maxNoteLength".to_owned()),
+                (Error, "clusterLimits.web".to_owned()),
+                (Error, "id".to_owned()),
+                (Warning, "cuid.length".to_owned()),
+                (Error, "maxLogLevel".to_owned()),
+                (Warning, "logLevel".to_owned()),
+                (Warning, "logFilter".to_owned()),
+                (Error, "logFormat".to_owned()),
+                (Error, "logFile.rotation".to_owned()),
+                (Error, "syslog.protocol".to_owned()),
+                (Error, "syslog.facility".to_owned()),
+                (Error, "telemetry.otlpEndpoint".to_owned()),
+                (Error, "telemetry.sampleRatio".to_owned()),
+                (Error, "telemetry.metricsPort".to_owned()),
+                (Error, "ssrf.allowedNetworks".to_owned()),
+                (Error, "ssrf.deniedNetworks".to_owned()),
+                (Error, "ssrf.allowedPorts".to_owned()),
+            ]
+        );
+        assert_eq!(
+            check(
+                r#"
+                id: aidx
+                cuid:
+                  length: 20
+                "#
+            ),
+            vec![(Warning, "cuid".to_owned())]
+        );
+    }
+
+    #[test]
+    fn invalid_proxies() {
+        use ConfigIssueSeverity::*;
+
+        assert_eq!(
+            check(
+                r#"
+                proxy: ftp://127.0.0.1:3128
+                proxySmtp: 127.0.0.1:1080
+                proxyBypassHosts: ["https://example.com/", "192.0.2.8"]
+                "#
+            ),
+            vec![
+                (Error, "proxy".to_owned()),
+                (Error, "proxySmtp".to_owned()),
+                (Warning, "proxyBypassHosts[0]".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn conflicting_managed_settings() {
+        use ConfigIssueSeverity::*;
+
+        assert_eq!(
+            check(
+                r#"
+                isManagedHosting: true
+                deepl:
+                  managed: true
+                  authKey: key
+                libreTranslate:
+                  managed: true
+                objectStorage:
+                  managed: true
+                  bucket: firefish
+                  endpoint: https://s3.example.com
+                "#
+            ),
+            vec![
+                (Error, "libreTranslate.managed".to_owned()),
+                (Error, "libreTranslate.apiUrl".to_owned()),
+                (Error, "objectStorage.accessKey".to_owned()),
+                (Error, "objectStorage.secretKey".to_owned()),
+                (Error, "objectStorage.endpoint".to_owned()),
+            ]
+        );
+    }
+}
@@ -3,5 +3,8 @@
 pub use meta::local_server_info;
 pub use server::CONFIG;
 
+pub mod check;
 pub mod meta;
+pub mod reload;
+mod schema;
 pub mod server;
@@ -0,0 +1,195 @@
+//! Reloading server configurations at runtime
+
+use super::{
+    check::{self, ConfigIssueSeverity},
+    server::{self, Config, ConfigError},
+    CONFIG,
+};
+use arc_swap::ArcSwap;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Config fields that are derived from the other fields
+const DERIVED_FIELDS: [&str; 11] = [
+    "version",
+    "host",
+    "hostname",
+    "redisKeyPrefix",
+    "scheme",
+    "wsScheme",
+    "apiUrl",
+    "wsUrl",
+    "authUrl",
+    "driveUrl",
+    "userAgent",
+];
+
+/// Config fields that can be changed without restarting the server
+///
+/// The job concurrency (`deliverJobConcurrency` and `inboxJobConcurrency`) is not
+/// included, as it is passed to the queue workers when they start and can't be changed
+/// afterwards. Changing it is reported as requiring a restart.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object, use_nullable = false)]
+pub struct RuntimeConfig {
+    pub max_log_level: Option<String>,
+    pub log_filter: Option<String>,
+    pub proxy_bypass_hosts: Option<Vec<String>>,
+    pub reserved_usernames: Option<Vec<String>>,
+}
+
+impl From<&Config> for RuntimeConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            max_log_level: config.max_log_level.clone(),
+            log_filter: config.log_filter.clone(),
+            proxy_bypass_hosts: config.proxy_bypass_hosts.clone(),
+            reserved_usernames: config.reserved_usernames.clone(),
+        }
+    }
+}
+
+static RUNTIME_CONFIG: Lazy<ArcSwap<RuntimeConfig>> =
+    Lazy::new(|| ArcSwap::from_pointee(RuntimeConfig::from(&*CONFIG)));
+
+/// Returns the current values of the config fields that can be changed at runtime.
+///
+/// The returned value is not updated by later reloads, so don't hold it for a long time.
+pub fn runtime_config() -> Arc<RuntimeConfig> {
+    RUNTIME_CONFIG.load_full()
+}
+
+/// Returns the current values of the config fields that can be changed at runtime.
+#[macros::ts_export]
+pub fn get_runtime_config() -> RuntimeConfig {
+    runtime_config().as_ref().clone()
+}
+
+/// Appends the paths of the fields that differ between `old` and `new` to `changed`.
+fn diff(old: &serde_json::Value, new: &serde_json::Value, path: &str, changed: &mut Vec<String>) {
+    match (old, new) {
+        (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let child_path = match path.is_empty() {
+                    true => key.to_owned(),
+                    false => format!("{}.{}", path, key),
+                };
+                diff(
+                    old.get(key).unwrap_or(&serde_json::Value::Null),
+                    new.get(key).unwrap_or(&serde_json::Value::Null),
+                    &child_path,
+                    changed,
+                );
+            }
+        }
+        (old, new) if old != new => changed.push(path.to_owned()),
+        _ => {}
+    }
+}
+
+/// Returns the paths of the fields that differ between `old` and `new`.
+fn changed_fields(old: &impl Serialize, new: &impl Serialize) -> Vec<String> {
+    let mut changed = Vec::new();
+
+    if let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) {
+        diff(&old, &new, "", &mut changed);
+    }
+
+    changed
+}
+
+/// Re-reads the config file and applies the fields that can be changed at runtime
+/// (see [RuntimeConfig]). The config is not applied if it has semantic errors.
+///
+/// This only affects the current process. The TypeScript backend calls this on SIGHUP
+/// and forwards the signal from the master process to the workers.
+///
+/// Returns the paths of the changed fields that require a restart to take effect.
+#[macros::export]
+pub fn reload_config() -> Result<Vec<String>, ConfigError> {
+    let server_config = server::read_config_file()?;
+
+    let issues = check::validate(&server_config);
+    if issues
+        .iter()
+        .any(|issue| issue.severity == ConfigIssueSeverity::Error)
+    {
+        return Err(ConfigError::InvalidValues(issues));
+    }
+    for issue in issues {
+        tracing::warn!("{}: {}", issue.path, issue.message);
+    }
+
+    let config = server::build_config(server_config)?;
+    let runtime = RuntimeConfig::from(&config);
+
+    let applied = changed_fields(&*runtime_config(), &runtime);
+    let reloadable: Vec<String> = match serde_json::to_value(&runtime) {
+        Ok(serde_json::Value::Object(fields)) => fields.keys().cloned().collect(),
+        _ => vec![],
+    };
+    let restart_required: Vec<String> = changed_fields(&*CONFIG, &config)
+        .into_iter()
+        .filter(|path| {
+            let field = path.split('.').next().unwrap_or_default();
+            !DERIVED_FIELDS.contains(&field) && !reloadable.iter().any(|f| f == field)
+        })
+        .collect();
+
+    RUNTIME_CONFIG.store(Arc::new(runtime));
+    crate::init::log::update_filter();
+
+    if !applied.is_empty() {
+        tracing::info!("applied config changes: {}", applied.join(", "));
+    }
+    if !restart_required.is_empty() {
+        tracing::warn!(
+            "restart the server to apply config changes: {}",
+            restart_required.join(", ")
+        );
+    }
+
+    Ok(restart_required)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::diff;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn diff_fields() {
+        let old = json!({
+            "port": 3000,
+            "db": { "host": "localhost", "port": 5432 },
+            "maxLogLevel": "info",
+            "reservedUsernames": ["root", "admin"],
+        });
+        let new = json!({
+            "port": 3000,
+            "db": { "host": "db.example.com", "port": 5432, "maxConnections": 20 },
+            "maxLogLevel": "debug",
+            "reservedUsernames": ["root"],
+        });
+
+        let mut changed = Vec::new();
+        diff(&old, &new, "", &mut changed);
+
+        assert_eq!(
+            changed,
+            vec![
+                "db.host",
+                "db.maxConnections",
+                "maxLogLevel",
+                "reservedUsernames"
+            ]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Listing the config fields from the [Deserialize] implementation of the config
+
+use super::server::ServerConfig;
+use once_cell::sync::Lazy;
+use serde::de::{
+    self, value::Error, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess,
+    Visitor,
+};
+use serde::Deserialize;
+use std::collections::HashSet;
+
+/// Paths of the fields with scalar values (strings, numbers, booleans, or arbitrary values)
+static SCALAR_FIELDS: Lazy<HashSet<Vec<String>>> = Lazy::new(|| {
+    let mut fields = Vec::new();
+    if let Err(err) = ServerConfig::deserialize(Probe {
+        path: Vec::new(),
+        fields: &mut fields,
+    }) {
+        tracing::warn!("failed to list the config fields: {}", err);
+    }
+    fields.into_iter().collect()
+});
+
+/// Returns whether the path (e.g., `["db", "pass"]`) is a config field with a scalar value.
+pub(super) fn is_scalar_field(path: &[String]) -> bool {
+    SCALAR_FIELDS.contains(path)
+}
+
+/// Deserializer that records the paths of the scalar fields while giving them dummy values
+struct Probe<'a> {
+    path: Vec<String>,
+    fields: &'a mut Vec<Vec<String>>,
+}
+
+impl Probe<'_> {
+    fn record(self) {
+        self.fields.push(self.path);
+    }
+}
+
+macro_rules! scalar {
+    ($($method:ident => $visit:ident($($value:expr)?)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.record();
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Probe<'_> {
+    type Error = Error;
+
+    scalar! {
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i64(0),
+        deserialize_i16 => visit_i64(0),
+        deserialize_i32 => visit_i64(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_u8 => visit_u64(0),
+        deserialize_u16 => visit_u64(0),
+        deserialize_u32 => visit_u64(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_f32 => visit_f64(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char(' '),
+        // some strings are validated (e.g., `url`)
+        deserialize_str => visit_str("https://example.com/"),
+        deserialize_string => visit_str("https://example.com/"),
+        deserialize_identifier => visit_str(""),
+        deserialize_any => visit_unit(),
+        deserialize_ignored_any => visit_unit(),
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // elements of sequences can't be set by environment variables
+        visitor.visit_seq(Empty)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(Empty)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_map(ProbeFields {
+            path: self.path,
+            fields: self.fields,
+            names: fields.iter(),
+            current: None,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Error> {
+        Err(de::Error::custom("enums are not supported"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit unit_struct newtype_struct tuple tuple_struct
+    }
+}
+
+/// Gives a dummy value to each field of a struct
+struct ProbeFields<'a> {
+    path: Vec<String>,
+    fields: &'a mut Vec<Vec<String>>,
+    names: std::slice::Iter<'static, &'static str>,
+    current: Option<&'static str>,
+}
+
+impl<'de> MapAccess<'de> for ProbeFields<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some(&name) = self.names.next() else {
+            return Ok(None);
+        };
+        self.current = Some(name);
+        seed.deserialize(name.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let mut path = self.path.clone();
+        path.extend(self.current.map(str::to_owned));
+        seed.deserialize(Probe {
+            path,
+            fields: self.fields,
+        })
+    }
+}
+
+/// Empty sequence or map
+struct Empty;
+
+impl<'de> SeqAccess<'de> for Empty {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        _seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        Ok(None)
+    }
+}
+
+impl<'de> MapAccess<'de> for Empty {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        _seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, _seed: V) -> Result<V::Value, Error> {
+        Err(de::Error::custom("no value"))
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::is_scalar_field;
+
+    fn path(path: &[&str]) -> Vec<String> {
+        path.iter().map(|&key| key.to_owned()).collect()
+    }
+
+    #[test]
+    fn list_scalar_fields() {
+        assert!(is_scalar_field(&path(&["url"])));
+        assert!(is_scalar_field(&path(&["db", "pass"])));
+        assert!(is_scalar_field(&path(&["redis", "pass"])));
+        assert!(is_scalar_field(&path(&["cacheServer", "pass"])));
+        assert!(is_scalar_field(&path(&["maxNoteLength"])));
+        assert!(is_scalar_field(&path(&["db", "extra"])));
+
+        // objects
+        assert!(!is_scalar_field(&path(&["db"])));
+        assert!(!is_scalar_field(&path(&["logFile"])));
+        // unknown fields
+        assert!(!is_scalar_field(&path(&["log"])));
+        assert!(!is_scalar_field(&path(&["db", "password"])));
+    }
+}
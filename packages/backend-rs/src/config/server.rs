@@ -1,15 +1,18 @@
 //! Server configuration
 
+use super::{check::ConfigIssue, schema};
+use crate::{misc::ssrf_policy::parse_network, util::error_chain::format_error};
 use once_cell::sync::Lazy;
-use serde::Deserialize;
-use std::{env, fs};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::PathBuf};
 
 pub const VERSION: &str = macros::read_version_from_package_json!();
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
-struct ServerConfig {
+pub(super) struct ServerConfig {
+    #[serde(deserialize_with = "deserialize_url")]
     pub url: String,
     pub port: u16,
     /// the host address to bind to
@@ -22,6 +25,9 @@ struct ServerConfig {
     pub redis: RedisConfig,
     /// secondary Redis server configurations
     pub cache_server: Option<RedisConfig>,
+    /// keep recent events of the main and chat streams in Redis for 24 hours, so that
+    /// reconnecting clients can catch up with them (default: false)
+    pub durable_streams: Option<bool>,
 
     /// proxy host used for HTTP requests
     pub proxy: Option<String>,
@@ -35,8 +41,12 @@ struct ServerConfig {
     pub max_file_size: Option<i64>,
     pub access_log: Option<String>,
     pub cluster_limits: Option<WorkerConfigInternal>,
+    /// scheme of new IDs. `cuid` (default), `aid`, `aidx`, `meid`, `ulid`, or `objectid`
+    pub id: Option<String>,
     pub cuid: Option<IdConfig>,
     pub outgoing_address: Option<String>,
+    /// destinations that outgoing requests to remote servers may reach
+    pub ssrf: Option<SsrfConfig>,
 
     pub deliver_job_concurrency: Option<u32>,
     pub inbox_job_concurrency: Option<u32>,
@@ -48,10 +58,20 @@ struct ServerConfig {
     /// deprecated in favor of `max_log_level`
     pub log_level: Option<Vec<String>>,
 
-    /// verbosity of the server log. `error`, `warn`, `info`, `debug`, or `trace`
+    /// verbosity of the server log. `error`, `warning`, `info`, `debug`, or `trace`
     pub max_log_level: Option<String>,
+    /// per-module log levels in the `RUST_LOG` syntax (e.g., `backend_rs::federation=debug,sea_orm=warn`).
+    /// these take precedence over `max_log_level`, and `RUST_LOG` takes precedence over these
+    pub log_filter: Option<String>,
+
+    /// format of the server log. `compact` (default), `pretty`, or `json`
+    pub log_format: Option<String>,
+    /// file to write the server log to
+    pub log_file: Option<LogFileConfig>,
 
     pub syslog: Option<SysLogConfig>,
+    /// OpenTelemetry traces and Prometheus metrics
+    pub telemetry: Option<TelemetryConfig>,
 
     pub proxy_remote_files: Option<bool>,
     pub media_proxy: Option<String>,
@@ -70,7 +90,7 @@ struct ServerConfig {
     pub object_storage: Option<ObjectStorageConfig>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct DbConfig {
@@ -80,10 +100,47 @@ pub struct DbConfig {
     pub user: String,
     pub pass: String,
     pub disable_cache: Option<bool>,
+    /// [node-postgres](https://node-postgres.com/apis/client) options, some of which
+    /// (e.g., `statement_timeout`, `max`) are also used as fallbacks by the Rust backend
     pub extra: Option<serde_json::Value>,
+
+    /// maximum number of connections in the pool
+    pub max_connections: Option<u32>,
+    /// minimum number of idle connections kept in the pool
+    pub min_connections: Option<u32>,
+    /// connection timeout (in milliseconds)
+    pub connect_timeout: Option<u32>,
+    /// timeout for acquiring a connection from the pool (in milliseconds)
+    pub acquire_timeout: Option<u32>,
+    /// idle connections are closed after this duration (in milliseconds)
+    pub idle_timeout: Option<u32>,
+    /// connections are closed after this duration (in milliseconds)
+    pub max_lifetime: Option<u32>,
+    /// statement timeout (in milliseconds, `0` to disable)
+    pub statement_timeout: Option<u32>,
+    /// `application_name` reported to PostgreSQL
+    pub application_name: Option<String>,
+    /// `disable`, `allow`, `prefer`, `require`, `verify-ca`, or `verify-full`
+    pub ssl_mode: Option<String>,
+    /// read-only replica servers
+    pub replicas: Option<Vec<DbReplicaConfig>>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object, use_nullable = false)]
+pub struct DbReplicaConfig {
+    pub host: String,
+    pub port: u16,
+    /// defaults to the primary database name
+    pub db: Option<String>,
+    /// defaults to the primary database user
+    pub user: Option<String>,
+    /// defaults to the primary database password
+    pub pass: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct RedisConfig {
@@ -96,9 +153,48 @@ pub struct RedisConfig {
     #[serde(default)]
     pub db: u32,
     pub prefix: Option<String>,
+
+    /// Unix socket path (`host` and `port` are ignored if this is set)
+    pub path: Option<String>,
+    /// Redis Sentinel configurations (`host` and `port` are ignored if this is set)
+    pub sentinel: Option<RedisSentinelConfig>,
+
+    /// maximum number of connections in the pool
+    pub max_connections: Option<u32>,
+    /// minimum number of idle connections kept in the pool
+    pub min_connections: Option<u32>,
+    /// connection timeout (in milliseconds)
+    pub connect_timeout: Option<u32>,
+    /// timeout for acquiring a connection from the pool (in milliseconds)
+    pub acquire_timeout: Option<u32>,
+    /// idle connections are closed after this duration (in milliseconds)
+    pub idle_timeout: Option<u32>,
+    /// connections are closed after this duration (in milliseconds)
+    pub max_lifetime: Option<u32>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object, use_nullable = false)]
+pub struct RedisSentinelConfig {
+    /// name of the master group
+    pub name: String,
+    pub nodes: Vec<RedisSentinelNode>,
+    /// username used to connect to the Sentinel nodes
+    pub user: Option<String>,
+    /// password used to connect to the Sentinel nodes
+    pub pass: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object, use_nullable = false)]
+pub struct RedisSentinelNode {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct TlsConfig {
@@ -106,13 +202,15 @@ pub struct TlsConfig {
     pub reject_unauthorized: bool,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct WorkerConfig {
     pub web: u32,
     pub queue: u32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct WorkerConfigInternal {
@@ -120,7 +218,7 @@ pub struct WorkerConfigInternal {
     pub queue: Option<u32>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct IdConfig {
@@ -128,15 +226,61 @@ pub struct IdConfig {
     pub fingerprint: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct SysLogConfig {
     pub host: String,
     pub port: u16,
+    /// `udp` (default) or `tcp`
+    pub protocol: Option<String>,
+    /// syslog facility (e.g., `daemon`, `local0`). defaults to `user`
+    pub facility: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object, use_nullable = false)]
+pub struct SsrfConfig {
+    /// networks (in CIDR notation) that are allowed even if they are private (e.g., `10.1.0.0/16`)
+    pub allowed_networks: Option<Vec<String>>,
+    /// networks (in CIDR notation) to reject in addition to the private and reserved ones
+    pub denied_networks: Option<Vec<String>>,
+    /// ports that may be accessed. any port is allowed if not set
+    pub allowed_ports: Option<Vec<u16>>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object, use_nullable = false)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP endpoint to export traces to (e.g., `http://localhost:4318/v1/traces`).
+    /// traces are not exported if not set
+    pub otlp_endpoint: Option<String>,
+    /// service name reported to the collector. defaults to `firefish`
+    pub service_name: Option<String>,
+    /// ratio of the traces to export (0.0 to 1.0). defaults to 1.0
+    pub sample_ratio: Option<f64>,
+    /// serve Prometheus metrics of each worker process at `/metrics` on port
+    /// `metrics_port + i`, where `i` is the index of the worker (starting from 0).
+    /// metrics are not served if not set
+    pub metrics_port: Option<u16>,
+    /// address to serve metrics on. defaults to `127.0.0.1`
+    pub metrics_bind: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object, use_nullable = false)]
+pub struct LogFileConfig {
+    pub path: String,
+    /// `minutely`, `hourly`, `daily` (default), or `never`
+    pub rotation: Option<String>,
+    /// maximum number of log files to keep (unlimited if not set)
+    pub max_files: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct DeepLConfig {
@@ -145,7 +289,7 @@ pub struct DeepLConfig {
     pub is_pro: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct LibreTranslateConfig {
@@ -154,7 +298,7 @@ pub struct LibreTranslateConfig {
     pub api_key: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct EmailConfig {
@@ -167,7 +311,7 @@ pub struct EmailConfig {
     pub use_implicit_ssl_tls: Option<bool>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct ObjectStorageConfig {
@@ -185,6 +329,8 @@ pub struct ObjectStorageConfig {
     pub s3_force_path_style: Option<bool>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 #[macros::export(object, use_nullable = false)]
 pub struct Config {
     // ServerConfig (from default.yml)
@@ -195,14 +341,17 @@ pub struct Config {
     pub db: DbConfig,
     pub redis: RedisConfig,
     pub cache_server: Option<RedisConfig>,
+    pub durable_streams: bool,
     pub proxy: Option<String>,
     pub proxy_smtp: Option<String>,
     pub proxy_bypass_hosts: Option<Vec<String>>,
     pub max_file_size: i64,
     pub access_log: Option<String>,
     pub cluster_limits: WorkerConfig,
+    pub id: Option<String>,
     pub cuid: Option<IdConfig>,
     pub outgoing_address: Option<String>,
+    pub ssrf: Option<SsrfConfig>,
     pub deliver_job_concurrency: Option<u32>,
     pub inbox_job_concurrency: Option<u32>,
     pub deliver_job_per_sec: Option<u32>,
@@ -214,7 +363,11 @@ pub struct Config {
     pub log_level: Option<Vec<String>>,
 
     pub max_log_level: Option<String>,
+    pub log_filter: Option<String>,
+    pub log_format: Option<String>,
+    pub log_file: Option<LogFileConfig>,
     pub syslog: Option<SysLogConfig>,
+    pub telemetry: Option<TelemetryConfig>,
     pub proxy_remote_files: Option<bool>,
     pub media_proxy: Option<String>,
     pub summaly_proxy_url: Option<String>,
@@ -242,34 +395,338 @@ pub struct Config {
     pub user_agent: String,
 }
 
-fn read_config_file() -> ServerConfig {
-    let cwd = env::current_dir().unwrap();
-    let yml = fs::File::open(cwd.join("../../.config/default.yml"))
-        .expect("Failed to open '.config/default.yml'");
-    let mut data: ServerConfig =
-        serde_yaml::from_reader(yml).expect("Failed to parse .config/default.yml");
+/// Environment variable to specify the path of the config file
+const CONFIG_PATH_ENV: &str = "FIREFISH_CONFIG";
+/// Prefix of the environment variables that override config values
+const OVERRIDE_ENV_PREFIX: &str = "FIREFISH_";
+/// Suffix of the environment variables that specify files containing config values
+const FILE_ENV_SUFFIX: &str = "_FILE";
+
+/// Config field that could not be loaded
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidField {
+    /// path of the field (e.g., `db.port`)
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+fn list_lines<T: std::fmt::Display>(items: &[T]) -> String {
+    items.iter().map(|item| format!("\n  {}", item)).collect()
+}
+
+#[error_doc::errors]
+pub enum ConfigError {
+    #[error("failed to read the config file ({0})")]
+    ReadFile(String, #[source] std::io::Error),
+    #[error("failed to parse the config file ({0})")]
+    ParseFile(String, #[source] serde_yaml::Error),
+    #[error("failed to read the file specified by {0}")]
+    ReadSecretFile(String, #[source] std::io::Error),
+    #[doc = "Some config fields are invalid"]
+    #[error("invalid config fields:{}", list_lines(.0))]
+    InvalidFields(Vec<InvalidField>),
+    #[doc = "Some config values are semantically wrong"]
+    #[error("invalid config values:{}", list_lines(.0))]
+    InvalidValues(Vec<ConfigIssue>),
+}
+
+/// Config value given by an environment variable
+struct EnvOverride {
+    /// name of the environment variable
+    name: String,
+    /// path of the field (e.g., `["db", "port"]`)
+    path: Vec<String>,
+    /// unparsed value
+    raw: String,
+}
 
-    data.url = url::Url::parse(&data.url)
-        .expect("Config url is invalid")
-        .origin()
-        .ascii_serialization();
+/// Converts `SCREAMING_SNAKE_CASE` into `camelCase`.
+fn to_camel_case(name: &str) -> String {
+    let mut words = name.split('_').filter(|word| !word.is_empty());
+    let mut converted = words.next().unwrap_or_default().to_ascii_lowercase();
+
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            converted.push(first.to_ascii_uppercase());
+        }
+        converted.push_str(&chars.as_str().to_ascii_lowercase());
+    }
+
+    converted
+}
+
+/// Parses the value of an environment variable as YAML,
+/// falling back to the string as is if it isn't a valid non-null YAML value.
+fn parse_env_value(raw: &str) -> serde_yaml::Value {
+    match serde_yaml::from_str(raw) {
+        Ok(serde_yaml::Value::Null) | Err(_) => serde_yaml::Value::String(raw.to_owned()),
+        Ok(value) => value,
+    }
+}
+
+fn get_value<'a>(
+    mut node: &'a serde_yaml::Value,
+    path: &[String],
+) -> Option<&'a serde_yaml::Value> {
+    for key in path {
+        node = node.get(key)?;
+    }
+    Some(node)
+}
+
+fn set_value(mut node: &mut serde_yaml::Value, path: &[String], value: serde_yaml::Value) {
+    for key in path {
+        if !node.is_mapping() {
+            *node = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+        }
+        let serde_yaml::Value::Mapping(mapping) = node else {
+            unreachable!()
+        };
+        node = mapping
+            .entry(serde_yaml::Value::String(key.to_owned()))
+            .or_insert(serde_yaml::Value::Null);
+    }
+    *node = value;
+}
+
+fn remove_value(node: &mut serde_yaml::Value, path: &[String]) -> bool {
+    let Some((last, parents)) = path.split_last() else {
+        return false;
+    };
+
+    let mut node = Some(node);
+    for key in parents {
+        node = node.and_then(|node| node.get_mut(key));
+    }
+
+    node.and_then(|node| node.as_mapping_mut())
+        .and_then(|mapping| mapping.remove(last))
+        .is_some()
+}
+
+/// Applies `FIREFISH_*` environment variables to the config.
+///
+/// Nested fields are separated by `__` (e.g., `FIREFISH_DB__PORT` overrides `db.port`),
+/// and the content of the file is used if the name has the `_FILE` suffix
+/// (e.g., `FIREFISH_DB__PASS_FILE=/run/secrets/db_pass`).
+fn apply_env_overrides(
+    config: &mut serde_yaml::Value,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<EnvOverride>, ConfigError> {
+    let mut vars: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(OVERRIDE_ENV_PREFIX) && name != CONFIG_PATH_ENV)
+        .collect();
+    // apply parent fields first so that nested fields are not overwritten
+    vars.sort();
+
+    let mut overrides = Vec::with_capacity(vars.len());
+
+    for (name, value) in vars {
+        let field = &name[OVERRIDE_ENV_PREFIX.len()..];
+        let (field, file) = match field.strip_suffix(FILE_ENV_SUFFIX) {
+            Some(field) => (field, true),
+            None => (field, false),
+        };
+
+        let path: Vec<String> = field.split("__").map(to_camel_case).collect();
+        if path.iter().any(String::is_empty) {
+            tracing::warn!("ignoring environment variable {}", name);
+            continue;
+        }
+
+        let raw = if !file {
+            value
+        } else if schema::is_scalar_field(&path) {
+            let content = fs::read_to_string(&value)
+                .map_err(|err| ConfigError::ReadSecretFile(name.clone(), err))?;
+            content.trim_end_matches(['\r', '\n']).to_owned()
+        } else {
+            tracing::warn!(
+                "ignoring environment variable {} ({} is not a config field)",
+                name,
+                path.join(".")
+            );
+            continue;
+        };
+
+        set_value(config, &path, parse_env_value(&raw));
+        overrides.push(EnvOverride { name, path, raw });
+    }
+
+    Ok(overrides)
+}
+
+/// Deserializes the config, collecting all invalid fields instead of stopping at the first one.
+fn deserialize_config(
+    mut config: serde_yaml::Value,
+    overrides: &[EnvOverride],
+) -> Result<ServerConfig, ConfigError> {
+    use serde_path_to_error::Segment;
+
+    let mut invalid_fields: Vec<InvalidField> = Vec::new();
+
+    loop {
+        let err = match serde_path_to_error::deserialize::<_, ServerConfig>(config.clone()) {
+            Ok(data) if invalid_fields.is_empty() => return Ok(data),
+            Ok(_) => return Err(ConfigError::InvalidFields(invalid_fields)),
+            Err(err) => err,
+        };
+
+        let mut segments: Vec<Segment> = err.path().iter().cloned().collect();
+        let mut message = err.inner().to_string();
+
+        // the object containing a missing field is removed on retry
+        let to_remove: Vec<String> = segments
+            .iter()
+            .map_while(|segment| match segment {
+                Segment::Map { key } => Some(key.to_owned()),
+                _ => None,
+            })
+            .collect();
+
+        if let Some(field) = message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.strip_suffix('`'))
+        {
+            segments.push(Segment::Map {
+                key: field.to_owned(),
+            });
+            message = "this field is required".to_owned();
+        } else if to_remove.len() < segments.len() {
+            // an element of a sequence is invalid, so remove the entire sequence
+        } else if let Some(env) = overrides.iter().find(|env| env.path == to_remove) {
+            // the value might be meant to be a string (e.g., numeric passwords)
+            let raw = serde_yaml::Value::String(env.raw.to_owned());
+            if get_value(&config, &env.path) != Some(&raw) {
+                set_value(&mut config, &env.path, raw);
+                continue;
+            }
+            message = format!("{} (set by {})", message, env.name);
+        }
+
+        let path = match segments.is_empty() {
+            true => ".".to_owned(),
+            false => segments
+                .iter()
+                .enumerate()
+                .map(|(i, segment)| match segment {
+                    Segment::Seq { index } => format!("[{}]", index),
+                    _ if i == 0 => segment.to_string(),
+                    _ => format!(".{}", segment),
+                })
+                .collect(),
+        };
+
+        // errors caused by removing invalid fields are not reported
+        let is_consequence = invalid_fields.iter().any(|field| {
+            field.path == path
+                || field.path.starts_with(&format!("{}.", path))
+                || field.path.starts_with(&format!("{}[", path))
+        });
+        if !is_consequence {
+            invalid_fields.push(InvalidField { path, message });
+        }
+
+        if !remove_value(&mut config, &to_remove) {
+            return Err(ConfigError::InvalidFields(invalid_fields));
+        }
+    }
+}
+
+/// Normalizes the server URL into its origin.
+fn normalize_url(url: &str) -> Result<url::Url, String> {
+    let url = url::Url::parse(url).map_err(|err| err.to_string())?;
+
+    if url.host_str().is_none() {
+        return Err("hostname is missing".to_owned());
+    }
+
+    url::Url::parse(&url.origin().ascii_serialization()).map_err(|err| err.to_string())
+}
+
+fn deserialize_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let url = String::deserialize(deserializer)?;
+
+    match normalize_url(&url) {
+        Ok(_) => Ok(url),
+        Err(err) => Err(serde::de::Error::custom(err)),
+    }
+}
+
+pub(super) fn read_config_file() -> Result<ServerConfig, ConfigError> {
+    let path = match env::var_os(CONFIG_PATH_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => env::current_dir()
+            .map_err(|err| ConfigError::ReadFile(".config/default.yml".to_owned(), err))?
+            .join("../../.config/default.yml"),
+    };
+
+    let yml = fs::File::open(&path)
+        .map_err(|err| ConfigError::ReadFile(path.display().to_string(), err))?;
+    let mut config: serde_yaml::Value = serde_yaml::from_reader(yml)
+        .map_err(|err| ConfigError::ParseFile(path.display().to_string(), err))?;
+
+    let vars = env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    let overrides = apply_env_overrides(&mut config, vars)?;
+
+    let mut data = deserialize_config(config, &overrides)?;
 
     if data.bind.is_none() {
         data.bind = std::env::var("BIND").ok()
     }
 
-    data
+    Ok(data)
 }
 
 #[macros::export]
-pub fn load_config() -> Config {
-    let server_config = read_config_file();
+pub fn load_config() -> Result<Config, ConfigError> {
+    build_config(read_config_file()?)
+}
+
+/// Fills in the default values and the values derived from the other fields.
+pub(super) fn build_config(server_config: ServerConfig) -> Result<Config, ConfigError> {
     let version = VERSION.to_owned();
-    let url = url::Url::parse(&server_config.url).expect("Config url is invalid");
-    let hostname = url
-        .host_str()
-        .expect("Hostname is missing in the config url")
-        .to_owned();
+    let url = normalize_url(&server_config.url).map_err(|message| {
+        ConfigError::InvalidFields(vec![InvalidField {
+            path: "url".to_owned(),
+            message,
+        }])
+    })?;
+    // an invalid SSRF policy must not be silently replaced with a weaker one
+    if let Some(ssrf) = &server_config.ssrf {
+        let invalid_fields: Vec<InvalidField> = [
+            ("ssrf.allowedNetworks", &ssrf.allowed_networks),
+            ("ssrf.deniedNetworks", &ssrf.denied_networks),
+        ]
+        .into_iter()
+        .flat_map(|(path, networks)| {
+            networks.iter().flatten().filter_map(move |network| {
+                parse_network(network).err().map(|err| InvalidField {
+                    path: path.to_owned(),
+                    message: err.to_string(),
+                })
+            })
+        })
+        .collect();
+
+        if !invalid_fields.is_empty() {
+            return Err(ConfigError::InvalidFields(invalid_fields));
+        }
+    }
+
+    let hostname = url.host_str().unwrap_or_default().to_owned();
     let host = match url.port() {
         Some(port) => format!("{}:{}", hostname, port),
         None => hostname.clone(),
@@ -295,22 +752,25 @@ pub fn load_config() -> Config {
     }
     .unwrap_or_else(|| hostname.clone());
 
-    Config {
-        url: server_config.url,
+    Ok(Config {
+        url: url.origin().ascii_serialization(),
         port: server_config.port,
         bind: server_config.bind,
         disable_hsts: server_config.disable_hsts,
         db: server_config.db,
         redis: server_config.redis,
         cache_server: server_config.cache_server,
+        durable_streams: server_config.durable_streams.unwrap_or(false),
         proxy: server_config.proxy,
         proxy_smtp: server_config.proxy_smtp,
         proxy_bypass_hosts: server_config.proxy_bypass_hosts,
         max_file_size: server_config.max_file_size.unwrap_or(262144000),
         access_log: server_config.access_log,
         cluster_limits,
+        id: server_config.id,
         cuid: server_config.cuid,
         outgoing_address: server_config.outgoing_address,
+        ssrf: server_config.ssrf,
         deliver_job_concurrency: server_config.deliver_job_concurrency,
         inbox_job_concurrency: server_config.inbox_job_concurrency,
         deliver_job_per_sec: server_config.deliver_job_per_sec,
@@ -319,7 +779,11 @@ pub fn load_config() -> Config {
         inbox_job_max_attempts: server_config.inbox_job_max_attempts,
         log_level: server_config.log_level,
         max_log_level: server_config.max_log_level,
+        log_filter: server_config.log_filter,
+        log_format: server_config.log_format,
+        log_file: server_config.log_file,
         syslog: server_config.syslog,
+        telemetry: server_config.telemetry,
         proxy_remote_files: server_config.proxy_remote_files,
         media_proxy: server_config.media_proxy,
         summaly_proxy_url: server_config.summaly_proxy_url,
@@ -344,7 +808,190 @@ pub fn load_config() -> Config {
         redis_key_prefix,
         scheme,
         ws_scheme: ws_scheme.to_owned(),
-    }
+    })
 }
 
-pub static CONFIG: Lazy<Config> = Lazy::new(load_config);
+pub static CONFIG: Lazy<Config> = Lazy::new(|| match load_config() {
+    Ok(config) => config,
+    Err(err) => panic!("{}", format_error(&err)),
+});
+
+#[cfg(test)]
+mod unit_test {
+    use super::{
+        apply_env_overrides, build_config, deserialize_config, normalize_url, to_camel_case,
+        ConfigError,
+    };
+    use pretty_assertions::assert_eq;
+
+    const CONFIG: &str = r#"
+        url: https://example.com/path
+        port: 3000
+        db:
+          host: localhost
+          port: 5432
+          db: firefish_db
+          user: firefish
+          pass: password
+        redis:
+          host: localhost
+          port: 6379
+        "#;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn convert_to_camel_case() {
+        assert_eq!(to_camel_case("URL"), "url");
+        assert_eq!(to_camel_case("MAX_CONNECTIONS"), "maxConnections");
+        assert_eq!(to_camel_case("S3_FORCE_PATH_STYLE"), "s3ForcePathStyle");
+        // non-ASCII characters are kept as is
+        assert_eq!(to_camel_case("MAX_ÉTÉ"), "maxÉtÉ");
+    }
+
+    #[test]
+    fn normalize_server_url() {
+        // the path is kept in `ServerConfig` (so that `check_config` can warn about it)
+        // and removed when the config is loaded
+        assert_eq!(
+            normalize_url("https://example.com/path?query#fragment")
+                .unwrap()
+                .as_str(),
+            "https://example.com/"
+        );
+        assert_eq!(
+            normalize_url("https://Example.com:8443/").unwrap().as_str(),
+            "https://example.com:8443/"
+        );
+        assert!(normalize_url("unix:/run/firefish.sock").is_err());
+        assert!(normalize_url("not a url").is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // can't call foreign function `open` on OS `linux`
+    fn override_with_env() {
+        let secret_file = std::env::temp_dir().join(format!(
+            "firefish_config_unit_test_override_with_env_{}",
+            std::process::id()
+        ));
+        std::fs::write(&secret_file, "secret\n").unwrap();
+
+        let mut config = serde_yaml::from_str(CONFIG).unwrap();
+        let overrides = apply_env_overrides(
+            &mut config,
+            vars(&[
+                ("FIREFISH_CONFIG", "/etc/firefish.yml"),
+                ("FIREFISH_PORT", "4000"),
+                ("FIREFISH_DB__PASS", "123456"),
+                ("FIREFISH_DB__MAX_CONNECTIONS", "20"),
+                ("FIREFISH_PROXY_BYPASS_HOSTS", "[a.example, b.example]"),
+                ("FIREFISH_REDIS__PASS_FILE", secret_file.to_str().unwrap()),
+                // not files for config fields
+                ("FIREFISH_LOG_FILE", "/nonexistent/firefish.log"),
+                ("FIREFISH_DB_FILE", "/nonexistent/db.yml"),
+                ("HOME", "/root"),
+            ]),
+        )
+        .unwrap();
+        std::fs::remove_file(&secret_file).unwrap();
+
+        assert_eq!(overrides.len(), 5);
+
+        let config = deserialize_config(config, &overrides).unwrap();
+
+        // normalized in `load_config`
+        assert_eq!(config.url, "https://example.com/path");
+        assert_eq!(config.port, 4000);
+        assert_eq!(config.db.pass, "123456");
+        assert_eq!(config.db.max_connections, Some(20));
+        assert_eq!(config.db.host, "localhost");
+        assert_eq!(
+            config.proxy_bypass_hosts,
+            Some(vec!["a.example".to_owned(), "b.example".to_owned()])
+        );
+        assert_eq!(config.redis.pass.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn list_invalid_fields() {
+        let mut config = serde_yaml::from_str(
+            r#"
+            url: example.com
+            port: 3000
+            db:
+              host: localhost
+              port: 5432
+              db: firefish_db
+              user: firefish
+              pass: password
+              replicas:
+                - host: replica
+            redis:
+              host: localhost
+              port: 6379
+            maxNoteLength: -1
+            "#,
+        )
+        .unwrap();
+        let overrides = apply_env_overrides(
+            &mut config,
+            vars(&[("FIREFISH_REDIS__DB", "one"), ("FIREFISH_PORT", "http")]),
+        )
+        .unwrap();
+
+        let Err(ConfigError::InvalidFields(fields)) = deserialize_config(config, &overrides) else {
+            panic!("invalid config must not be loaded");
+        };
+        let fields: Vec<String> = fields.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "url: relative URL without a base",
+                "maxNoteLength: invalid value: integer `-1`, expected u32",
+                "redis.db: invalid type: string \"one\", expected u32 (set by FIREFISH_REDIS__DB)",
+                "port: invalid type: string \"http\", expected u16 (set by FIREFISH_PORT)",
+                "db.replicas[0].port: this field is required",
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_ssrf_policy() {
+        let config = serde_yaml::from_str(
+            r#"
+            url: https://example.com
+            port: 3000
+            db:
+              host: localhost
+              port: 5432
+              db: firefish_db
+              user: firefish
+              pass: password
+            redis:
+              host: localhost
+              port: 6379
+            ssrf:
+              deniedNetworks: [10.0.0.0/8, 192.0.2.0/33]
+            "#,
+        )
+        .unwrap();
+        let config = deserialize_config(config, &[]).unwrap();
+
+        let Err(ConfigError::InvalidFields(fields)) = build_config(config) else {
+            panic!("invalid SSRF policy must not be loaded");
+        };
+        let fields: Vec<String> = fields.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "ssrf.deniedNetworks: invalid network `192.0.2.0/33` (expected an IP address or CIDR notation)"
+            ]
+        );
+    }
+}
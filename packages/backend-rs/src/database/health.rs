@@ -0,0 +1,415 @@
+//! Health check of PostgreSQL, Redis and the cache server
+
+use crate::{
+    config::{server::RedisConfig, CONFIG},
+    database::{
+        db_conn,
+        postgresql::{get_replica_conn, replica_names},
+        redis::{connection_manager, pool_state},
+        redis_conn, redis_key, RedisConnError,
+    },
+    util::error_chain::format_error,
+};
+use bb8::ManageConnection;
+use futures_util::future::join_all;
+use redis::{streams::StreamInfoGroupsReply, AsyncCommands, RedisError};
+use sea_orm::{ConnectionTrait, DbBackend, DbConn, DbErr, Statement};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Each check is considered failed if it takes longer than this.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// The database is considered degraded if the replication lag exceeds this (in seconds).
+const REPLICATION_LAG_THRESHOLD: f64 = 30.0;
+
+#[error_doc::errors]
+enum Error {
+    #[doc = "Database error"]
+    #[error(transparent)]
+    Db(#[from] DbErr),
+    #[error("failed to execute a Redis command")]
+    Redis(#[from] RedisError),
+    #[error("bad Redis connection")]
+    RedisConn(#[from] RedisConnError),
+    #[error("health check timed out")]
+    Timeout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(string_enum = "camelCase")]
+pub enum HealthStatus {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object)]
+pub struct PoolStats {
+    /// number of connections managed by the pool
+    pub connections: u32,
+    /// number of idle connections
+    pub idle_connections: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object)]
+pub struct PostgresHealth {
+    pub status: HealthStatus,
+    /// round-trip time of a ping (in milliseconds)
+    pub latency: Option<f64>,
+    /// maximum replay lag of the streaming replicas (in seconds)
+    pub replication_lag: Option<f64>,
+    pub pool: Option<PoolStats>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object)]
+pub struct ReplicaHealth {
+    /// `host:port` of the replica server
+    pub name: String,
+    /// this is at most [HealthStatus::Degraded], as the primary server is used
+    /// while the replica is unavailable
+    pub status: HealthStatus,
+    /// round-trip time of a ping (in milliseconds)
+    pub latency: Option<f64>,
+    /// time since the last replayed transaction (in seconds), which is zero if the
+    /// replica has replayed everything it has received
+    pub replication_lag: Option<f64>,
+    pub pool: Option<PoolStats>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object)]
+pub struct RedisHealth {
+    pub status: HealthStatus,
+    /// round-trip time of a `PING` command (in milliseconds)
+    pub latency: Option<f64>,
+    /// memory used by the server (in bytes)
+    pub used_memory: Option<i64>,
+    /// this is `None` if the server is not accessed via the connection pool
+    pub pool: Option<PoolStats>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object)]
+pub struct StreamHealth {
+    /// stream key (without the prefix)
+    pub key: String,
+    /// number of entries in the stream
+    pub length: i64,
+    /// number of entries delivered to consumer groups but not yet acknowledged
+    pub pending: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[macros::export(object)]
+pub struct HealthReport {
+    /// the worst status among the components
+    pub status: HealthStatus,
+    pub postgres: PostgresHealth,
+    /// read replicas of PostgreSQL
+    pub replicas: Vec<ReplicaHealth>,
+    pub redis: RedisHealth,
+    /// this is `None` if the cache server is not configured
+    pub cache_server: Option<RedisHealth>,
+    pub streams: Vec<StreamHealth>,
+}
+
+async fn with_timeout<T>(
+    future: impl std::future::Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    tokio::time::timeout(CHECK_TIMEOUT, future)
+        .await
+        .map_err(|_| Error::Timeout)?
+}
+
+fn elapsed_millis(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+fn pool_stats(db: &DbConn) -> PoolStats {
+    let pool = db.get_postgres_connection_pool();
+    PoolStats {
+        connections: pool.size(),
+        idle_connections: pool.num_idle().try_into().unwrap_or(u32::MAX),
+    }
+}
+
+async fn check_postgres() -> PostgresHealth {
+    let result = with_timeout(async {
+        let db = db_conn().await?;
+
+        let start = Instant::now();
+        db.ping().await?;
+        let latency = elapsed_millis(start);
+
+        // There is no entity for this system view
+        let replication_lag = db
+            .query_one(Statement::from_string(
+                DbBackend::Postgres,
+                "SELECT MAX(EXTRACT(EPOCH FROM replay_lag))::float8 AS lag FROM pg_stat_replication",
+            ))
+            .await?
+            .map(|row| row.try_get::<Option<f64>>("", "lag"))
+            .transpose()?
+            .flatten();
+
+        Ok((latency, replication_lag, pool_stats(db)))
+    })
+    .await;
+
+    match result {
+        Ok((latency, replication_lag, pool)) => PostgresHealth {
+            status: match replication_lag {
+                Some(lag) if lag > REPLICATION_LAG_THRESHOLD => HealthStatus::Degraded,
+                _ => HealthStatus::Healthy,
+            },
+            latency: Some(latency),
+            replication_lag,
+            pool: Some(pool),
+            error: None,
+        },
+        Err(err) => PostgresHealth {
+            status: HealthStatus::Unhealthy,
+            latency: None,
+            replication_lag: None,
+            pool: None,
+            error: Some(format_error(&err)),
+        },
+    }
+}
+
+/// Checks the replica server at `index` in [replica_names].
+async fn check_replica(index: usize, name: &str) -> ReplicaHealth {
+    let result = with_timeout(async {
+        let db = get_replica_conn(index).await?;
+
+        let start = Instant::now();
+        db.ping().await?;
+        let latency = elapsed_millis(start);
+
+        let replication_lag = db
+            .query_one(Statement::from_string(
+                DbBackend::Postgres,
+                "SELECT CASE WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0 \
+                 ELSE EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp()) END::float8 AS lag",
+            ))
+            .await?
+            .map(|row| row.try_get::<Option<f64>>("", "lag"))
+            .transpose()?
+            .flatten();
+
+        Ok((latency, replication_lag, pool_stats(db)))
+    })
+    .await;
+
+    match result {
+        Ok((latency, replication_lag, pool)) => ReplicaHealth {
+            name: name.to_owned(),
+            status: match replication_lag {
+                Some(lag) if lag > REPLICATION_LAG_THRESHOLD => HealthStatus::Degraded,
+                _ => HealthStatus::Healthy,
+            },
+            latency: Some(latency),
+            replication_lag,
+            pool: Some(pool),
+            error: None,
+        },
+        Err(err) => ReplicaHealth {
+            name: name.to_owned(),
+            status: HealthStatus::Degraded,
+            latency: None,
+            replication_lag: None,
+            pool: None,
+            error: Some(format_error(&err)),
+        },
+    }
+}
+
+/// Returns the `used_memory` value in the `INFO memory` command output.
+fn parse_used_memory(info: &str) -> Option<i64> {
+    info.lines()
+        .find_map(|line| line.strip_prefix("used_memory:"))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Checks the Redis server.
+///
+/// # Arguments
+///
+/// * `config` : configuration of the Redis server
+/// * `pooled` : whether the server is accessed via the connection pool ([redis_conn])
+async fn check_redis(config: &RedisConfig, pooled: bool) -> RedisHealth {
+    let result = with_timeout(async {
+        let mut conn = if pooled {
+            (*redis_conn().await?).clone()
+        } else {
+            connection_manager(config)?.connect().await?
+        };
+
+        let start = Instant::now();
+        redis::cmd("PING").query_async::<String>(&mut conn).await?;
+        let latency = elapsed_millis(start);
+
+        let info: String = redis::cmd("INFO")
+            .arg("memory")
+            .query_async(&mut conn)
+            .await?;
+
+        Ok((latency, parse_used_memory(&info)))
+    })
+    .await;
+
+    let pool = match pooled {
+        true => pool_state().map(|state| PoolStats {
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+        }),
+        false => None,
+    };
+
+    match result {
+        Ok((latency, used_memory)) => RedisHealth {
+            status: HealthStatus::Healthy,
+            latency: Some(latency),
+            used_memory,
+            pool,
+            error: None,
+        },
+        Err(err) => RedisHealth {
+            status: HealthStatus::Unhealthy,
+            latency: None,
+            used_memory: None,
+            pool,
+            error: Some(format_error(&err)),
+        },
+    }
+}
+
+async fn check_streams(keys: &[String]) -> Result<Vec<StreamHealth>, Error> {
+    with_timeout(async {
+        let mut redis = redis_conn().await?;
+        let mut streams = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let length: i64 = redis.xlen(redis_key(key)).await?;
+            let groups: StreamInfoGroupsReply = match length {
+                0 => StreamInfoGroupsReply::default(),
+                _ => redis.xinfo_groups(redis_key(key)).await?,
+            };
+
+            streams.push(StreamHealth {
+                key: key.to_owned(),
+                length,
+                pending: groups.groups.iter().map(|group| group.pending as i64).sum(),
+            });
+        }
+
+        Ok(streams)
+    })
+    .await
+}
+
+/// Checks the status of PostgreSQL (and its replicas), Redis, the cache server and
+/// the given Redis streams.
+///
+/// This function doesn't return an error; failures are reported in [HealthReport].
+///
+/// # Argument
+///
+/// * `stream_keys` : keys (without the prefix) of the Redis streams to report
+#[macros::export]
+pub async fn check_health(stream_keys: &[String]) -> HealthReport {
+    let replica_names = replica_names();
+    let (postgres, replicas, redis, cache_server, streams) = tokio::join!(
+        check_postgres(),
+        join_all(
+            replica_names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| check_replica(index, name))
+        ),
+        check_redis(&CONFIG.redis, CONFIG.cache_server.is_none()),
+        async {
+            match &CONFIG.cache_server {
+                Some(cache_server) => Some(check_redis(cache_server, true).await),
+                None => None,
+            }
+        },
+        check_streams(stream_keys),
+    );
+
+    let mut status = postgres.status.max(redis.status);
+
+    for replica in &replicas {
+        status = status.max(replica.status);
+    }
+
+    if let Some(cache_server) = &cache_server {
+        status = status.max(cache_server.status);
+    }
+
+    let streams = streams.unwrap_or_else(|err| {
+        tracing::warn!("failed to check Redis streams: {}", format_error(&err));
+        status = status.max(HealthStatus::Degraded);
+        vec![]
+    });
+
+    HealthReport {
+        status,
+        postgres,
+        replicas,
+        redis,
+        cache_server,
+        streams,
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{check_health, parse_used_memory, HealthStatus};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_info_memory() {
+        let info = "# Memory\r\nused_memory:1048576\r\nused_memory_human:1.00M\r\n";
+        assert_eq!(parse_used_memory(info), Some(1048576));
+        assert_eq!(parse_used_memory("# Memory\r\n"), None);
+    }
+
+    #[test]
+    fn compare_status() {
+        assert!(HealthStatus::Healthy < HealthStatus::Degraded);
+        assert!(HealthStatus::Degraded < HealthStatus::Unhealthy);
+        assert_eq!(
+            HealthStatus::Healthy.max(HealthStatus::Unhealthy),
+            HealthStatus::Unhealthy
+        );
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // can't call foreign function `getaddrinfo` on OS `linux`
+    async fn report_health() {
+        let report = check_health(&["CARGO_UNIT_TEST_STREAM".to_owned()]).await;
+
+        assert_eq!(report.postgres.status, HealthStatus::Healthy);
+        assert!(report
+            .replicas
+            .iter()
+            .all(|replica| replica.status == HealthStatus::Healthy));
+        assert_eq!(report.redis.status, HealthStatus::Healthy);
+        assert_eq!(report.streams.len(), 1);
+        assert_eq!(report.streams[0].length, 0);
+    }
+}
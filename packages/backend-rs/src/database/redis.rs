@@ -1,35 +1,277 @@
 //! Redis interface
 
-use crate::config::CONFIG;
+use crate::{
+    config::{server::RedisConfig, CONFIG},
+    util::metrics,
+};
 use bb8::{ManageConnection, Pool, PooledConnection, RunError};
-use redis::{aio::MultiplexedConnection, Client, ErrorKind, IntoConnectionInfo, RedisError};
-use tokio::sync::OnceCell;
+use redis::{
+    aio::{ConnectionLike, MultiplexedConnection, PubSub},
+    sentinel::{Sentinel, SentinelNodeConnectionInfo},
+    Arg, AsyncConnectionConfig, Client, Cmd, ConnectionAddr, ConnectionInfo, ErrorKind,
+    ExistenceCheck, IntoConnectionInfo, Pipeline, RedisConnectionInfo, RedisError, RedisFuture,
+    SetExpiry, SetOptions, TlsMode, Value,
+};
+use std::{
+    net::IpAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex, OnceCell};
+use tracing::Instrument;
+
+/// IP address family used to connect to the Redis server
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddressFamily {
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    fn matches(&self, ip: &IpAddr) -> bool {
+        match self {
+            AddressFamily::Any => true,
+            AddressFamily::Ipv4 => ip.is_ipv4(),
+            AddressFamily::Ipv6 => ip.is_ipv6(),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Target {
+    Direct {
+        client: Client,
+        family: AddressFamily,
+    },
+    Sentinel {
+        sentinel: Arc<Mutex<Sentinel>>,
+        service_name: String,
+        node_info: SentinelNodeConnectionInfo,
+    },
+}
+
+/// A [MultiplexedConnection] that records a span and the latency of each command
+#[derive(Clone)]
+pub struct RedisConnection(MultiplexedConnection);
+
+/// Returns the name of the command (e.g., `GET`).
+fn command_name(cmd: &Cmd) -> String {
+    match cmd.args_iter().next() {
+        Some(Arg::Simple(name)) => String::from_utf8_lossy(name).to_ascii_uppercase(),
+        _ => "UNKNOWN".to_owned(),
+    }
+}
+
+impl RedisConnection {
+    async fn traced<T>(command: String, request: RedisFuture<'_, T>) -> Result<T, RedisError> {
+        let span = tracing::info_span!("redis.command", db.operation = %command);
+
+        async {
+            let start = Instant::now();
+            let result = request.await;
+            metrics::observe_redis_command(&command, start.elapsed());
+            result
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(Self::traced(
+            command_name(cmd),
+            self.0.req_packed_command(cmd),
+        ))
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(Self::traced(
+            "PIPELINE".to_owned(),
+            self.0.req_packed_commands(cmd, offset, count),
+        ))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.0.get_db()
+    }
+}
 
 /// A [bb8::ManageConnection] for [redis::Client::get_multiplexed_async_connection].
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct RedisConnectionManager {
-    client: Client,
+    target: Target,
+    config: AsyncConnectionConfig,
+    connection_timeout: Option<Duration>,
 }
 
 impl RedisConnectionManager {
     /// Creates a new [RedisConnectionManager].
     /// See [redis::Client::open] for a description of the parameter types.
     pub fn new<T: IntoConnectionInfo>(info: T) -> Result<Self, RedisError> {
+        Self::with_family(info, AddressFamily::Any)
+    }
+
+    /// Creates a new [RedisConnectionManager] that only uses the given address family.
+    ///
+    /// The host name is resolved every time a new connection is established.
+    /// The address family preference is not applied to TLS connections
+    /// with certificate verification, as the certificate is issued for the host name.
+    pub fn with_family<T: IntoConnectionInfo>(
+        info: T,
+        family: AddressFamily,
+    ) -> Result<Self, RedisError> {
+        Ok(Self {
+            target: Target::Direct {
+                client: Client::open(info.into_connection_info()?)?,
+                family,
+            },
+            config: AsyncConnectionConfig::new(),
+            connection_timeout: None,
+        })
+    }
+
+    /// Creates a new [RedisConnectionManager] that connects to the master server
+    /// discovered via Redis Sentinel.
+    ///
+    /// # Arguments
+    ///
+    /// * `sentinels` : connection information of the Sentinel nodes
+    /// * `service_name` : name of the master group
+    /// * `node_info` : connection information used to connect to the master server
+    pub fn sentinel<T: IntoConnectionInfo>(
+        sentinels: Vec<T>,
+        service_name: &str,
+        node_info: SentinelNodeConnectionInfo,
+    ) -> Result<Self, RedisError> {
         Ok(Self {
-            client: Client::open(info.into_connection_info()?)?,
+            target: Target::Sentinel {
+                sentinel: Arc::new(Mutex::new(Sentinel::build(sentinels)?)),
+                service_name: service_name.to_owned(),
+                node_info,
+            },
+            config: AsyncConnectionConfig::new(),
+            connection_timeout: None,
         })
     }
+
+    /// Sets the timeout for establishing a new connection.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.config = self.config.set_connection_timeout(timeout);
+        self.connection_timeout = Some(timeout);
+        self
+    }
+
+    /// Returns a client of the server to connect to.
+    async fn client(&self) -> Result<Client, RedisError> {
+        match &self.target {
+            Target::Direct { client, family } => {
+                let info = client.get_connection_info();
+
+                let (host, port) = match &info.addr {
+                    ConnectionAddr::Tcp(host, port) if *family != AddressFamily::Any => {
+                        (host, *port)
+                    }
+                    ConnectionAddr::TcpTls {
+                        host,
+                        port,
+                        insecure: true,
+                        ..
+                    } if *family != AddressFamily::Any => (host, *port),
+                    _ => return Ok(client.to_owned()),
+                };
+
+                let ip = tokio::net::lookup_host((host.as_str(), port))
+                    .await?
+                    .map(|addr| addr.ip())
+                    .find(|ip| family.matches(ip))
+                    .ok_or_else(|| {
+                        RedisError::from((
+                            ErrorKind::IoError,
+                            "no address of the specified family",
+                            host.to_owned(),
+                        ))
+                    })?;
+
+                let addr = match &info.addr {
+                    ConnectionAddr::TcpTls {
+                        insecure,
+                        tls_params,
+                        ..
+                    } => ConnectionAddr::TcpTls {
+                        host: ip.to_string(),
+                        port,
+                        insecure: *insecure,
+                        tls_params: tls_params.clone(),
+                    },
+                    _ => ConnectionAddr::Tcp(ip.to_string(), port),
+                };
+
+                Client::open(ConnectionInfo {
+                    addr,
+                    redis: info.redis.clone(),
+                })
+            }
+            Target::Sentinel {
+                sentinel,
+                service_name,
+                node_info,
+            } => {
+                sentinel
+                    .lock()
+                    .await
+                    .async_master_for(service_name, Some(node_info))
+                    .await
+            }
+        }
+    }
+
+    /// Establishes a new connection.
+    async fn connect_multiplexed(&self) -> Result<MultiplexedConnection, RedisError> {
+        self.client()
+            .await?
+            .get_multiplexed_async_connection_with_config(&self.config)
+            .await
+    }
+
+    /// Establishes a new connection dedicated to receiving Pub/Sub messages.
+    pub async fn pubsub(&self) -> Result<PubSub, RedisError> {
+        let connect = async { self.client().await?.get_async_pubsub().await };
+
+        match self.connection_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect)
+                .await
+                .map_err(|_| RedisError::from((ErrorKind::IoError, "connection timed out")))?,
+            None => connect.await,
+        }
+    }
 }
 
 impl ManageConnection for RedisConnectionManager {
-    type Connection = MultiplexedConnection;
+    type Connection = RedisConnection;
     type Error = RedisError;
 
     async fn connect(&self) -> Result<Self::Connection, Self::Error> {
-        self.client.get_multiplexed_async_connection().await
+        self.connect_multiplexed().await.map(RedisConnection)
     }
 
     async fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        if let Target::Sentinel { .. } = self.target {
+            // the master server may have been demoted to a replica
+            let role: Vec<redis::Value> = redis::cmd("ROLE").query_async(conn).await?;
+            return match role.first().map(redis::from_redis_value::<String>) {
+                Some(Ok(role)) if role == "master" => Ok(()),
+                _ => Err((ErrorKind::ResponseError, "not a master server").into()),
+            };
+        }
+
         let pong: String = redis::cmd("PING").query_async(conn).await?;
         match pong.as_str() {
             "PONG" => Ok(()),
@@ -44,36 +286,119 @@ impl ManageConnection for RedisConnectionManager {
 
 static CONN_POOL: OnceCell<Pool<RedisConnectionManager>> = OnceCell::const_new();
 
-async fn init_conn_pool() -> Result<(), RedisError> {
-    let redis_url = {
-        let mut params = vec!["redis://".to_owned()];
-
-        let redis = if let Some(cache_server) = &CONFIG.cache_server {
-            cache_server
-        } else {
-            &CONFIG.redis
-        };
+/// Returns the Redis server (or the cache server, if specified) configuration.
+fn redis_config() -> &'static RedisConfig {
+    CONFIG.cache_server.as_ref().unwrap_or(&CONFIG.redis)
+}
 
-        if let Some(user) = &redis.user {
-            params.push(user.to_owned())
-        }
-        if let Some(pass) = &redis.pass {
-            params.push(format!(":{}@", urlencoding::encode(pass)))
+fn connection_info(config: &RedisConfig) -> ConnectionInfo {
+    let addr = if let Some(path) = &config.path {
+        ConnectionAddr::Unix(PathBuf::from(path))
+    } else if let Some(tls) = &config.tls {
+        ConnectionAddr::TcpTls {
+            host: config.host.to_owned(),
+            port: config.port,
+            insecure: !tls.reject_unauthorized,
+            tls_params: None,
         }
-        params.push(redis.host.to_owned());
-        params.push(format!(":{}", redis.port));
-        params.push(format!("/{}", redis.db));
+    } else {
+        ConnectionAddr::Tcp(config.host.to_owned(), config.port)
+    };
+
+    ConnectionInfo {
+        addr,
+        redis: RedisConnectionInfo {
+            db: config.db.into(),
+            username: config.user.to_owned(),
+            password: config.pass.to_owned(),
+            ..Default::default()
+        },
+    }
+}
+
+pub(super) fn connection_manager(
+    config: &RedisConfig,
+) -> Result<RedisConnectionManager, RedisError> {
+    let info = connection_info(config);
+
+    let manager = if let Some(sentinel) = &config.sentinel {
+        let sentinels: Vec<ConnectionInfo> = sentinel
+            .nodes
+            .iter()
+            .map(|node| ConnectionInfo {
+                addr: match &config.tls {
+                    Some(tls) => ConnectionAddr::TcpTls {
+                        host: node.host.to_owned(),
+                        port: node.port,
+                        insecure: !tls.reject_unauthorized,
+                        tls_params: None,
+                    },
+                    None => ConnectionAddr::Tcp(node.host.to_owned(), node.port),
+                },
+                redis: RedisConnectionInfo {
+                    username: sentinel.user.to_owned(),
+                    password: sentinel.pass.to_owned(),
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        let node_info = SentinelNodeConnectionInfo {
+            tls_mode: config
+                .tls
+                .as_ref()
+                .map(|tls| match tls.reject_unauthorized {
+                    true => TlsMode::Secure,
+                    false => TlsMode::Insecure,
+                }),
+            redis_connection_info: Some(info.redis),
+        };
 
-        params.concat()
+        RedisConnectionManager::sentinel(sentinels, &sentinel.name, node_info)?
+    } else {
+        let family = match config.family {
+            Some(4) => AddressFamily::Ipv4,
+            Some(6) => AddressFamily::Ipv6,
+            _ => AddressFamily::Any,
+        };
+        RedisConnectionManager::with_family(info, family)?
     };
 
+    Ok(match config.connect_timeout {
+        Some(timeout) => manager.connection_timeout(Duration::from_millis(timeout.into())),
+        None => manager,
+    })
+}
+
+async fn init_conn_pool() -> Result<(), RedisError> {
+    let config = redis_config();
+
     tracing::info!("initializing connection manager");
-    let manager = RedisConnectionManager::new(redis_url)?;
+    let manager = connection_manager(config)?;
 
     CONN_POOL
         .get_or_try_init(|| async {
             tracing::info!("creating connection pool");
-            Pool::builder().build(manager).await
+
+            let mut builder = Pool::builder();
+
+            if let Some(max_connections) = config.max_connections {
+                builder = builder.max_size(max_connections);
+            }
+            if let Some(min_connections) = config.min_connections {
+                builder = builder.min_idle(min_connections);
+            }
+            if let Some(timeout) = config.acquire_timeout {
+                builder = builder.connection_timeout(Duration::from_millis(timeout.into()));
+            }
+            if let Some(timeout) = config.idle_timeout {
+                builder = builder.idle_timeout(Duration::from_millis(timeout.into()));
+            }
+            if let Some(lifetime) = config.max_lifetime {
+                builder = builder.max_lifetime(Duration::from_millis(lifetime.into()));
+            }
+
+            builder.build(manager).await
         })
         .await?;
 
@@ -107,17 +432,113 @@ pub async fn get_conn() -> Result<PooledConnection<'static, RedisConnectionManag
         .map_err(RedisConnError::Bb8Pool)
 }
 
+/// Returns a new [PubSub] connection, which is not managed by the connection pool.
+pub async fn get_pubsub() -> Result<PubSub, RedisError> {
+    connection_manager(redis_config())?.pubsub().await
+}
+
+/// Returns the state of the connection pool if it has been initialized.
+pub(super) fn pool_state() -> Option<bb8::State> {
+    CONN_POOL.get().map(|pool| pool.state())
+}
+
 /// prefix Redis key
 #[inline]
 pub fn key(key: impl std::fmt::Display) -> String {
     format!("{}:{}", CONFIG.redis_key_prefix, key)
 }
 
+/// Increments the counter at `key` and returns the count in the current window,
+/// which starts at the first increment and lasts for `window` seconds.
+pub async fn count_in_window(
+    conn: &mut impl ConnectionLike,
+    key: &str,
+    window: u64,
+) -> Result<u32, RedisError> {
+    // the counter is created with the expiration, so it never outlives the window
+    let (count,): (u32,) = redis::pipe()
+        .atomic()
+        .set_options(
+            key,
+            0,
+            SetOptions::default()
+                .conditional_set(ExistenceCheck::NX)
+                .with_expiration(SetExpiry::EX(window)),
+        )
+        .ignore()
+        .incr(key, 1)
+        .query_async(conn)
+        .await?;
+
+    Ok(count)
+}
+
 #[cfg(test)]
 mod unit_test {
-    use super::get_conn;
+    use super::{connection_info, count_in_window, get_conn};
+    use crate::config::server::RedisConfig;
     use pretty_assertions::assert_eq;
-    use redis::AsyncCommands;
+    use redis::{AsyncCommands, ConnectionAddr};
+    use std::path::PathBuf;
+
+    #[test]
+    fn build_connection_info() {
+        let config: RedisConfig = serde_yaml::from_str(
+            r#"
+            host: localhost
+            port: 6379
+            user: firefish
+            db: 2
+            "#,
+        )
+        .unwrap();
+        let info = connection_info(&config);
+
+        assert_eq!(info.addr, ConnectionAddr::Tcp("localhost".to_owned(), 6379));
+        assert_eq!(info.redis.db, 2);
+        assert_eq!(info.redis.username.as_deref(), Some("firefish"));
+        assert_eq!(info.redis.password, None);
+
+        let config: RedisConfig = serde_yaml::from_str(
+            r#"
+            host: redis.example.com
+            port: 6380
+            pass: p@ss:word
+            tls:
+              host: redis.example.com
+              rejectUnauthorized: false
+            "#,
+        )
+        .unwrap();
+        let info = connection_info(&config);
+
+        assert_eq!(
+            info.addr,
+            ConnectionAddr::TcpTls {
+                host: "redis.example.com".to_owned(),
+                port: 6380,
+                insecure: true,
+                tls_params: None,
+            }
+        );
+        assert_eq!(info.redis.db, 0);
+        assert_eq!(info.redis.username, None);
+        assert_eq!(info.redis.password.as_deref(), Some("p@ss:word"));
+
+        let config: RedisConfig = serde_yaml::from_str(
+            r#"
+            host: localhost
+            port: 6379
+            path: /run/redis/redis.sock
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            connection_info(&config).addr,
+            ConnectionAddr::Unix(PathBuf::from("/run/redis/redis.sock"))
+        );
+    }
 
     #[tokio::test]
     #[cfg_attr(miri, ignore)] // can't call foreign function `getaddrinfo` on OS `linux`
@@ -164,4 +585,19 @@ mod unit_test {
         assert_eq!(redis.get::<&str, String>(key).await.unwrap(), value);
         assert_eq!(redis.del::<&str, u32>(key).await.unwrap(), 1);
     }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // can't call foreign function `getaddrinfo` on OS `linux`
+    async fn count_requests_in_window() {
+        let mut redis = get_conn().await.unwrap();
+
+        let key = "CARGO_UNIT_TEST_COUNTER";
+        redis.del::<&str, ()>(key).await.unwrap();
+
+        assert_eq!(count_in_window(&mut *redis, key, 60).await.unwrap(), 1);
+        assert_eq!(count_in_window(&mut *redis, key, 60).await.unwrap(), 2);
+        assert!((1..=60).contains(&redis.ttl::<&str, i64>(key).await.unwrap()));
+
+        redis.del::<&str, ()>(key).await.unwrap();
+    }
 }
@@ -2,29 +2,24 @@
 //!
 //! ref: <https://nodeinfo.diaspora.software/protocol.html>
 
-use crate::{federation::nodeinfo::schema::*, misc::is_safe_url::is_safe_url, util::http_client};
-use futures_util::io::AsyncReadExt;
-use isahc::AsyncReadResponseExt;
+use crate::{
+    federation::nodeinfo::schema::*,
+    util::http_client::{self, FetchOptions},
+};
+use isahc::Request;
 use serde::Deserialize;
 
 /// Errors that can occur while fetching NodeInfo from a remote server
 #[error_doc::errors]
 pub enum Error {
-    #[error("failed to acquire an HTTP client")]
-    HttpClient(#[from] http_client::Error),
     #[error("HTTP request failed")]
-    Http(#[from] isahc::Error),
-    #[doc = "Bad HTTP status"]
-    #[error("bad HTTP status ({0})")]
-    BadStatus(String),
-    #[error("failed to parse HTTP response body as text")]
-    Response(#[from] std::io::Error),
+    Fetch(#[from] http_client::FetchError),
+    #[error("invalid HTTP request")]
+    Request(#[from] isahc::http::Error),
     #[error("failed to parse HTTP response body as json")]
     Json(#[from] serde_json::Error),
     #[error("nodeinfo is missing")]
     MissingNodeinfo,
-    #[error("access to this URL is not allowed")]
-    UnsafeUrl,
 }
 
 /// Represents the schema of `/.well-known/nodeinfo`.
@@ -42,29 +37,18 @@ pub struct NodeinfoLink {
 
 /// Fetches `/.well-known/nodeinfo` and parses the result.
 async fn fetch_nodeinfo_links(host: &str) -> Result<NodeinfoLinks, Error> {
-    let client = http_client::client()?;
     let wellknown_url = format!("https://{}/.well-known/nodeinfo", host);
 
-    if !is_safe_url(&wellknown_url) {
-        return Err(Error::UnsafeUrl);
-    }
-
-    let wellknown_response = client.get_async(&wellknown_url).await?;
-
-    if !wellknown_response.status().is_success() {
-        return Err(Error::BadStatus(format!(
-            "{} returned {}",
-            wellknown_url,
-            wellknown_response.status()
-        )));
-    }
-
     // Read up to 1 MiB of the response body
-    let text = wellknown_response
-        .map(|body| body.take(1024 * 1024))
-        .text()
-        .await?;
-    Ok(serde_json::from_str(&text)?)
+    let options = FetchOptions {
+        max_body_size: 1024 * 1024,
+        ..Default::default()
+    };
+    let response = http_client::fetch(Request::get(wellknown_url).body(Vec::new())?, &options)
+        .await?
+        .error_for_status()?;
+
+    Ok(response.json()?)
 }
 
 /// Check if any of the following relations is present in the given [NodeinfoLinks].
@@ -84,19 +68,17 @@ fn check_nodeinfo_link(links: NodeinfoLinks) -> Result<String, Error> {
 
 /// Fetches the nodeinfo from the given URL and parses the result.
 async fn fetch_nodeinfo_impl(nodeinfo_link: &str) -> Result<Nodeinfo20, Error> {
-    let client = http_client::client()?;
-    let mut response = client.get_async(nodeinfo_link).await?;
-
-    if !response.status().is_success() {
-        tracing::debug!("{:#?}", response.body());
-        return Err(Error::BadStatus(format!(
-            "{} returned {}",
-            nodeinfo_link,
-            response.status()
-        )));
+    let response = http_client::fetch(
+        Request::get(nodeinfo_link).body(Vec::new())?,
+        &FetchOptions::default(),
+    )
+    .await?;
+
+    if !response.status.is_success() {
+        tracing::debug!("{}", response.text());
     }
 
-    Ok(serde_json::from_str(&response.text().await?)?)
+    Ok(response.error_for_status()?.json()?)
 }
 
 // for napi export
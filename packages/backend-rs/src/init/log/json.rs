@@ -0,0 +1,207 @@
+//! JSON lines log format
+
+use crate::util::error_chain::error_messages;
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::fmt;
+use tracing::{field::Field, span::Record, Event, Subscriber};
+use tracing_subscriber::{
+    field::{RecordFields, Visit},
+    fmt::{
+        format::{FormatEvent, FormatFields, Writer},
+        FmtContext, FormattedFields,
+    },
+    registry::LookupSpan,
+};
+
+/// Collects fields into a JSON object
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0
+            .insert(field.name().to_owned(), error_messages(value).into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_owned(), format!("{:?}", value).into());
+    }
+}
+
+/// Formats span fields as JSON objects
+pub(super) struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor::default();
+        fields.record(&mut visitor);
+
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &Record<'_>,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor(serde_json::from_str(&current.fields).unwrap_or_default());
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+
+        Ok(())
+    }
+}
+
+/// Formats events as JSON lines
+///
+/// ```json
+/// {"timestamp":"2024-01-01T00:00:00.000000Z","level":"INFO","target":"backend_rs::init::greet","line":20,"threadName":"main","fields":{"message":"Firefish is starting"},"spans":[]}
+/// ```
+pub(super) struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+
+        let mut fields = JsonVisitor::default();
+        event.record(&mut fields);
+
+        let spans: Vec<Value> = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let extensions = span.extensions();
+                let fields = extensions
+                    .get::<FormattedFields<N>>()
+                    .and_then(|fields| serde_json::from_str(&fields.fields).ok())
+                    .unwrap_or_else(|| Value::Object(Map::new()));
+
+                serde_json::json!({ "name": span.name(), "fields": fields })
+            })
+            .collect();
+
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_owned(),
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::Micros, true)
+                .into(),
+        );
+        line.insert("level".to_owned(), metadata.level().as_str().into());
+        line.insert("target".to_owned(), metadata.target().into());
+        if let Some(number) = metadata.line() {
+            line.insert("line".to_owned(), number.into());
+        }
+        if let Some(name) = std::thread::current().name() {
+            line.insert("threadName".to_owned(), name.into());
+        }
+        line.insert("fields".to_owned(), Value::Object(fields.0));
+        line.insert("spans".to_owned(), Value::Array(spans));
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{JsonFields, JsonFormat};
+    use pretty_assertions::assert_eq;
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+    use tracing_subscriber::{fmt, layer::SubscriberExt};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    #[error("failed to connect")]
+    struct OuterError(#[source] io::Error);
+
+    #[test]
+    fn format_json_lines() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            fmt::layer()
+                .event_format(JsonFormat)
+                .fmt_fields(JsonFields)
+                .with_writer(move || writer.clone()),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", id = 42);
+            let _enter = span.enter();
+            span.record("id", 43);
+
+            let error = OuterError(io::Error::other("connection refused"));
+            tracing::warn!(error = &error as &dyn std::error::Error, "bad server");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(output.trim_end()).unwrap();
+
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], module_path!());
+        assert_eq!(
+            line["fields"],
+            serde_json::json!({
+                "message": "bad server",
+                "error": ["failed to connect", "connection refused"],
+            })
+        );
+        assert_eq!(
+            line["spans"],
+            serde_json::json!([{ "name": "request", "fields": { "id": 43 } }])
+        );
+    }
+}
@@ -0,0 +1,304 @@
+mod json;
+mod otlp;
+mod syslog;
+
+pub use otlp::shutdown_telemetry;
+
+use crate::config::{reload::runtime_config, server::LogFileConfig, CONFIG};
+use crate::util::error_chain::format_error;
+use std::{path::Path, sync::OnceLock};
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{
+    filter::{Directive, EnvFilter},
+    fmt,
+    layer::Layered,
+    prelude::*,
+    reload, Layer, Registry,
+};
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+type Subscriber = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+type BoxedLayer = Box<dyn Layer<Subscriber> + Send + Sync>;
+
+/// Returns the maximum log level specified by `max_log_level` (or deprecated `log_level`).
+fn max_level() -> LevelFilter {
+    if let Some(max_level) = &runtime_config().max_log_level {
+        match max_level.as_str() {
+            "error" => LevelFilter::ERROR,
+            "warn" | "warning" => LevelFilter::WARN,
+            "info" => LevelFilter::INFO,
+            "debug" => LevelFilter::DEBUG,
+            "trace" => LevelFilter::TRACE,
+            _ => LevelFilter::INFO, // Fallback
+        }
+    } else if let Some(levels) = &CONFIG.log_level {
+        // `logLevel` config is Deprecated
+        if levels.contains(&"trace".to_owned()) {
+            LevelFilter::TRACE
+        } else if levels.contains(&"debug".to_owned()) {
+            LevelFilter::DEBUG
+        } else if levels.contains(&"info".to_owned()) {
+            LevelFilter::INFO
+        } else if levels.contains(&"warning".to_owned()) {
+            LevelFilter::WARN
+        } else if levels.contains(&"error".to_owned()) {
+            LevelFilter::ERROR
+        } else {
+            // Fallback
+            LevelFilter::INFO
+        }
+    } else {
+        // Fallback
+        LevelFilter::INFO
+    }
+}
+
+/// Parses comma-separated filter directives.
+fn parse_directives(directives: &str, source: &str, errors: &mut Vec<String>) -> Vec<Directive> {
+    directives
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .filter_map(|directive| match directive.parse() {
+            Ok(directive) => Some(directive),
+            Err(err) => {
+                errors.push(format!(
+                    "ignored invalid log filter directive `{}` in {}: {}",
+                    directive, source, err
+                ));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the log filter directives in ascending order of precedence
+/// (`max_level`, `log_filter`, and then `rust_log`) and the errors in them.
+fn directives(
+    max_level: LevelFilter,
+    log_filter: Option<&str>,
+    rust_log: Option<&str>,
+) -> (Vec<Directive>, Vec<String>) {
+    let mut directives = vec![Directive::from(max_level)];
+    let mut errors = Vec::new();
+
+    if let Some(log_filter) = log_filter {
+        directives.extend(parse_directives(log_filter, "`logFilter`", &mut errors));
+    }
+    if let Some(rust_log) = rust_log {
+        directives.extend(parse_directives(
+            rust_log,
+            EnvFilter::DEFAULT_ENV,
+            &mut errors,
+        ));
+    }
+
+    (directives, errors)
+}
+
+/// Builds the log filter from `max_log_level`, `log_filter`, and the `RUST_LOG` environment variable.
+fn env_filter() -> (EnvFilter, Vec<String>) {
+    let rust_log = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+    let (directives, errors) = directives(
+        max_level(),
+        runtime_config().log_filter.as_deref(),
+        rust_log.as_deref(),
+    );
+
+    let filter = directives
+        .into_iter()
+        .fold(EnvFilter::default(), |filter, directive| {
+            filter.add_directive(directive)
+        });
+
+    (filter, errors)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Compact,
+    Pretty,
+    Json,
+}
+
+impl Format {
+    /// Returns the log format specified by `log_format`.
+    fn from_config() -> Self {
+        match CONFIG.log_format.as_deref() {
+            Some("pretty") => Self::Pretty,
+            Some("json") => Self::Json,
+            _ => Self::Compact, // Fallback
+        }
+    }
+}
+
+/// Creates a formatting layer that writes logs to `writer`.
+///
+/// Timestamps are omitted from the compact format (and syslog messages, which have their own),
+/// as they are usually added by the process manager.
+fn fmt_layer<W>(format: Format, writer: W, ansi: bool, with_time: bool) -> BoxedLayer
+where
+    W: for<'writer> fmt::MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
+        .with_level(true)
+        .with_ansi(ansi)
+        .with_target(true)
+        .with_thread_names(true)
+        .with_line_number(true)
+        .log_internal_errors(true)
+        .with_writer(writer);
+
+    match (format, with_time) {
+        (Format::Json, _) => layer
+            .event_format(json::JsonFormat)
+            .fmt_fields(json::JsonFields)
+            .boxed(),
+        (Format::Pretty, true) => layer.pretty().boxed(),
+        (Format::Pretty, false) => layer.pretty().without_time().boxed(),
+        (Format::Compact, true) => layer.compact().boxed(),
+        (Format::Compact, false) => layer.compact().without_time().boxed(),
+    }
+}
+
+#[error_doc::errors]
+enum LogFileError {
+    #[error("unknown rotation `{0}` (expected `minutely`, `hourly`, `daily`, or `never`)")]
+    UnknownRotation(String),
+    #[error("failed to create the log file")]
+    Create(#[from] tracing_appender::rolling::InitError),
+}
+
+/// Opens the log file specified by `log_file`.
+fn rolling_file(config: &LogFileConfig) -> Result<RollingFileAppender, LogFileError> {
+    let rotation = match config.rotation.as_deref() {
+        Some("minutely") => Rotation::MINUTELY,
+        Some("hourly") => Rotation::HOURLY,
+        None | Some("daily") => Rotation::DAILY,
+        Some("never") => Rotation::NEVER,
+        Some(rotation) => return Err(LogFileError::UnknownRotation(rotation.to_owned())),
+    };
+
+    let path = Path::new(&config.path);
+    let directory = path.parent().unwrap_or(Path::new("."));
+
+    let mut builder = RollingFileAppender::builder().rotation(rotation);
+    if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
+        builder = builder.filename_prefix(file_name);
+    }
+    if let Some(max_files) = config.max_files {
+        builder = builder.max_log_files(max_files as usize);
+    }
+
+    Ok(builder.build(directory)?)
+}
+
+/// Initializes the [tracing] logger.
+#[macros::export(js_name = "initializeRustLogger")]
+pub fn initialize_logger() {
+    let (filter, mut init_errors) = env_filter();
+    let (filter, handle) = reload::Layer::new(filter);
+    let format = Format::from_config();
+
+    let mut layers: Vec<BoxedLayer> = vec![fmt_layer(
+        format,
+        std::io::stdout,
+        format != Format::Json,
+        format != Format::Compact,
+    )];
+
+    if let Some(config) = &CONFIG.log_file {
+        match rolling_file(config) {
+            Ok(file) => layers.push(fmt_layer(format, file, false, true)),
+            Err(err) => init_errors.push(format!(
+                "failed to open the log file ({}):\n{}",
+                config.path,
+                format_error(&err)
+            )),
+        }
+    }
+
+    if let Some(config) = &CONFIG.syslog {
+        match syslog::Syslog::new(config) {
+            Ok(syslog) => layers.push(fmt_layer(format, syslog, false, false)),
+            Err(err) => init_errors.push(format!(
+                "failed to set up syslog ({}:{}):\n{}",
+                config.host,
+                config.port,
+                format_error(&err)
+            )),
+        }
+    }
+
+    if let Some(config) = &CONFIG.telemetry {
+        match otlp::layer(config) {
+            Ok(Some(layer)) => layers.push(layer),
+            Ok(None) => {}
+            Err(err) => init_errors.push(format!(
+                "failed to set up the trace exporter:\n{}",
+                format_error(&err)
+            )),
+        }
+    }
+
+    let subscriber = tracing_subscriber::registry().with(filter).with(layers);
+
+    tracing::subscriber::set_global_default(subscriber).expect("Failed to initialize the logger");
+
+    FILTER_HANDLE.get_or_init(|| handle);
+
+    for err in init_errors {
+        tracing::error!("{}", err);
+    }
+}
+
+/// Applies the current `max_log_level` and `log_filter` to the logger.
+pub(crate) fn update_filter() {
+    if let Some(handle) = FILTER_HANDLE.get() {
+        let (filter, errors) = env_filter();
+        if let Err(err) = handle.reload(filter) {
+            tracing::warn!("failed to update the log filter: {}", err);
+        }
+        for err in errors {
+            tracing::warn!("{}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::directives;
+    use pretty_assertions::assert_eq;
+    use tracing::level_filters::LevelFilter;
+
+    #[test]
+    fn build_directives() {
+        let (filter, errors) = directives(
+            LevelFilter::DEBUG,
+            Some("backend_rs::federation=trace, sea_orm=warn"),
+            Some("sqlx=error,sea_orm=info,=bogus=="),
+        );
+
+        assert_eq!(
+            filter.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "debug",
+                "backend_rs::federation=trace",
+                "sea_orm=warn",
+                "sqlx=error",
+                "sea_orm=info"
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("`=bogus==` in RUST_LOG"));
+
+        let (fallback, errors) = directives(LevelFilter::INFO, None, None);
+        assert_eq!(
+            fallback.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["info"]
+        );
+        assert!(errors.is_empty());
+    }
+}
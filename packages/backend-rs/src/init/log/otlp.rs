@@ -0,0 +1,72 @@
+//! OpenTelemetry trace exporter
+
+use super::BoxedLayer;
+use crate::config::server::TelemetryConfig;
+use opentelemetry::{global, trace::TracerProvider};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    trace::{Sampler, SdkTracerProvider},
+    Resource,
+};
+use std::sync::OnceLock;
+use tracing_subscriber::Layer;
+
+static TRACER_PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+#[error_doc::errors]
+pub enum Error {
+    #[error("failed to build the OTLP exporter")]
+    Exporter(#[from] opentelemetry_otlp::ExporterBuildError),
+}
+
+/// Creates a layer that exports spans to the OTLP endpoint.
+///
+/// Returns [None] if `otlp_endpoint` is not set.
+pub(super) fn layer(config: &TelemetryConfig) -> Result<Option<BoxedLayer>, Error> {
+    let Some(endpoint) = &config.otlp_endpoint else {
+        return Ok(None);
+    };
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio.unwrap_or(1.0),
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(
+                    config
+                        .service_name
+                        .clone()
+                        .unwrap_or_else(|| "firefish".to_owned()),
+                )
+                .build(),
+        )
+        .build();
+
+    let tracer = provider.tracer("backend-rs");
+    global::set_tracer_provider(provider.clone());
+    TRACER_PROVIDER.get_or_init(|| provider);
+
+    Ok(Some(
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_threads(true)
+            .boxed(),
+    ))
+}
+
+/// Exports the remaining spans and stops the trace exporter.
+#[macros::export]
+pub fn shutdown_telemetry() {
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        if let Err(err) = provider.shutdown() {
+            tracing::warn!("failed to shut down the trace exporter: {}", err);
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! RFC 5424 syslog sink
+
+use crate::config::server::SysLogConfig;
+use chrono::{SecondsFormat, Utc};
+use std::{
+    io::{self, Write},
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    sync::mpsc::{sync_channel, SyncSender},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{Level, Metadata};
+use tracing_subscriber::fmt::MakeWriter;
+
+const APP_NAME: &str = "firefish";
+const TCP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum number of log lines waiting to be sent
+const QUEUE_SIZE: usize = 1024;
+/// Initial time to wait before reconnecting to the server
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+/// Maximum time to wait before reconnecting to the server
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[error_doc::errors]
+pub enum Error {
+    #[error("failed to connect to the syslog server")]
+    Io(#[from] io::Error),
+    #[error("unknown syslog protocol `{0}` (expected `udp` or `tcp`)")]
+    UnknownProtocol(String),
+    #[error("unknown syslog facility `{0}`")]
+    UnknownFacility(String),
+}
+
+enum Transport {
+    Udp(UdpSocket),
+    Tcp(TcpTransport),
+}
+
+struct TcpTransport {
+    address: (String, u16),
+    stream: Option<TcpStream>,
+    /// log lines are dropped until this time after failing to connect
+    retry_at: Option<Instant>,
+    backoff: Duration,
+}
+
+/// Sends each log line to a syslog server
+///
+/// Log lines are sent by a background thread, so that logging never waits for the
+/// syslog server. Log lines are dropped if the queue is full or the server is down.
+pub(super) struct Syslog {
+    sender: SyncSender<Vec<u8>>,
+    facility: u8,
+    hostname: String,
+    pid: u32,
+}
+
+/// Returns the facility code (see RFC 5424 section 6.2.1).
+fn facility_code(name: &str) -> Option<u8> {
+    let code = match name {
+        "kern" => 0,
+        "user" => 1,
+        "mail" => 2,
+        "daemon" => 3,
+        "auth" => 4,
+        "syslog" => 5,
+        "lpr" => 6,
+        "news" => 7,
+        "uucp" => 8,
+        "cron" => 9,
+        "authpriv" => 10,
+        "ftp" => 11,
+        _ => {
+            let n: u8 = name.strip_prefix("local")?.parse().ok()?;
+            return (n <= 7).then_some(16 + n);
+        }
+    };
+    Some(code)
+}
+
+/// Returns the severity code (see RFC 5424 section 6.2.1).
+fn severity_code(level: &Level) -> u8 {
+    match *level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 6,
+        Level::DEBUG | Level::TRACE => 7,
+    }
+}
+
+/// Formats a syslog message (see RFC 5424 section 6).
+fn format_message(
+    facility: u8,
+    level: &Level,
+    hostname: &str,
+    pid: u32,
+    message: &[u8],
+) -> Vec<u8> {
+    let mut packet = format!(
+        "<{}>1 {} {} {} {} - - ",
+        facility * 8 + severity_code(level),
+        Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+        hostname,
+        APP_NAME,
+        pid,
+    )
+    .into_bytes();
+    packet.extend_from_slice(message.trim_ascii_end());
+
+    packet
+}
+
+impl Syslog {
+    pub(super) fn new(config: &SysLogConfig) -> Result<Self, Error> {
+        let facility = match config.facility.as_deref() {
+            Some(name) => {
+                facility_code(name).ok_or_else(|| Error::UnknownFacility(name.to_owned()))?
+            }
+            None => 1,
+        };
+
+        let address = (config.host.to_owned(), config.port);
+        let transport = match config.protocol.as_deref() {
+            None | Some("udp") => {
+                let target = address.to_socket_addrs()?.next().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, "failed to resolve the host")
+                })?;
+                let socket = match target.is_ipv4() {
+                    true => UdpSocket::bind(("0.0.0.0", 0))?,
+                    false => UdpSocket::bind(("::", 0))?,
+                };
+                socket.connect(target)?;
+                Transport::Udp(socket)
+            }
+            Some("tcp") => Transport::Tcp(TcpTransport {
+                address,
+                stream: None,
+                retry_at: None,
+                backoff: MIN_BACKOFF,
+            }),
+            Some(protocol) => return Err(Error::UnknownProtocol(protocol.to_owned())),
+        };
+
+        // HOSTNAME must consist of printable ASCII characters
+        let hostname = sysinfo::System::host_name()
+            .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_graphic()))
+            .unwrap_or_else(|| "-".to_owned());
+
+        let (sender, receiver) = sync_channel::<Vec<u8>>(QUEUE_SIZE);
+        thread::Builder::new()
+            .name("syslog".to_owned())
+            .spawn(move || {
+                let mut transport = transport;
+                for packet in receiver {
+                    // logging the error here would cause an infinite loop
+                    let _ = transport.send(&packet);
+                }
+            })?;
+
+        Ok(Self {
+            sender,
+            facility,
+            hostname,
+            pid: std::process::id(),
+        })
+    }
+
+    /// Queues the log line, which is dropped if the queue is full.
+    fn send(&self, level: &Level, message: &[u8]) {
+        let packet = format_message(self.facility, level, &self.hostname, self.pid, message);
+        let _ = self.sender.try_send(packet);
+    }
+}
+
+impl Transport {
+    fn send(&mut self, packet: &[u8]) -> io::Result<()> {
+        match self {
+            Transport::Udp(socket) => socket.send(packet).map(|_| ()),
+            Transport::Tcp(tcp) => {
+                // octet-counting framing (see RFC 6587 section 3.4.1)
+                let mut frame = format!("{} ", packet.len()).into_bytes();
+                frame.extend_from_slice(packet);
+
+                // reconnect once if the connection has been closed
+                for _ in 0..2 {
+                    let stream = tcp.connect()?;
+                    match stream.write_all(&frame) {
+                        Ok(()) => return Ok(()),
+                        Err(_) => tcp.stream = None,
+                    }
+                }
+
+                Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "failed to send the log to the syslog server",
+                ))
+            }
+        }
+    }
+}
+
+impl TcpTransport {
+    /// Returns the connection to the server, connecting to it if disconnected.
+    ///
+    /// Once connecting fails, this fails without connecting until the backoff elapses.
+    fn connect(&mut self) -> io::Result<&mut TcpStream> {
+        if self.stream.is_none() {
+            if self
+                .retry_at
+                .is_some_and(|retry_at| Instant::now() < retry_at)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "waiting to reconnect to the syslog server",
+                ));
+            }
+
+            let connected = self
+                .address
+                .to_socket_addrs()?
+                .find_map(|addr| TcpStream::connect_timeout(&addr, TCP_TIMEOUT).ok());
+
+            let Some(connected) = connected else {
+                self.retry_at = Some(Instant::now() + self.backoff);
+                self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    "failed to connect to the syslog server",
+                ));
+            };
+
+            connected.set_write_timeout(Some(TCP_TIMEOUT))?;
+            self.retry_at = None;
+            self.backoff = MIN_BACKOFF;
+            self.stream = Some(connected);
+        }
+
+        Ok(self
+            .stream
+            .as_mut()
+            .expect("the stream should be connected"))
+    }
+}
+
+/// Buffers a log line and sends it to the syslog server on drop
+pub(super) struct SyslogWriter<'a> {
+    syslog: &'a Syslog,
+    level: Level,
+    buffer: Vec<u8>,
+}
+
+impl Write for SyslogWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for SyslogWriter<'_> {
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() {
+            self.syslog.send(&self.level, &self.buffer);
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for Syslog {
+    type Writer = SyslogWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SyslogWriter {
+            syslog: self,
+            level: Level::INFO,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        SyslogWriter {
+            syslog: self,
+            level: *meta.level(),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{facility_code, format_message, TcpTransport, MIN_BACKOFF};
+    use pretty_assertions::assert_eq;
+    use std::{io, net::TcpListener};
+    use tracing::Level;
+
+    #[test]
+    fn facility() {
+        assert_eq!(facility_code("user"), Some(1));
+        assert_eq!(facility_code("daemon"), Some(3));
+        assert_eq!(facility_code("local0"), Some(16));
+        assert_eq!(facility_code("local7"), Some(23));
+        assert_eq!(facility_code("local8"), None);
+        assert_eq!(facility_code("unknown"), None);
+    }
+
+    #[test]
+    fn rfc5424_message() {
+        let message = format_message(3, &Level::WARN, "example", 1234, b"something happened\n");
+        let message = String::from_utf8(message).unwrap();
+
+        // <PRI>VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG
+        let parts: Vec<&str> = message.splitn(8, ' ').collect();
+        assert_eq!(parts[0], "<28>1");
+        assert!(chrono::DateTime::parse_from_rfc3339(parts[1]).is_ok());
+        assert_eq!(
+            parts[2..],
+            [
+                "example",
+                "firefish",
+                "1234",
+                "-",
+                "-",
+                "something happened"
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // can't call foreign function `socket` on OS `linux`
+    fn reconnect_backoff() {
+        // find a closed port
+        let port = TcpListener::bind(("127.0.0.1", 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let mut tcp = TcpTransport {
+            address: ("127.0.0.1".to_owned(), port),
+            stream: None,
+            retry_at: None,
+            backoff: MIN_BACKOFF,
+        };
+
+        let err = tcp.connect().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        assert_eq!(tcp.backoff, MIN_BACKOFF * 2);
+
+        // doesn't try to connect until the backoff elapses
+        let err = tcp.connect().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+        assert_eq!(tcp.backoff, MIN_BACKOFF * 2);
+    }
+}
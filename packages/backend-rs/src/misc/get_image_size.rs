@@ -1,8 +1,10 @@
-use crate::{cache, misc::is_safe_url::is_safe_url, util::http_client};
+use crate::{
+    cache,
+    util::http_client::{self, FetchOptions},
+};
 use chrono::Duration;
-use futures_util::AsyncReadExt;
 use image::{ImageError, ImageFormat, ImageReader};
-use isahc::AsyncReadResponseExt;
+use isahc::Request;
 use nom_exif::{parse_jpeg_exif, EntryValue, ExifTag};
 use std::io::Cursor;
 use tokio::sync::Mutex;
@@ -11,13 +13,10 @@ use tokio::sync::Mutex;
 pub enum Error {
     #[error("Redis cache operation has failed")]
     Cache(#[from] cache::redis::Error),
-    #[error("failed to acquire an HTTP client")]
-    HttpClient(#[from] http_client::Error),
-    #[error("HTTP request failed")]
-    Isahc(#[from] isahc::Error),
-    #[doc = "Bad HTTP status"]
-    #[error("bad HTTP status ({0})")]
-    BadStatus(String),
+    #[error("failed to fetch the image")]
+    Fetch(#[from] http_client::FetchError),
+    #[error("invalid HTTP request")]
+    Request(#[from] isahc::http::Error),
     #[error("failed to decode an image")]
     Image(#[from] ImageError),
     #[error("failed to decode an image")]
@@ -30,8 +29,6 @@ pub enum Error {
     #[doc = "Unsupported image type"]
     #[error("unsupported image type ({0})")]
     UnsupportedImage(String),
-    #[error("access to this URL is not allowed")]
-    UnsafeUrl,
 }
 
 const BROWSER_SAFE_IMAGE_TYPES: [ImageFormat; 8] = [
@@ -56,10 +53,6 @@ pub struct ImageSize {
 
 #[macros::export]
 pub async fn get_image_size_from_url(url: &str) -> Result<ImageSize, Error> {
-    if !is_safe_url(url) {
-        return Err(Error::UnsafeUrl);
-    }
-
     let attempted: bool;
 
     {
@@ -81,22 +74,16 @@ pub async fn get_image_size_from_url(url: &str) -> Result<ImageSize, Error> {
 
     tracing::info!("retrieving image from {}", url);
 
-    let response = http_client::client()?.get_async(url).await?;
-
-    if !response.status().is_success() {
-        tracing::info!("status: {}", response.status());
-        return Err(Error::BadStatus(format!(
-            "{} returned {}",
-            url,
-            response.status()
-        )));
-    }
-
     // Read up to 8 MiB of the response body
-    let image_bytes = response
-        .map(|body| body.take(8 * 1024 * 1024))
-        .bytes()
-        .await?;
+    let options = FetchOptions {
+        max_body_size: 8 * 1024 * 1024,
+        truncate_body: true,
+        ..Default::default()
+    };
+    let image_bytes = http_client::fetch(Request::get(url).body(Vec::new())?, &options)
+        .await?
+        .error_for_status()?
+        .body;
 
     let reader = ImageReader::new(Cursor::new(&image_bytes)).with_guessed_format()?;
 
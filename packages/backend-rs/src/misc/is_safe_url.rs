@@ -1,3 +1,5 @@
+use crate::misc::ssrf_policy::ssrf_policy;
+
 #[macros::export]
 pub fn is_safe_url(url: &str) -> bool {
     // reject malformed url
@@ -18,21 +20,9 @@ pub fn is_safe_url(url: &str) -> bool {
         return false;
     }
 
-    // ensure no address is in rejected ranges
-    !addrs.iter().any(|addr| {
-        let ip = addr.ip().to_canonical();
-
-        ip.is_unspecified()
-            || ip.is_loopback()
-            || ip.is_multicast()
-            || match ip {
-                std::net::IpAddr::V4(ip) => ip.is_private() || ip.is_link_local(),
-                std::net::IpAddr::V6(ip) => {
-                    ip.segments()[0] & 0xfe00 == 0xfc00 // unique local (fc00::/7)
-                 || ip.segments()[0] & 0xffc0 == 0xfe80 // link local (fe80::/10)
-                }
-            }
-    })
+    // ensure no address is rejected by the policy
+    let policy = ssrf_policy();
+    addrs.iter().all(|addr| policy.is_allowed(*addr))
 }
 
 #[cfg(test)]
@@ -0,0 +1,528 @@
+//! Translate JavaScript regular expressions into the syntax of the [regex] crate
+
+use regex::Regex;
+use std::{iter::Peekable, str::CharIndices};
+
+#[error_doc::errors]
+pub enum Error {
+    #[error("not a regular expression literal (expected `/pattern/flags`)")]
+    InvalidLiteral,
+    #[doc = "Unknown flag"]
+    #[error("unknown regular expression flag `{0}`")]
+    InvalidFlag(char),
+    #[doc = "Duplicate flag"]
+    #[error("duplicate regular expression flag `{0}`")]
+    DuplicateFlag(char),
+    #[doc = "Invalid escape sequence"]
+    #[error("invalid escape sequence `\\{0}` at offset {1}")]
+    InvalidEscape(char, usize),
+    #[doc = "Lookahead or lookbehind assertion"]
+    #[error("lookaround assertions are not supported (at offset {0})")]
+    Lookaround(usize),
+    #[doc = "Backreference"]
+    #[error("backreferences are not supported (at offset {0})")]
+    Backreference(usize),
+    #[doc = "Invalid regular expression"]
+    #[error("invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+}
+
+/// Characters matched by `\s` in JavaScript (as the content of a character class)
+const SPACE: &str =
+    r"\t\n\x0B\f\r \xA0\x{1680}\x{2000}-\x{200A}\x{2028}\x{2029}\x{202F}\x{205F}\x{3000}\x{FEFF}";
+/// Characters matched by `\d` in JavaScript (as the content of a character class)
+const DIGIT: &str = "0-9";
+/// Characters matched by `\w` in JavaScript (as the content of a character class)
+const WORD: &str = "0-9A-Za-z_";
+/// Characters matched by `.` in JavaScript without the `s` flag
+const NOT_LINE_TERMINATOR: &str = r"[^\n\r\x{2028}\x{2029}]";
+/// Character class that matches nothing (e.g., `[]` in JavaScript)
+const NOTHING: &str = r"[^\x00-\x{10FFFF}]";
+/// Character class that matches anything (e.g., `[^]` in JavaScript)
+const ANYTHING: &str = r"[\x00-\x{10FFFF}]";
+
+#[derive(Default)]
+struct Flags {
+    ignore_case: bool,
+    multiline: bool,
+    dot_all: bool,
+    sticky: bool,
+    /// `u` or `v`
+    unicode: bool,
+    /// `v`
+    unicode_sets: bool,
+}
+
+impl Flags {
+    fn parse(flags: &str) -> Result<Self, Error> {
+        let mut parsed = Self::default();
+        let mut seen = String::new();
+
+        for flag in flags.chars() {
+            if seen.contains(flag) {
+                return Err(Error::DuplicateFlag(flag));
+            }
+            seen.push(flag);
+
+            match flag {
+                // `g` and `d` only affect the API of RegExp objects
+                'g' | 'd' => {}
+                'i' => parsed.ignore_case = true,
+                'm' => parsed.multiline = true,
+                's' => parsed.dot_all = true,
+                'y' => parsed.sticky = true,
+                'u' => parsed.unicode = true,
+                'v' => {
+                    parsed.unicode = true;
+                    parsed.unicode_sets = true;
+                }
+                _ => return Err(Error::InvalidFlag(flag)),
+            }
+        }
+
+        if seen.contains('u') && seen.contains('v') {
+            return Err(Error::InvalidFlag('v'));
+        }
+
+        Ok(parsed)
+    }
+}
+
+struct Translator<'a> {
+    pattern: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    flags: Flags,
+    out: String,
+    /// nesting depth of character classes (only `v` mode allows nested classes)
+    class_depth: usize,
+}
+
+impl<'a> Translator<'a> {
+    fn next_if_eq(&mut self, expected: char) -> bool {
+        self.chars.next_if(|&(_, c)| c == expected).is_some()
+    }
+
+    /// Consumes up to `max` hexadecimal digits and returns the value if there are exactly `max`.
+    fn hex(&mut self, max: usize) -> Option<u32> {
+        let rest = self
+            .chars
+            .peek()
+            .map_or("", |&(offset, _)| &self.pattern[offset..]);
+        let digits = rest
+            .chars()
+            .take(max)
+            .take_while(char::is_ascii_hexdigit)
+            .count();
+        if digits != max {
+            return None;
+        }
+        self.chars.nth(max - 1);
+        u32::from_str_radix(&rest[..max], 16).ok()
+    }
+
+    /// Consumes `{hex}` and returns the value.
+    fn braced_hex(&mut self) -> Option<u32> {
+        let rest = self
+            .chars
+            .peek()
+            .map_or("", |&(offset, _)| &self.pattern[offset..]);
+        let hex = rest.strip_prefix('{')?.split_once('}')?.0;
+        if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.chars.nth(hex.len() + 1);
+        u32::from_str_radix(hex, 16).ok()
+    }
+
+    /// Pushes a set of characters given as the content of a character class.
+    fn push_set(&mut self, set: &str, negated: bool) {
+        match (negated, self.class_depth > 0) {
+            (false, true) => self.out.push_str(set),
+            (false, false) => {
+                self.out.push('[');
+                self.out.push_str(set);
+                self.out.push(']');
+            }
+            // nested classes are allowed in the regex crate
+            (true, _) => {
+                self.out.push_str("[^");
+                self.out.push_str(set);
+                self.out.push(']');
+            }
+        }
+    }
+
+    fn push_code_point(&mut self, code: u32) {
+        match char::from_u32(code) {
+            Some(c) => self.out.push_str(&format!(r"\x{{{:X}}}", c as u32)),
+            // lone surrogates never appear in Rust strings
+            None => self.out.push_str(NOTHING),
+        }
+    }
+
+    fn escape(&mut self, offset: usize) -> Result<(), Error> {
+        let Some((_, c)) = self.chars.next() else {
+            // let the regex crate report the incomplete escape
+            self.out.push('\\');
+            return Ok(());
+        };
+        let in_class = self.class_depth > 0;
+
+        match c {
+            'd' => self.push_set(DIGIT, false),
+            'D' => self.push_set(DIGIT, true),
+            'w' => self.push_set(WORD, false),
+            'W' => self.push_set(WORD, true),
+            's' => self.push_set(SPACE, false),
+            'S' => self.push_set(SPACE, true),
+            // backspace in character classes
+            'b' if in_class => self.out.push_str(r"\x08"),
+            'b' => self.out.push_str(r"(?-u:\b)"),
+            'B' if !in_class => self.out.push_str(r"(?-u:\B)"),
+            'n' | 'r' | 't' | 'f' | 'v' => self.out.extend(['\\', c]),
+            '0' if !self.chars.peek().is_some_and(|(_, c)| c.is_ascii_digit()) => {
+                self.out.push_str(r"\x00")
+            }
+            '0' if self.flags.unicode => return Err(Error::InvalidEscape(c, offset)),
+            // legacy octal escape
+            '0' => {
+                let mut code = 0;
+                for _ in 0..2 {
+                    match self.chars.next_if(|(_, c)| ('0'..='7').contains(c)) {
+                        Some((_, digit)) => code = code * 8 + (digit as u32 - '0' as u32),
+                        None => break,
+                    }
+                }
+                self.push_code_point(code);
+            }
+            '1'..='9' => return Err(Error::Backreference(offset)),
+            'k' if self.flags.unicode || self.chars.peek().is_some_and(|&(_, c)| c == '<') => {
+                return Err(Error::Backreference(offset))
+            }
+            'c' => match self.chars.next_if(|(_, c)| c.is_ascii_alphabetic()) {
+                Some((_, letter)) => self.push_code_point(letter as u32 % 32),
+                None if self.flags.unicode => return Err(Error::InvalidEscape(c, offset)),
+                None => self.out.push_str(r"\\c"),
+            },
+            'x' => match self.hex(2) {
+                Some(code) => self.push_code_point(code),
+                None if self.flags.unicode => return Err(Error::InvalidEscape(c, offset)),
+                None => self.out.push('x'),
+            },
+            'u' => {
+                let code = match self.flags.unicode {
+                    true => self.braced_hex().or_else(|| self.hex(4)),
+                    false => self.hex(4),
+                };
+                match code {
+                    Some(high @ 0xD800..=0xDBFF) => {
+                        let low = self.pattern[offset..]
+                            .get(6..8)
+                            .filter(|&escape| escape == r"\u")
+                            .and_then(|_| self.pattern.get(offset + 8..offset + 12))
+                            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                            .filter(|low| (0xDC00..=0xDFFF).contains(low));
+                        match low {
+                            Some(low) => {
+                                self.chars.nth(5);
+                                self.push_code_point(
+                                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                                );
+                            }
+                            None => self.push_code_point(high),
+                        }
+                    }
+                    Some(code) => self.push_code_point(code),
+                    None if self.flags.unicode => return Err(Error::InvalidEscape(c, offset)),
+                    None => self.out.push('u'),
+                }
+            }
+            'p' | 'P' if self.flags.unicode => {
+                let rest = &self.pattern[offset + 2..];
+                let property = rest
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.split_once('}'))
+                    .map(|(property, _)| property)
+                    .filter(|property| {
+                        !property.is_empty()
+                            && property
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '=')
+                    })
+                    .ok_or(Error::InvalidEscape(c, offset))?;
+                self.chars.nth(property.len() + 1);
+                self.out.push_str(&format!(r"\{c}{{{property}}}"));
+            }
+            c if c.is_ascii_punctuation()
+                && (!self.flags.unicode
+                    || r"^$\.*+?()[]{}|/".contains(c)
+                    || in_class && c == '-'
+                    || in_class && self.flags.unicode_sets && "&!#%,:;<=>@`~".contains(c)) =>
+            {
+                match in_class && "&-~".contains(c) {
+                    // set operators in the regex crate
+                    true => self.out.extend(['\\', c]),
+                    // `\<` and `\>` are word boundaries in the regex crate
+                    false => self
+                        .out
+                        .push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+                }
+            }
+            // identity escapes are only allowed for syntax characters in Unicode mode
+            c if self.flags.unicode => return Err(Error::InvalidEscape(c, offset)),
+            c => self
+                .out
+                .push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the pattern continues with a valid quantifier such as `{2,3}`.
+    fn is_quantifier(&self, offset: usize) -> bool {
+        let Some((bounds, _)) = self.pattern[offset + 1..].split_once('}') else {
+            return false;
+        };
+        let (min, max) = bounds.split_once(',').unwrap_or((bounds, "0"));
+        !min.is_empty()
+            && min.chars().all(|c| c.is_ascii_digit())
+            && max.chars().all(|c| c.is_ascii_digit())
+    }
+
+    fn translate(mut self) -> Result<String, Error> {
+        let mut previous = None;
+
+        while let Some((offset, c)) = self.chars.next() {
+            let in_class = self.class_depth > 0;
+
+            match c {
+                '\\' => self.escape(offset)?,
+                '[' if !in_class => {
+                    let negated = self.next_if_eq('^');
+                    if self.next_if_eq(']') {
+                        self.out.push_str(if negated { ANYTHING } else { NOTHING });
+                    } else {
+                        self.class_depth = 1;
+                        self.out.push_str(if negated { "[^" } else { "[" });
+                    }
+                }
+                '[' if self.flags.unicode_sets => {
+                    self.class_depth += 1;
+                    self.out.push('[');
+                    if self.next_if_eq('^') {
+                        self.out.push('^');
+                    }
+                }
+                ']' if in_class => {
+                    self.class_depth -= 1;
+                    self.out.push(']');
+                }
+                // set operations of the regex crate are literal characters in JavaScript
+                // unless the `v` flag is set
+                '[' | '&' | '~' if in_class && !self.flags.unicode_sets => {
+                    self.out.extend(['\\', c])
+                }
+                '-' if in_class && !self.flags.unicode_sets && previous == Some('-') => {
+                    self.out.push_str(r"\-");
+                    previous = None;
+                    continue;
+                }
+                '(' if !in_class
+                    && (self.pattern[offset..].starts_with("(?=")
+                        || self.pattern[offset..].starts_with("(?!")
+                        || self.pattern[offset..].starts_with("(?<=")
+                        || self.pattern[offset..].starts_with("(?<!")) =>
+                {
+                    return Err(Error::Lookaround(offset));
+                }
+                '.' if !in_class && !self.flags.dot_all => self.out.push_str(NOT_LINE_TERMINATOR),
+                '{' if !in_class && self.is_quantifier(offset) => {
+                    self.out.push('{');
+                    for (_, c) in self.chars.by_ref() {
+                        self.out.push(c);
+                        if c == '}' {
+                            break;
+                        }
+                    }
+                }
+                // literal braces and brackets outside of classes are allowed in JavaScript
+                '{' | '}' | ']' if !in_class => self.out.extend(['\\', c]),
+                // the regex crate ignores whitespace and comments only with the `x` flag,
+                // which is never set here
+                c => self.out.push(c),
+            }
+
+            previous = Some(c);
+        }
+
+        Ok(self.out)
+    }
+}
+
+/// Translates a JavaScript regular expression into the syntax of the [regex] crate.
+///
+/// The result matches the same texts as `new RegExp(pattern, flags).test(text)`,
+/// except for constructs that the [regex] crate cannot express (lookaround assertions
+/// and backreferences), which are rejected. Differences in case folding between
+/// JavaScript and Unicode are not taken into account.
+pub fn translate(pattern: &str, flags: &str) -> Result<String, Error> {
+    let flags = Flags::parse(flags)?;
+
+    let mut out = String::with_capacity(pattern.len() + 8);
+    if flags.ignore_case || flags.multiline || flags.dot_all {
+        out.push_str("(?");
+        for (enabled, flag) in [
+            (flags.ignore_case, 'i'),
+            (flags.multiline, 'm'),
+            (flags.dot_all, 's'),
+        ] {
+            if enabled {
+                out.push(flag);
+            }
+        }
+        out.push(')');
+    }
+    // `test` of a sticky regular expression only matches at `lastIndex` (0)
+    let sticky = flags.sticky;
+    if sticky {
+        out.push_str(r"\A(?:");
+    }
+
+    let mut out = Translator {
+        pattern,
+        chars: pattern.char_indices().peekable(),
+        flags,
+        out,
+        class_depth: 0,
+    }
+    .translate()?;
+
+    if sticky {
+        out.push(')');
+    }
+
+    Ok(out)
+}
+
+/// Splits a regular expression literal (e.g., `/foo/i`) into the pattern and the flags.
+pub fn split_literal(literal: &str) -> Result<(&str, &str), Error> {
+    literal
+        .strip_prefix('/')
+        .and_then(|rest| rest.rsplit_once('/'))
+        .filter(|(pattern, _)| !pattern.is_empty())
+        .ok_or(Error::InvalidLiteral)
+}
+
+/// Compiles a regular expression literal (e.g., `/foo/i`) written in JavaScript syntax.
+pub fn compile(literal: &str) -> Result<Regex, Error> {
+    let (pattern, flags) = split_literal(literal)?;
+    Ok(Regex::new(&translate(pattern, flags)?)?)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{compile, translate, Error};
+    use pretty_assertions::assert_eq;
+
+    fn is_match(literal: &str, text: &str) -> bool {
+        compile(literal).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(translate("foo", "").unwrap(), "foo");
+        assert_eq!(translate("foo", "gimsuyd").unwrap(), r"(?ims)\A(?:foo)");
+
+        assert!(is_match("/FOO/gi", "foo"));
+        assert!(!is_match("/FOO/g", "foo"));
+        assert!(is_match("/^b/m", "a\nb"));
+        assert!(!is_match("/^b/", "a\nb"));
+        assert!(is_match("/a.b/s", "a\nb"));
+        assert!(is_match("/bc/", "abc"));
+        assert!(!is_match("/bc/y", "abc"));
+
+        assert!(matches!(translate("a", "x"), Err(Error::InvalidFlag('x'))));
+        assert!(matches!(
+            translate("a", "gg"),
+            Err(Error::DuplicateFlag('g'))
+        ));
+        assert!(matches!(translate("a", "uv"), Err(Error::InvalidFlag('v'))));
+    }
+
+    #[test]
+    fn character_classes() {
+        // ASCII-only in JavaScript
+        assert!(is_match(r"/^\d+$/u", "0123"));
+        assert!(!is_match(r"/\d/u", "٣"));
+        assert!(!is_match(r"/\w/", "é"));
+        assert!(is_match(r"/^\W$/", "é"));
+        assert!(is_match(r"/[\d\s]x/", "\u{FEFF}x"));
+        assert!(is_match(r"/[^\D]/", "5"));
+        assert!(is_match(r"/a\b/", "aé"));
+
+        assert!(!is_match("/a.b/", "a\rb"));
+        assert!(is_match("/a.b/", "a\u{3000}b"));
+
+        assert!(!is_match("/[]/", "anything"));
+        assert!(is_match("/a[^]b/", "a\nb"));
+        assert!(is_match("/[[&&]]/", "&]"));
+        assert!(is_match("/[--]/", "-"));
+        assert!(is_match(r"/[\p{L}--[a-z]]/v", "A"));
+        assert!(!is_match(r"/[\p{L}--[a-z]]/v", "a"));
+    }
+
+    #[test]
+    fn escapes() {
+        assert!(is_match(r"/\u00e9\x41/", "éA"));
+        assert!(is_match(r"/\uD83D\uDE00/", "😀"));
+        assert!(is_match(r"/\u{1F600}/u", "😀"));
+        assert!(is_match(r"/\u{3}/", "uuu"));
+        assert!(is_match(r"/\cJ/", "\n"));
+        assert!(is_match(r"/\0\012/", "\0\n"));
+        assert!(is_match(r"/\p{Script=Greek}/u", "α"));
+        assert!(is_match(r"/\p{L}/", "p{L}"));
+        assert!(is_match(r"/a\/b/", "a/b"));
+        assert!(is_match(r"/\<a\>/", "<a>"));
+        assert!(!is_match(r"/\<a\>/", "a"));
+        assert!(is_match(r"/[\<]/", "<"));
+        assert!(is_match(r"/[\<\-]/v", "-"));
+        assert!(is_match(r"/\a\e/", "ae"));
+        assert!(is_match(r"/a{2}/", "aa"));
+        assert!(is_match(r"/{a}/", "{a}"));
+        assert!(is_match(r"/a{,2}]/", "a{,2}]"));
+
+        assert!(matches!(
+            translate(r"\a", "u"),
+            Err(Error::InvalidEscape('a', 0))
+        ));
+        assert!(matches!(
+            translate(r"\u{12", "u"),
+            Err(Error::InvalidEscape('u', 0))
+        ));
+    }
+
+    #[test]
+    fn unsupported_syntax() {
+        assert!(matches!(translate("a(?=b)", ""), Err(Error::Lookaround(1))));
+        assert!(matches!(
+            translate("(?<!a)b", ""),
+            Err(Error::Lookaround(0))
+        ));
+        assert!(matches!(
+            translate(r"(a)\1", ""),
+            Err(Error::Backreference(3))
+        ));
+        assert!(matches!(
+            translate(r"(?<x>a)\k<x>", ""),
+            Err(Error::Backreference(7))
+        ));
+
+        // named groups are supported
+        assert!(is_match("/(?<x>a)b/", "ab"));
+
+        assert!(matches!(compile("foo"), Err(Error::InvalidLiteral)));
+        assert!(matches!(compile("//i"), Err(Error::InvalidLiteral)));
+        assert!(matches!(compile("/(/"), Err(Error::Regex(_))));
+        assert!(matches!(compile(r"/a\/"), Err(Error::Regex(_))));
+    }
+}
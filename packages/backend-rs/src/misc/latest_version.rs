@@ -1,26 +1,21 @@
 //! Fetch latest Firefish version from the Firefish repository
 
-use crate::{cache::Cache, misc::is_safe_url::is_safe_url, util::http_client};
+use crate::{
+    cache::Cache,
+    util::http_client::{self, FetchOptions},
+};
 use chrono::Duration;
-use futures_util::AsyncReadExt;
-use isahc::AsyncReadResponseExt;
+use isahc::Request;
 use serde::Deserialize;
 
 #[error_doc::errors]
 pub enum Error {
-    #[error("HTTP request failed")]
-    Isahc(#[from] isahc::Error),
-    #[error("failed to acquire an HTTP client")]
-    HttpClient(#[from] http_client::Error),
-    #[doc = "codeberg.org returned bad HTTP status"]
-    #[error("codeberg.org returned bad HTTP status ({0})")]
-    BadStatus(String),
-    #[error("failed to parse the HTTP response")]
-    Io(#[from] std::io::Error),
+    #[error("failed to fetch the package.json")]
+    Fetch(#[from] http_client::FetchError),
+    #[error("invalid HTTP request")]
+    Request(#[from] isahc::http::Error),
     #[error("failed to parse the HTTP response as JSON")]
     Json(#[from] serde_json::Error),
-    #[error("access to this URL is not allowed")]
-    UnsafeUrl,
 }
 
 #[derive(Clone, Deserialize)]
@@ -34,22 +29,19 @@ const UPSTREAM_PACKAGE_JSON_URL: &str =
 static PACKAGE_JSON_CACHE: Cache<PackageJson> = Cache::new_with_ttl(Duration::hours(3));
 
 async fn get_package_json() -> Result<PackageJson, Error> {
-    if !is_safe_url(UPSTREAM_PACKAGE_JSON_URL) {
-        return Err(Error::UnsafeUrl);
-    }
-
     // Read up to 1 MiB of the response body
-    let mut response = http_client::client()?
-        .get_async(UPSTREAM_PACKAGE_JSON_URL)
-        .await?
-        .map(|body| body.take(1024 * 1024));
-
-    if !response.status().is_success() {
-        tracing::info!("status: {}", response.status());
-        return Err(Error::BadStatus(response.status().to_string()));
-    }
-
-    let package_json: PackageJson = serde_json::from_str(&response.text().await?)?;
+    let options = FetchOptions {
+        max_body_size: 1024 * 1024,
+        ..Default::default()
+    };
+    let response = http_client::fetch(
+        Request::get(UPSTREAM_PACKAGE_JSON_URL).body(Vec::new())?,
+        &options,
+    )
+    .await?
+    .error_for_status()?;
+
+    let package_json: PackageJson = response.json()?;
 
     Ok(package_json)
 }
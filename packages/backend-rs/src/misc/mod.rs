@@ -9,13 +9,16 @@ pub mod format_milliseconds;
 pub mod get_image_size;
 pub mod is_quote;
 pub mod is_safe_url;
+pub mod js_regex;
 pub mod latest_version;
+pub mod mute_filter;
 pub mod note;
 pub mod nyaify;
 pub mod password;
 pub mod random_icon;
 pub mod remove_old_attestation_challenges;
 pub mod should_nyaify;
+pub mod ssrf_policy;
 pub mod system_info;
 pub mod translate;
 pub mod user;
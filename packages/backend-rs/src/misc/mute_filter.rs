@@ -0,0 +1,374 @@
+//! Precompiled word mute matcher
+
+use super::js_regex;
+use aho_corasick::AhoCorasick;
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexSet};
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+use unicode_normalization::{char::decompose_compatible, UnicodeNormalization};
+
+/// Maximum number of filters kept in [MuteFilter::cached]
+const CACHE_CAPACITY: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[macros::export(string_enum = "camelCase")]
+pub enum MuteRuleKind {
+    /// space-separated list of words that all have to appear in a text
+    Words,
+    /// JavaScript-style regular expression
+    Pattern,
+}
+
+/// Explanation of why a note is muted
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[macros::export(object)]
+pub struct MutedMatch {
+    pub kind: MuteRuleKind,
+    /// the muted words or pattern as configured by the user
+    pub rule: String,
+    /// parts of the text that matched the rule (one for each word)
+    pub fragments: Vec<String>,
+}
+
+/// Checks whether `pattern` can be used as a muted pattern (e.g., `/foo/i`).
+///
+/// JavaScript regular expressions are supported except for lookaround assertions
+/// and backreferences.
+#[macros::export]
+pub fn validate_muted_pattern(pattern: &str) -> Result<(), js_regex::Error> {
+    js_regex::compile(pattern).map(|_| ())
+}
+
+/// Text folded for case- and width-insensitive matching
+struct Folded {
+    text: String,
+    /// byte offset of the original character for each byte of `text`
+    origins: Vec<usize>,
+}
+
+/// Appends the case-folded form of `c` to `out`.
+fn fold_case(c: char, out: &mut String) {
+    match c {
+        'ß' | 'ẞ' => out.push_str("ss"),
+        'ς' => out.push('σ'),
+        _ => out.extend(c.to_lowercase()),
+    }
+}
+
+/// Applies compatibility decomposition (which unifies full-width and half-width forms)
+/// and case folding to each character of `text`.
+///
+/// Strings that are equal in NFKC are also equal after this, but unlike NFKC this can
+/// be done character by character, which lets us map matches back to the original text.
+fn fold(text: &str) -> Folded {
+    let mut folded = Folded {
+        text: String::with_capacity(text.len()),
+        origins: Vec::with_capacity(text.len()),
+    };
+
+    for (offset, c) in text.char_indices() {
+        let start = folded.text.len();
+        decompose_compatible(c, |d| fold_case(d, &mut folded.text));
+        folded
+            .origins
+            .resize(folded.origins.len() + folded.text.len() - start, offset);
+    }
+
+    folded
+}
+
+impl Folded {
+    /// Returns the part of `original` that corresponds to `range` of the folded text.
+    fn original<'a>(&self, original: &'a str, range: Range<usize>) -> &'a str {
+        let start = self.origins[range.start];
+        let last = self.origins[range.end - 1];
+        let end = last + original[last..].chars().next().map_or(0, char::len_utf8);
+        &original[start..end]
+    }
+}
+
+struct WordGroup {
+    rule: String,
+    /// indices of the words in [MuteFilter::words]
+    words: Vec<usize>,
+}
+
+/// Word mute rules of a user compiled into automata
+///
+/// Words are matched regardless of case, width (e.g., `ｆｏｏ` and `foo`), and other
+/// compatibility variants. Patterns are matched against both the original text and its
+/// NFKC normalization, and their flags (e.g., `i`) decide whether they ignore case.
+pub struct MuteFilter {
+    groups: Vec<WordGroup>,
+    /// automaton that finds all distinct words in [MuteFilter::groups]
+    words: Option<AhoCorasick>,
+    word_count: usize,
+    patterns: Vec<String>,
+    regex_set: RegexSet,
+    regexes: Vec<Regex>,
+}
+
+impl MuteFilter {
+    /// Compiles the rules.
+    ///
+    /// # Arguments
+    ///
+    /// * `muted_words` : list of muted keyword lists (each array item is a space-separated keyword list that represents an AND condition)
+    /// * `muted_patterns` : list of JavaScript-style (e.g., `/foo/i`) regular expressions. invalid ones are ignored
+    pub fn new(muted_words: &[String], muted_patterns: &[String]) -> Self {
+        let mut word_indices: HashMap<String, usize> = HashMap::new();
+        let mut groups = Vec::new();
+
+        for rule in muted_words {
+            let words: Vec<usize> = rule
+                .split_whitespace()
+                .map(|word| fold(word).text)
+                .filter(|word| !word.is_empty())
+                .map(|word| {
+                    let next = word_indices.len();
+                    *word_indices.entry(word).or_insert(next)
+                })
+                .collect();
+
+            // an empty rule would mute everything
+            if !words.is_empty() {
+                groups.push(WordGroup {
+                    rule: rule.to_owned(),
+                    words,
+                });
+            }
+        }
+
+        let mut word_list = vec![String::new(); word_indices.len()];
+        for (word, index) in word_indices {
+            word_list[index] = word;
+        }
+        let words = match word_list.is_empty() {
+            true => None,
+            false => AhoCorasick::new(&word_list)
+                .inspect_err(|err| tracing::warn!("failed to compile muted words: {}", err))
+                .ok(),
+        };
+
+        let (patterns, regexes): (Vec<String>, Vec<Regex>) = muted_patterns
+            .iter()
+            .filter_map(|pattern| match js_regex::compile(pattern) {
+                Ok(regex) => Some((pattern.to_owned(), regex)),
+                Err(err) => {
+                    tracing::debug!("ignoring invalid muted pattern {}: {}", pattern, err);
+                    None
+                }
+            })
+            .unzip();
+        let regex_set =
+            RegexSet::new(regexes.iter().map(Regex::as_str)).unwrap_or_else(|_| RegexSet::empty());
+
+        Self {
+            groups,
+            words,
+            word_count: word_list.len(),
+            patterns,
+            regex_set,
+            regexes,
+        }
+    }
+
+    /// Returns the filter compiled from the same rules if there is one, or compiles a new one.
+    pub fn cached(muted_words: &[String], muted_patterns: &[String]) -> Arc<Self> {
+        type Entry = (Vec<String>, Vec<String>, Arc<MuteFilter>);
+        static CACHE: Lazy<Mutex<HashMap<u64, Entry>>> = Lazy::new(Default::default);
+
+        let mut hasher = DefaultHasher::new();
+        (muted_words, muted_patterns).hash(&mut hasher);
+        let key = hasher.finish();
+
+        if let Ok(cache) = CACHE.lock() {
+            if let Some((words, patterns, filter)) = cache.get(&key) {
+                if words == muted_words && patterns == muted_patterns {
+                    return filter.clone();
+                }
+            }
+        }
+
+        let filter = Arc::new(Self::new(muted_words, muted_patterns));
+
+        if let Ok(mut cache) = CACHE.lock() {
+            if cache.len() >= CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(
+                key,
+                (
+                    muted_words.to_owned(),
+                    muted_patterns.to_owned(),
+                    filter.clone(),
+                ),
+            );
+        }
+
+        filter
+    }
+
+    /// Returns whether the filter has no rules.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty() && self.regexes.is_empty()
+    }
+
+    /// Returns the first occurrence of each word in the folded text.
+    fn find_words(&self, folded: &Folded) -> Vec<Option<Range<usize>>> {
+        let mut found = vec![None; self.word_count];
+        if let Some(words) = &self.words {
+            for m in words.find_overlapping_iter(&folded.text) {
+                found[m.pattern().as_usize()].get_or_insert(m.range());
+            }
+        }
+        found
+    }
+
+    /// Returns whether any of the texts matches any of the rules.
+    pub fn is_muted<S: AsRef<str>>(&self, texts: &[S]) -> bool {
+        self.why_muted(texts).is_some()
+    }
+
+    /// Returns the first rule that matches any of the texts and the matched parts.
+    ///
+    /// Word rules are checked before patterns.
+    pub fn why_muted<S: AsRef<str>>(&self, texts: &[S]) -> Option<MutedMatch> {
+        if !self.groups.is_empty() {
+            for text in texts {
+                let text = text.as_ref();
+                let folded = fold(text);
+                let found = self.find_words(&folded);
+
+                for group in &self.groups {
+                    let ranges: Option<Vec<Range<usize>>> =
+                        group.words.iter().map(|&i| found[i].clone()).collect();
+                    if let Some(ranges) = ranges {
+                        return Some(MutedMatch {
+                            kind: MuteRuleKind::Words,
+                            rule: group.rule.to_owned(),
+                            fragments: ranges
+                                .into_iter()
+                                .map(|range| folded.original(text, range).to_owned())
+                                .collect(),
+                        });
+                    }
+                }
+            }
+        }
+
+        if !self.regexes.is_empty() {
+            for text in texts {
+                let text = text.as_ref();
+                let normalized: String = text.nfkc().collect();
+
+                for candidate in [text, normalized.as_str()] {
+                    if let Some(index) = self.regex_set.matches(candidate).into_iter().next() {
+                        let fragment = self.regexes[index]
+                            .find(candidate)
+                            .map(|m| m.as_str().to_owned())
+                            .unwrap_or_default();
+                        return Some(MutedMatch {
+                            kind: MuteRuleKind::Pattern,
+                            rule: self.patterns[index].to_owned(),
+                            fragments: vec![fragment],
+                        });
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{fold, validate_muted_pattern, MuteFilter, MuteRuleKind, MutedMatch};
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+
+    fn filter(words: &[&str], patterns: &[&str]) -> MuteFilter {
+        let words: Vec<String> = words.iter().map(|&word| word.to_owned()).collect();
+        let patterns: Vec<String> = patterns.iter().map(|&pattern| pattern.to_owned()).collect();
+        MuteFilter::new(&words, &patterns)
+    }
+
+    #[test]
+    fn normalization() {
+        assert_eq!(fold("ＦｏＯ").text, "foo");
+        assert_eq!(fold("ｶﾞｯｺｳ").text, fold("ガッコウ").text);
+        assert_eq!(fold("Straße").text, fold("STRASSE").text);
+        assert_eq!(fold("㌔").text, fold("キロ").text);
+        assert_eq!(fold("é").text, fold("e\u{301}").text);
+    }
+
+    #[test]
+    fn match_words() {
+        let filter = filter(&["brown fox", "ｌａｚｙ", "ガッコウ"], &[]);
+
+        assert!(filter.is_muted(&["The quick Brown FOX"]));
+        assert!(filter.is_muted(&["LAZY dog"]));
+        assert!(filter.is_muted(&["ｶﾞｯｺｳ"]));
+        assert!(!filter.is_muted(&["brown", "fox"]));
+        assert!(!filter.is_muted(&["quick dog"]));
+
+        assert_eq!(
+            filter.why_muted(&["Nothing here", "A ＦＯＸ in BROWN"]),
+            Some(MutedMatch {
+                kind: MuteRuleKind::Words,
+                rule: "brown fox".to_owned(),
+                fragments: vec!["BROWN".to_owned(), "ＦＯＸ".to_owned()],
+            })
+        );
+        assert_eq!(
+            filter.why_muted(&["ｶﾞｯｺｳに行く"]).unwrap().fragments,
+            vec!["ｶﾞｯｺｳ".to_owned()]
+        );
+    }
+
+    #[test]
+    fn match_patterns() {
+        let filter = filter(&["", "  "], &["/(", "/(?=a)/", "/qu+ick/i", "/ABC/gu"]);
+
+        assert!(!filter.is_muted(&["anything"]));
+        assert_eq!(
+            filter.why_muted(&["The QUUICK brown fox"]),
+            Some(MutedMatch {
+                kind: MuteRuleKind::Pattern,
+                rule: "/qu+ick/i".to_owned(),
+                fragments: vec!["QUUICK".to_owned()],
+            })
+        );
+        // matched against the NFKC normalization
+        assert!(filter.is_muted(&["ＡＢＣ"]));
+        assert!(!filter.is_muted(&["ａｂｃ"]));
+    }
+
+    #[test]
+    fn validate_patterns() {
+        assert!(validate_muted_pattern("/foo/gi").is_ok());
+        assert!(validate_muted_pattern(r"/\bfoo\b/y").is_ok());
+        assert!(validate_muted_pattern("foo").is_err());
+        assert!(validate_muted_pattern("/(a)\\1/").is_err());
+        assert!(validate_muted_pattern("/foo/x").is_err());
+    }
+
+    #[test]
+    fn reuse_filters() {
+        let words = vec!["foo".to_owned()];
+        let patterns = vec!["/bar/".to_owned()];
+
+        let filter_1 = MuteFilter::cached(&words, &patterns);
+        let filter_2 = MuteFilter::cached(&words, &patterns);
+        let filter_3 = MuteFilter::cached(&words, &[]);
+
+        assert!(Arc::ptr_eq(&filter_1, &filter_2));
+        assert!(!Arc::ptr_eq(&filter_1, &filter_3));
+    }
+}
@@ -0,0 +1,373 @@
+//! Destinations that outgoing requests may reach (protection against SSRF)
+
+use crate::config::{server::SsrfConfig, CONFIG};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use once_cell::sync::Lazy;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+#[error_doc::errors]
+pub enum Error {
+    #[doc = "Invalid network"]
+    #[error("invalid network `{0}` (expected an IP address or CIDR notation)")]
+    InvalidNetwork(String),
+}
+
+/// Private, shared, and reserved networks that are rejected unless explicitly allowed
+const DENIED_NETWORKS: [IpNet; 26] = [
+    // "this network" (RFC 791)
+    v4([0, 0, 0, 0], 8),
+    // private-use (RFC 1918)
+    v4([10, 0, 0, 0], 8),
+    // shared address space (CGNAT, RFC 6598)
+    v4([100, 64, 0, 0], 10),
+    // loopback (RFC 1122)
+    v4([127, 0, 0, 0], 8),
+    // link-local (RFC 3927)
+    v4([169, 254, 0, 0], 16),
+    // private-use (RFC 1918)
+    v4([172, 16, 0, 0], 12),
+    // IETF protocol assignments (RFC 6890)
+    v4([192, 0, 0, 0], 24),
+    // documentation (TEST-NET-1, RFC 5737)
+    v4([192, 0, 2, 0], 24),
+    // 6to4 relay anycast (RFC 7526)
+    v4([192, 88, 99, 0], 24),
+    // private-use (RFC 1918)
+    v4([192, 168, 0, 0], 16),
+    // benchmarking (RFC 2544)
+    v4([198, 18, 0, 0], 15),
+    // documentation (TEST-NET-2, RFC 5737)
+    v4([198, 51, 100, 0], 24),
+    // documentation (TEST-NET-3, RFC 5737)
+    v4([203, 0, 113, 0], 24),
+    // multicast (RFC 5771)
+    v4([224, 0, 0, 0], 4),
+    // reserved and limited broadcast (RFC 1112, RFC 919)
+    v4([240, 0, 0, 0], 4),
+    // unspecified, loopback, and deprecated IPv4-compatible addresses (RFC 4291)
+    v6([0, 0, 0, 0, 0, 0, 0, 0], 96),
+    // local-use IPv4/IPv6 translation (RFC 8215)
+    v6([0x64, 0xff9b, 1, 0, 0, 0, 0, 0], 48),
+    // discard-only (RFC 6666)
+    v6([0x100, 0, 0, 0, 0, 0, 0, 0], 64),
+    // IETF protocol assignments (RFC 2928), including Teredo (RFC 4380),
+    // benchmarking (RFC 5180), and ORCHIDv2 (RFC 7343)
+    v6([0x2001, 0, 0, 0, 0, 0, 0, 0], 23),
+    // documentation (RFC 3849)
+    v6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 0], 32),
+    // documentation (RFC 9637)
+    v6([0x3fff, 0, 0, 0, 0, 0, 0, 0], 20),
+    // segment routing SIDs (RFC 9602)
+    v6([0x5f00, 0, 0, 0, 0, 0, 0, 0], 16),
+    // unique local (RFC 4193)
+    v6([0xfc00, 0, 0, 0, 0, 0, 0, 0], 7),
+    // link-local (RFC 4291)
+    v6([0xfe80, 0, 0, 0, 0, 0, 0, 0], 10),
+    // site-local (deprecated, RFC 3879)
+    v6([0xfec0, 0, 0, 0, 0, 0, 0, 0], 10),
+    // multicast (RFC 4291)
+    v6([0xff00, 0, 0, 0, 0, 0, 0, 0], 8),
+];
+
+const fn v4(octets: [u8; 4], prefix_len: u8) -> IpNet {
+    let [a, b, c, d] = octets;
+    IpNet::V4(Ipv4Net::new_assert(Ipv4Addr::new(a, b, c, d), prefix_len))
+}
+
+const fn v6(segments: [u16; 8], prefix_len: u8) -> IpNet {
+    let [a, b, c, d, e, f, g, h] = segments;
+    IpNet::V6(Ipv6Net::new_assert(
+        Ipv6Addr::new(a, b, c, d, e, f, g, h),
+        prefix_len,
+    ))
+}
+
+/// Returns the IPv4 address embedded in an IPv4-mapped (`::ffff:0:0/96`),
+/// NAT64 (`64:ff9b::/96`), or 6to4 (`2002::/16`) address.
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        return Some(ip);
+    }
+
+    let octets = ip.octets();
+    match ip.segments() {
+        [0x64, 0xff9b, 0, 0, 0, 0, _, _] => Some(Ipv4Addr::new(
+            octets[12], octets[13], octets[14], octets[15],
+        )),
+        [0x2002, _, _, _, _, _, _, _] => {
+            Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5]))
+        }
+        _ => None,
+    }
+}
+
+/// Parses an IP address or a network in CIDR notation.
+pub(crate) fn parse_network(network: &str) -> Result<IpNet, Error> {
+    network
+        .parse::<IpNet>()
+        .or_else(|_| network.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| Error::InvalidNetwork(network.to_owned()))
+}
+
+/// Decides which addresses and ports outgoing requests may reach
+#[derive(Clone, Debug, Default)]
+pub struct SsrfPolicy {
+    allowed_networks: Vec<IpNet>,
+    denied_networks: Vec<IpNet>,
+    allowed_ports: Option<Vec<u16>>,
+}
+
+impl SsrfPolicy {
+    pub fn from_config(config: &SsrfConfig) -> Result<Self, Error> {
+        let parse_all = |networks: &Option<Vec<String>>| {
+            networks
+                .iter()
+                .flatten()
+                .map(|network| parse_network(network))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            allowed_networks: parse_all(&config.allowed_networks)?,
+            denied_networks: parse_all(&config.denied_networks)?,
+            allowed_ports: config.allowed_ports.clone(),
+        })
+    }
+
+    /// Returns a policy that denies all addresses and ports.
+    pub fn deny_all() -> Self {
+        Self {
+            allowed_networks: Vec::new(),
+            denied_networks: vec![v4([0, 0, 0, 0], 0), v6([0, 0, 0, 0, 0, 0, 0, 0], 0)],
+            allowed_ports: Some(Vec::new()),
+        }
+    }
+
+    /// Returns whether the IP address may be accessed.
+    ///
+    /// `denied_networks` take precedence over `allowed_networks`, which take precedence over
+    /// the built-in private and reserved networks. IPv4 addresses embedded in IPv6 addresses
+    /// are checked as IPv4 addresses.
+    pub fn is_allowed_ip(&self, ip: IpAddr) -> bool {
+        let embedded = match ip {
+            IpAddr::V4(_) => None,
+            IpAddr::V6(ip) => embedded_ipv4(ip).map(IpAddr::V4),
+        };
+        let candidates = [Some(ip), embedded];
+        let matches = |networks: &[IpNet]| {
+            candidates
+                .iter()
+                .flatten()
+                .any(|ip| networks.iter().any(|network| network.contains(ip)))
+        };
+
+        if matches(&self.denied_networks) {
+            return false;
+        }
+        if matches(&self.allowed_networks) {
+            return true;
+        }
+
+        let ip = embedded.unwrap_or(ip);
+        !DENIED_NETWORKS.iter().any(|network| network.contains(&ip))
+    }
+
+    /// Returns whether the port may be accessed.
+    pub fn is_allowed_port(&self, port: u16) -> bool {
+        match &self.allowed_ports {
+            Some(ports) => ports.contains(&port),
+            None => true,
+        }
+    }
+
+    /// Returns whether the socket address may be accessed.
+    pub fn is_allowed(&self, addr: SocketAddr) -> bool {
+        self.is_allowed_port(addr.port()) && self.is_allowed_ip(addr.ip())
+    }
+}
+
+static SSRF_POLICY: Lazy<SsrfPolicy> = Lazy::new(|| match &CONFIG.ssrf {
+    Some(config) => SsrfPolicy::from_config(config).unwrap_or_else(|err| {
+        // `load_config` rejects invalid networks, so this should never happen
+        tracing::error!(
+            "invalid SSRF policy, denying all outgoing requests: {}",
+            err
+        );
+        SsrfPolicy::deny_all()
+    }),
+    None => SsrfPolicy::default(),
+});
+
+/// Returns the policy configured by `ssrf` in the server config.
+pub fn ssrf_policy() -> &'static SsrfPolicy {
+    &SSRF_POLICY
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{SsrfPolicy, DENIED_NETWORKS};
+    use crate::config::server::SsrfConfig;
+    use std::net::{IpAddr, SocketAddr};
+
+    fn allowed(policy: &SsrfPolicy, ip: &str) -> bool {
+        policy.is_allowed_ip(ip.parse::<IpAddr>().unwrap())
+    }
+
+    #[test]
+    fn deny_all() {
+        let policy = SsrfPolicy::deny_all();
+        assert!(!allowed(&policy, "93.184.215.14"));
+        assert!(!allowed(&policy, "2606:2800:21f:cb07:6820:80da:af6b:8b2c"));
+        assert!(!policy.is_allowed_port(443));
+    }
+
+    #[test]
+    fn built_in_networks() {
+        // every network is rejected, including the first and last addresses
+        let policy = SsrfPolicy::default();
+        for network in DENIED_NETWORKS {
+            assert!(!policy.is_allowed_ip(network.network()), "{}", network);
+            assert!(!policy.is_allowed_ip(network.broadcast()), "{}", network);
+        }
+    }
+
+    #[test]
+    fn ipv4_ranges() {
+        let policy = SsrfPolicy::default();
+
+        assert!(allowed(&policy, "8.8.8.8"));
+        assert!(allowed(&policy, "1.1.1.1"));
+
+        // unspecified
+        assert!(!allowed(&policy, "0.0.0.0"));
+        // private-use
+        assert!(!allowed(&policy, "10.1.2.3"));
+        assert!(!allowed(&policy, "172.17.0.1"));
+        assert!(allowed(&policy, "172.32.0.1"));
+        assert!(!allowed(&policy, "192.168.1.1"));
+        // CGNAT
+        assert!(!allowed(&policy, "100.64.0.1"));
+        assert!(!allowed(&policy, "100.127.255.254"));
+        assert!(allowed(&policy, "100.128.0.1"));
+        // loopback
+        assert!(!allowed(&policy, "127.0.0.1"));
+        assert!(!allowed(&policy, "127.255.0.1"));
+        // link-local
+        assert!(!allowed(&policy, "169.254.169.254"));
+        // IETF protocol assignments
+        assert!(!allowed(&policy, "192.0.0.8"));
+        // documentation
+        assert!(!allowed(&policy, "192.0.2.1"));
+        assert!(!allowed(&policy, "198.51.100.1"));
+        assert!(!allowed(&policy, "203.0.113.1"));
+        // 6to4 relay anycast
+        assert!(!allowed(&policy, "192.88.99.1"));
+        // benchmarking
+        assert!(!allowed(&policy, "198.18.0.1"));
+        assert!(!allowed(&policy, "198.19.255.254"));
+        assert!(allowed(&policy, "198.20.0.1"));
+        // multicast
+        assert!(!allowed(&policy, "224.0.0.1"));
+        assert!(!allowed(&policy, "239.255.255.250"));
+        // reserved and broadcast
+        assert!(!allowed(&policy, "240.0.0.1"));
+        assert!(!allowed(&policy, "255.255.255.255"));
+    }
+
+    #[test]
+    fn ipv6_ranges() {
+        let policy = SsrfPolicy::default();
+
+        assert!(allowed(&policy, "2001:4860:4860::8888"));
+        assert!(allowed(&policy, "2606:4700::1111"));
+
+        // unspecified and loopback
+        assert!(!allowed(&policy, "::"));
+        assert!(!allowed(&policy, "::1"));
+        // IPv4-compatible
+        assert!(!allowed(&policy, "::8.8.8.8"));
+        // discard-only
+        assert!(!allowed(&policy, "100::1"));
+        // Teredo
+        assert!(!allowed(&policy, "2001:0:4136:e378::1"));
+        // benchmarking
+        assert!(!allowed(&policy, "2001:2::1"));
+        // ORCHIDv2
+        assert!(!allowed(&policy, "2001:20::1"));
+        // documentation
+        assert!(!allowed(&policy, "2001:db8::1"));
+        assert!(!allowed(&policy, "3fff::1"));
+        // segment routing
+        assert!(!allowed(&policy, "5f00::1"));
+        // unique local
+        assert!(!allowed(&policy, "fc39:ff14::abc"));
+        assert!(!allowed(&policy, "fdef:194::3e03"));
+        // link-local and site-local
+        assert!(!allowed(&policy, "fe80::428"));
+        assert!(!allowed(&policy, "fec0::1"));
+        // multicast
+        assert!(!allowed(&policy, "ff02::1"));
+    }
+
+    #[test]
+    fn embedded_ipv4() {
+        let policy = SsrfPolicy::default();
+
+        // IPv4-mapped
+        assert!(allowed(&policy, "::ffff:8.8.8.8"));
+        assert!(!allowed(&policy, "::ffff:127.0.0.1"));
+        assert!(!allowed(&policy, "::ffff:100.64.0.1"));
+        // NAT64
+        assert!(allowed(&policy, "64:ff9b::808:808"));
+        assert!(!allowed(&policy, "64:ff9b::7f00:1"));
+        assert!(!allowed(&policy, "64:ff9b::a9fe:a9fe"));
+        assert!(!allowed(&policy, "64:ff9b:1::808:808"));
+        // 6to4
+        assert!(allowed(&policy, "2002:808:808::1"));
+        assert!(!allowed(&policy, "2002:c0a8:101::1"));
+    }
+
+    #[test]
+    fn configured_networks() {
+        let policy = SsrfPolicy::from_config(&SsrfConfig {
+            allowed_networks: Some(vec!["10.1.0.0/16".to_owned(), "fd00::1".to_owned()]),
+            denied_networks: Some(vec!["8.8.4.0/24".to_owned(), "10.1.2.0/24".to_owned()]),
+            allowed_ports: None,
+        })
+        .unwrap();
+
+        assert!(allowed(&policy, "10.1.0.1"));
+        assert!(allowed(&policy, "::ffff:10.1.0.1"));
+        assert!(!allowed(&policy, "10.2.0.1"));
+        assert!(allowed(&policy, "fd00::1"));
+        assert!(!allowed(&policy, "fd00::2"));
+        assert!(allowed(&policy, "8.8.8.8"));
+        assert!(!allowed(&policy, "8.8.4.4"));
+        assert!(!allowed(&policy, "64:ff9b::808:404"));
+        // denied networks take precedence
+        assert!(!allowed(&policy, "10.1.2.3"));
+
+        assert!(SsrfPolicy::from_config(&SsrfConfig {
+            allowed_networks: Some(vec!["10.0.0.0/33".to_owned()]),
+            denied_networks: None,
+            allowed_ports: None,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn allowed_ports() {
+        let policy = SsrfPolicy::from_config(&SsrfConfig {
+            allowed_networks: None,
+            denied_networks: None,
+            allowed_ports: Some(vec![80, 443]),
+        })
+        .unwrap();
+        let addr = |addr: &str| addr.parse::<SocketAddr>().unwrap();
+
+        assert!(policy.is_allowed(addr("8.8.8.8:443")));
+        assert!(!policy.is_allowed(addr("8.8.8.8:22")));
+        assert!(!policy.is_allowed(addr("127.0.0.1:80")));
+        assert!(SsrfPolicy::default().is_allowed(addr("8.8.8.8:22")));
+    }
+}
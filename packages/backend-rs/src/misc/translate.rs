@@ -8,14 +8,12 @@ pub enum Error {
     #[doc = "Database error"]
     #[error(transparent)]
     Db(#[from] sea_orm::DbErr),
-    #[error("failed to acquire an HTTP client")]
-    HttpClient(#[from] http_client::Error),
     #[error("invalid http request body")]
     InvalidRequestBody(#[from] isahc::http::Error),
     #[error("http request failed")]
-    HttpRequest(#[from] isahc::Error),
-    #[error("failed to serialize the request body")]
-    Serialize(#[from] serde_json::Error),
+    Fetch(#[from] http_client::FetchError),
+    #[error("failed to (de)serialize JSON")]
+    Json(#[from] serde_json::Error),
     #[error("Libretranslate API url is not set")]
     MissingApiUrl,
     #[error("DeepL API key is not set")]
@@ -24,8 +22,6 @@ pub enum Error {
     NoResponse,
     #[error("translator is not set")]
     NoTranslator,
-    #[error("access to this URL is not allowed")]
-    UnsafeUrl,
 }
 
 #[macros::export(object)]
@@ -97,9 +93,8 @@ pub async fn translate(
 }
 
 mod deepl_translate {
-    use crate::{misc::is_safe_url::is_safe_url, util::http_client};
-    use futures_util::AsyncReadExt;
-    use isahc::{AsyncReadResponseExt, Request};
+    use crate::util::http_client::{self, FetchOptions};
+    use isahc::Request;
     use serde::Deserialize;
     use serde_json::json;
 
@@ -121,18 +116,12 @@ mod deepl_translate {
         api_key: &str,
         is_pro: bool,
     ) -> Result<super::Translation, super::Error> {
-        let client = http_client::client()?;
-
         let api_url = if is_pro {
             "https://api.deepl.com/v2/translate"
         } else {
             "https://api-free.deepl.com/v2/translate"
         };
 
-        if !is_safe_url(api_url) {
-            return Err(super::Error::UnsafeUrl);
-        }
-
         let to_zh_hant_tw = super::is_zh_hant_tw(target_lang);
 
         let mut target_lang = target_lang.split('-').collect::<Vec<&str>>()[0];
@@ -161,15 +150,14 @@ mod deepl_translate {
         let request = Request::post(api_url)
             .header("Authorization", format!("DeepL-Auth-Key {}", api_key))
             .header("Content-Type", "application/json")
-            .body(serde_json::to_string(&body)?)?;
+            .body(serde_json::to_vec(&body)?)?;
 
         // Read up to 1 MiB of the response body
-        let response = client
-            .send_async(request)
-            .await?
-            .map(|body| body.take(1024 * 1024))
-            .json::<Response>()
-            .await?;
+        let options = FetchOptions {
+            max_body_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let response: Response = http_client::fetch(request, &options).await?.json()?;
 
         let result = response
             .translations
@@ -203,9 +191,8 @@ mod deepl_translate {
 }
 
 mod libre_translate {
-    use crate::{misc::is_safe_url::is_safe_url, util::http_client};
-    use futures_util::AsyncReadExt;
-    use isahc::{AsyncReadResponseExt, Request};
+    use crate::util::http_client::{self, FetchOptions};
+    use isahc::Request;
     use serde::Deserialize;
     use serde_json::json;
 
@@ -228,12 +215,6 @@ mod libre_translate {
         api_url: &str,
         api_key: Option<&str>,
     ) -> Result<super::Translation, super::Error> {
-        if !is_safe_url(api_url) {
-            return Err(super::Error::UnsafeUrl);
-        }
-
-        let client = http_client::client()?;
-
         let target_lang = if super::is_zh_hant_tw(target_lang) {
             "zt"
         } else {
@@ -264,15 +245,14 @@ mod libre_translate {
 
         let request = Request::post(api_url)
             .header("Content-Type", "application/json")
-            .body(serde_json::to_string(&body)?)?;
+            .body(serde_json::to_vec(&body)?)?;
 
         // Read up to 1 MiB of the response body
-        let result = client
-            .send_async(request)
-            .await?
-            .map(|body| body.take(1024 * 1024))
-            .json::<Translation>()
-            .await?;
+        let options = FetchOptions {
+            max_body_size: 1024 * 1024,
+            ..Default::default()
+        };
+        let result: Translation = http_client::fetch(request, &options).await?.json()?;
 
         Ok(super::Translation {
             source_lang: source_lang
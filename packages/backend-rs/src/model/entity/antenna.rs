@@ -34,6 +34,13 @@ pub struct Model {
     pub keywords: Vec<String>,
     #[sea_orm(column_name = "excludeKeywords")]
     pub exclude_keywords: Vec<String>,
+    #[sea_orm(column_name = "wholeWord")]
+    pub whole_word: bool,
+    pub langs: Vec<String>,
+    #[sea_orm(column_name = "excludeLangs")]
+    pub exclude_langs: Vec<String>,
+    #[sea_orm(column_name = "notifyDigest")]
+    pub notify_digest: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -36,6 +36,8 @@ pub struct Model {
     pub custom_icon: Option<String>,
     #[sea_orm(column_name = "appAccessTokenId")]
     pub app_access_token_id: Option<String>,
+    #[sea_orm(column_name = "noteCount")]
+    pub note_count: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,76 @@
+//! Entities with default values for unit tests
+//!
+//! Override the fields that matter to a test with the struct update syntax, e.g.,
+//! `antenna::Model { keywords, ..fixture::antenna("id") }`.
+
+use super::entity::{
+    antenna, note,
+    sea_orm_active_enums::{AntennaSrc, NoteVisibility},
+};
+use chrono::Utc;
+
+/// Returns an antenna of `owner` named after its ID that accepts all notes.
+pub(crate) fn antenna(id: &str) -> antenna::Model {
+    antenna::Model {
+        id: id.to_owned(),
+        created_at: Utc::now().into(),
+        user_id: "owner".to_owned(),
+        name: id.to_owned(),
+        src: AntennaSrc::All,
+        user_list_id: None,
+        with_file: false,
+        expression: None,
+        notify: false,
+        case_sensitive: false,
+        with_replies: false,
+        user_group_joining_id: None,
+        users: vec![],
+        instances: vec![],
+        keywords: vec![],
+        exclude_keywords: vec![],
+        whole_word: false,
+        langs: vec![],
+        exclude_langs: vec![],
+        notify_digest: false,
+    }
+}
+
+/// Returns a public note without text by a local user.
+pub(crate) fn note(id: &str, user_id: &str) -> note::Model {
+    note::Model {
+        id: id.to_owned(),
+        created_at: Utc::now().into(),
+        reply_id: None,
+        renote_id: None,
+        text: None,
+        name: None,
+        cw: None,
+        user_id: user_id.to_owned(),
+        local_only: false,
+        renote_count: 0,
+        replies_count: 0,
+        reactions: serde_json::json!({}),
+        visibility: NoteVisibility::Public,
+        uri: None,
+        score: 0,
+        file_ids: vec![],
+        attached_file_types: vec![],
+        visible_user_ids: vec![],
+        mentions: vec![],
+        mentioned_remote_users: "[]".to_owned(),
+        emojis: vec![],
+        tags: vec![],
+        has_poll: false,
+        user_host: None,
+        reply_user_id: None,
+        reply_user_host: None,
+        renote_user_id: None,
+        renote_user_host: None,
+        url: None,
+        channel_id: None,
+        thread_id: None,
+        updated_at: None,
+        lang: None,
+        scheduled_at: None,
+    }
+}
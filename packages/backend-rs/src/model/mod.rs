@@ -1,3 +1,5 @@
 //! Database structure, auto-generated by [sea_orm]
 
 pub mod entity;
+#[cfg(test)]
+pub(crate) mod fixture;
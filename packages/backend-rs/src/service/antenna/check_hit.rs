@@ -4,6 +4,11 @@ use crate::{
     database::db_conn,
     federation::acct::Acct,
     model::entity::{antenna, blocking, following, note, sea_orm_active_enums::*},
+    service::antenna::{
+        expression::{Context, Expr},
+        matcher::{accepts_lang, Keywords, NoteText},
+        members::Members,
+    },
 };
 use chrono::Duration;
 use sea_orm::{prelude::*, QuerySelect};
@@ -17,24 +22,43 @@ pub enum AntennaCheckError {
     Cache(#[from] cache::redis::Error),
 }
 
-fn match_all(space_separated_words: &str, text: &str, case_sensitive: bool) -> bool {
-    if case_sensitive {
-        space_separated_words
-            .split_whitespace()
-            .all(|word| text.contains(word))
-    } else {
-        space_separated_words
-            .to_lowercase()
-            .split_whitespace()
-            .all(|word| text.to_lowercase().contains(word))
+/// Compiled conditions on the texts of notes
+pub(super) enum TextFilter {
+    /// the expression, which replaces keywords and excluded keywords if it is set
+    Expression(Expr),
+    Keywords(Keywords),
+    /// the expression is invalid, so the antenna accepts no notes
+    Invalid,
+}
+
+impl TextFilter {
+    pub(super) fn new(antenna: &antenna::Model) -> Self {
+        let Some(expression) = antenna
+            .expression
+            .as_deref()
+            .filter(|expression| !expression.trim().is_empty())
+        else {
+            return Self::Keywords(Keywords::new(antenna));
+        };
+
+        match expression.parse::<Expr>() {
+            Ok(expr) => Self::Expression(expr),
+            Err(err) => {
+                tracing::warn!("invalid expression of antenna {}: {}", antenna.id, err);
+                Self::Invalid
+            }
+        }
     }
 }
 
+#[tracing::instrument(name = "antenna.check_hit", skip_all, fields(antenna.id = %antenna.id, note.id = %note.id))]
 pub(super) async fn check_hit_antenna(
     antenna: &antenna::Model,
     note: &note::Model,
-    note_all_texts: &[String],
+    note_all_texts: &[NoteText<'_>],
     note_author: &Acct,
+    members: &Members,
+    filter: &TextFilter,
 ) -> Result<bool, AntennaCheckError> {
     if note.visibility == NoteVisibility::Specified {
         return Ok(false);
@@ -74,25 +98,27 @@ pub(super) async fn check_hit_antenna(
         }
     }
 
-    // "Home", "Group", "List" sources are currently disabled
-
-    let has_keyword = antenna.keywords.iter().any(|words| {
-        note_all_texts
-            .iter()
-            .any(|text| match_all(words, text, antenna.case_sensitive))
-    });
-
-    if !has_keyword {
+    if !members.accepts(antenna, &note.user_id) {
         return Ok(false);
     }
 
-    let has_excluded_word = antenna.exclude_keywords.iter().any(|words| {
-        note_all_texts
-            .iter()
-            .any(|text| match_all(words, text, antenna.case_sensitive))
-    });
+    if !accepts_lang(antenna, note.lang.as_deref()) {
+        return Ok(false);
+    }
 
-    if has_excluded_word {
+    let matched = match filter {
+        TextFilter::Expression(expr) => expr.matches(&Context {
+            note,
+            author: note_author,
+            texts: note_all_texts,
+            case_sensitive: antenna.case_sensitive,
+            whole_word: antenna.whole_word,
+            local_host: &CONFIG.host,
+        }),
+        TextFilter::Keywords(keywords) => keywords.matches(note_all_texts),
+        TextFilter::Invalid => false,
+    };
+    if !matched {
         return Ok(false);
     }
 
@@ -159,21 +185,3 @@ pub(super) async fn check_hit_antenna(
 
     Ok(true)
 }
-
-#[cfg(test)]
-mod unit_test {
-    use super::match_all;
-    use pretty_assertions::assert_eq;
-
-    #[test]
-    fn check_match_string() {
-        assert_eq!(match_all("Apple", "apple and banana", false), true);
-        assert_eq!(match_all("Apple", "apple and banana", true), false);
-        assert_eq!(match_all("Apple Banana", "apple and banana", false), true);
-        assert_eq!(match_all("Apple Banana", "apple and cinnamon", true), false);
-        assert_eq!(
-            match_all("Apple Banana", "apple and cinnamon", false),
-            false
-        );
-    }
-}
@@ -0,0 +1,554 @@
+//! Boolean expressions to select notes for antennas
+//!
+//! ```text
+//! (rust OR ferris) AND NOT crypto AND lang:en AND has:media AND from:@alice@host AND host:*.social
+//! ```
+//!
+//! * Keywords (optionally quoted, e.g., `"hello world"`) match if any text of the note
+//!   contains them, respecting the case sensitivity and whole-word settings of the antenna
+//! * `AND` may be omitted (`rust ferris` is the same as `rust AND ferris`)
+//! * `NOT` binds tighter than `AND`, which binds tighter than `OR`
+//! * `lang:en` matches notes in English (including regional variants such as `en-US`)
+//! * `has:media` and `has:poll` match notes with attached files and polls
+//! * `from:@alice` and `from:@alice@host` match notes by the user
+//! * `host:example.com` matches notes by users on the server (`*` matches any string)
+
+use crate::{
+    federation::acct::Acct,
+    model::entity::note,
+    service::antenna::matcher::{lang_matches, MatchOptions, NoteText},
+};
+use std::str::FromStr;
+
+/// Maximum nesting depth of parentheses and `NOT`s
+const MAX_DEPTH: usize = 32;
+
+#[error_doc::errors]
+pub enum ExpressionError {
+    #[error("expression is empty")]
+    Empty,
+    #[doc = "Expression ends with an operator or an open parenthesis"]
+    #[error("unexpected end of expression at position {0}")]
+    UnexpectedEnd(usize),
+    #[doc = "Misplaced operator or parenthesis"]
+    #[error("unexpected `{0}` at position {1}")]
+    UnexpectedToken(String, usize),
+    #[doc = "Quotation mark is not closed"]
+    #[error("unclosed quotation mark at position {0}")]
+    UnclosedQuote(usize),
+    #[doc = "Parenthesis is not closed"]
+    #[error("unclosed parenthesis at position {0}")]
+    UnclosedParenthesis(usize),
+    #[doc = "Invalid value for a filter (e.g., `has:something`)"]
+    #[error("invalid value for `{0}:` at position {1}")]
+    InvalidValue(String, usize),
+    #[doc = "Too many nested parentheses or `NOT`s"]
+    #[error("expression is nested too deeply at position {0}")]
+    TooDeep(usize),
+}
+
+impl ExpressionError {
+    /// Returns the position (in characters) where the error occurred.
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            Self::Empty => None,
+            Self::UnexpectedEnd(position)
+            | Self::UnexpectedToken(_, position)
+            | Self::UnclosedQuote(position)
+            | Self::UnclosedParenthesis(position)
+            | Self::InvalidValue(_, position)
+            | Self::TooDeep(position) => Some(*position),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Keyword(String),
+    /// lowercase language tag
+    Lang(String),
+    HasMedia,
+    HasPoll,
+    From {
+        username: String,
+        /// [None] for local users
+        host: Option<String>,
+    },
+    /// lowercase host pattern
+    Host(String),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+    /// quoted keyword
+    Quoted(String),
+    Word(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::LeftParen => "(".to_owned(),
+            Self::RightParen => ")".to_owned(),
+            Self::And => "AND".to_owned(),
+            Self::Or => "OR".to_owned(),
+            Self::Not => "NOT".to_owned(),
+            Self::Quoted(keyword) => format!("\"{}\"", keyword),
+            Self::Word(word) => word.to_owned(),
+        }
+    }
+}
+
+/// Splits the expression into tokens and their positions.
+fn tokenize(expression: &str) -> Result<Vec<(Token, usize)>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().enumerate().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push((Token::LeftParen, position)),
+            ')' => tokens.push((Token::RightParen, position)),
+            '"' => {
+                let mut keyword = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => keyword.push(c),
+                            None => return Err(ExpressionError::UnclosedQuote(position)),
+                        },
+                        Some((_, c)) => keyword.push(c),
+                        None => return Err(ExpressionError::UnclosedQuote(position)),
+                    }
+                }
+                tokens.push((Token::Quoted(keyword), position));
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some((_, c)) =
+                    chars.next_if(|&(_, c)| !c.is_whitespace() && !matches!(c, '(' | ')' | '"'))
+                {
+                    word.push(c);
+                }
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                };
+                tokens.push((token, position));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Converts a word into a filter if it starts with a known field name (e.g., `lang:`),
+/// or a keyword otherwise.
+fn parse_term(word: String, position: usize) -> Result<Expr, ExpressionError> {
+    let Some((field, value)) = word.split_once(':') else {
+        return Ok(Expr::Keyword(word));
+    };
+    let invalid = || ExpressionError::InvalidValue(field.to_owned(), position);
+
+    match field {
+        "lang" => {
+            if value.is_empty() || !value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                return Err(invalid());
+            }
+            Ok(Expr::Lang(value.to_ascii_lowercase()))
+        }
+        "has" => match value {
+            "media" => Ok(Expr::HasMedia),
+            "poll" => Ok(Expr::HasPoll),
+            _ => Err(invalid()),
+        },
+        "from" => {
+            let acct = value.strip_prefix('@').unwrap_or(value);
+            if acct.matches('@').count() > 1 {
+                return Err(invalid());
+            }
+            let acct = Acct::from_str(value).map_err(|_| invalid())?;
+            if acct.username.is_empty() || acct.host.as_ref().is_some_and(String::is_empty) {
+                return Err(invalid());
+            }
+            Ok(Expr::From {
+                username: acct.username,
+                host: acct.host,
+            })
+        }
+        "host" => {
+            if value.is_empty() || value.contains('@') {
+                return Err(invalid());
+            }
+            Ok(Expr::Host(value.to_ascii_lowercase()))
+        }
+        _ => Ok(Expr::Keyword(word)),
+    }
+}
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<(Token, usize)>>,
+    /// length of the expression in characters
+    end: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Expr, ExpressionError> {
+        let mut operands = vec![self.and()?];
+        while self
+            .tokens
+            .next_if(|(token, _)| *token == Token::Or)
+            .is_some()
+        {
+            operands.push(self.and()?);
+        }
+        Ok(match operands.len() {
+            1 => operands.pop().unwrap(),
+            _ => Expr::Or(operands),
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, ExpressionError> {
+        let mut operands = vec![self.unary()?];
+        loop {
+            match self.tokens.peek() {
+                Some((Token::And, _)) => {
+                    self.tokens.next();
+                }
+                // implicit AND
+                Some((Token::LeftParen | Token::Not | Token::Quoted(_) | Token::Word(_), _)) => {}
+                _ => break,
+            }
+            operands.push(self.unary()?);
+        }
+        Ok(match operands.len() {
+            1 => operands.pop().unwrap(),
+            _ => Expr::And(operands),
+        })
+    }
+
+    fn nest<T>(
+        &mut self,
+        position: usize,
+        f: impl FnOnce(&mut Self) -> Result<T, ExpressionError>,
+    ) -> Result<T, ExpressionError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(ExpressionError::TooDeep(position));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        match self.tokens.next() {
+            Some((Token::Not, position)) => {
+                let operand = self.nest(position, Self::unary)?;
+                Ok(Expr::Not(Box::new(operand)))
+            }
+            Some((Token::LeftParen, position)) => {
+                let inner = self.nest(position, Self::or)?;
+                match self.tokens.next() {
+                    Some((Token::RightParen, _)) => Ok(inner),
+                    Some((token, position)) => {
+                        Err(ExpressionError::UnexpectedToken(token.describe(), position))
+                    }
+                    None => Err(ExpressionError::UnclosedParenthesis(position)),
+                }
+            }
+            Some((Token::Quoted(keyword), _)) => Ok(Expr::Keyword(keyword)),
+            Some((Token::Word(word), position)) => parse_term(word, position),
+            Some((token, position)) => {
+                Err(ExpressionError::UnexpectedToken(token.describe(), position))
+            }
+            None => Err(ExpressionError::UnexpectedEnd(self.end)),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ExpressionError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(expression)?;
+        if tokens.is_empty() {
+            return Err(ExpressionError::Empty);
+        }
+
+        let mut parser = Parser {
+            tokens: tokens.into_iter().peekable(),
+            end: expression.chars().count(),
+            depth: 0,
+        };
+        let expr = parser.or()?;
+
+        match parser.tokens.next() {
+            None => Ok(expr),
+            Some((token, position)) => {
+                Err(ExpressionError::UnexpectedToken(token.describe(), position))
+            }
+        }
+    }
+}
+
+/// Returns whether `text` matches `pattern`, in which `*` matches any string.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // no wildcard
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Note to evaluate an expression against
+pub struct Context<'a> {
+    pub note: &'a note::Model,
+    pub author: &'a Acct,
+    /// texts of the note (see [crate::misc::note::elaborate])
+    pub texts: &'a [NoteText<'a>],
+    pub case_sensitive: bool,
+    /// whether keywords only match whole words
+    pub whole_word: bool,
+    /// host of this server
+    pub local_host: &'a str,
+}
+
+impl Context<'_> {
+    fn contains(&self, keyword: &str) -> bool {
+        let options = MatchOptions {
+            case_sensitive: self.case_sensitive,
+            whole_word: self.whole_word,
+        };
+        self.texts
+            .iter()
+            .any(|text| options.contains(text, keyword))
+    }
+
+    /// Returns the lowercase host of the author (the local host for local users).
+    fn author_host(&self) -> String {
+        self.author
+            .host
+            .as_deref()
+            .unwrap_or(self.local_host)
+            .to_ascii_lowercase()
+    }
+}
+
+impl Expr {
+    /// Returns whether the note matches the expression.
+    pub fn matches(&self, context: &Context) -> bool {
+        match self {
+            Self::Keyword(keyword) => context.contains(keyword),
+            Self::Lang(lang) => context
+                .note
+                .lang
+                .as_ref()
+                .is_some_and(|note_lang| lang_matches(note_lang, lang)),
+            Self::HasMedia => !context.note.file_ids.is_empty(),
+            Self::HasPoll => context.note.has_poll,
+            Self::From { username, host } => {
+                let host = host.as_deref().unwrap_or(context.local_host);
+                username.eq_ignore_ascii_case(&context.author.username)
+                    && host.eq_ignore_ascii_case(&context.author_host())
+            }
+            Self::Host(pattern) => glob_match(pattern, &context.author_host()),
+            Self::Not(operand) => !operand.matches(context),
+            Self::And(operands) => operands.iter().all(|operand| operand.matches(context)),
+            Self::Or(operands) => operands.iter().any(|operand| operand.matches(context)),
+        }
+    }
+}
+
+/// Checks whether `expression` is a valid antenna expression.
+///
+/// The error message contains the position (in characters) of the problem.
+#[macros::export]
+pub fn validate_antenna_expression(expression: &str) -> Result<(), ExpressionError> {
+    expression.parse::<Expr>().map(|_| ())
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{glob_match, Context, Expr, ExpressionError};
+    use crate::{
+        federation::acct::Acct,
+        model::{entity::note, fixture},
+        service::antenna::matcher::NoteText,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn keyword(keyword: &str) -> Expr {
+        Expr::Keyword(keyword.to_owned())
+    }
+
+    fn acct(username: &str, host: Option<&str>) -> Acct {
+        Acct {
+            username: username.to_owned(),
+            host: host.map(str::to_owned),
+        }
+    }
+
+    fn note(lang: Option<&str>, file_ids: &[&str]) -> note::Model {
+        note::Model {
+            file_ids: file_ids.iter().map(|&id| id.to_owned()).collect(),
+            lang: lang.map(str::to_owned),
+            ..fixture::note("9zfw7an1c5fyr1hk", "9zfw6s8fc5fyr1hk")
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("rust".parse::<Expr>().unwrap(), keyword("rust"));
+        assert_eq!(
+            "rust ferris OR \"hello world\"".parse::<Expr>().unwrap(),
+            Expr::Or(vec![
+                Expr::And(vec![keyword("rust"), keyword("ferris")]),
+                keyword("hello world"),
+            ])
+        );
+        assert_eq!(
+            "(rust OR ferris) AND NOT crypto AND lang:EN AND has:media AND from:@alice@host AND host:*.social"
+                .parse::<Expr>()
+                .unwrap(),
+            Expr::And(vec![
+                Expr::Or(vec![keyword("rust"), keyword("ferris")]),
+                Expr::Not(Box::new(keyword("crypto"))),
+                Expr::Lang("en".to_owned()),
+                Expr::HasMedia,
+                Expr::From {
+                    username: "alice".to_owned(),
+                    host: Some("host".to_owned()),
+                },
+                Expr::Host("*.social".to_owned()),
+            ])
+        );
+        assert_eq!(
+            "NOT NOT \"a \\\"b\\\"\" https://example.com"
+                .parse::<Expr>()
+                .unwrap(),
+            Expr::And(vec![
+                Expr::Not(Box::new(Expr::Not(Box::new(keyword("a \"b\""))))),
+                keyword("https://example.com"),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |expression: &str| expression.parse::<Expr>().unwrap_err();
+
+        assert!(matches!(error("  "), ExpressionError::Empty));
+        assert!(matches!(
+            error("rust AND"),
+            ExpressionError::UnexpectedEnd(8)
+        ));
+        assert!(matches!(
+            error("rust OR OR ferris"),
+            ExpressionError::UnexpectedToken(token, 8) if token == "OR"
+        ));
+        assert!(matches!(
+            error("rust)"),
+            ExpressionError::UnexpectedToken(token, 4) if token == ")"
+        ));
+        assert!(matches!(
+            error("ラスト (a OR b"),
+            ExpressionError::UnclosedParenthesis(4)
+        ));
+        assert!(matches!(error("a \"b"), ExpressionError::UnclosedQuote(2)));
+        assert!(matches!(
+            error("a has:video"),
+            ExpressionError::InvalidValue(field, 2) if field == "has"
+        ));
+        assert!(matches!(
+            error("from:@a@b@c"),
+            ExpressionError::InvalidValue(field, 0) if field == "from"
+        ));
+        assert!(matches!(error("lang:"), ExpressionError::InvalidValue(..)));
+        assert!(matches!(
+            error(&"(".repeat(100)),
+            ExpressionError::TooDeep(32)
+        ));
+        assert_eq!(error("rust AND").position(), Some(8));
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("example.com", "example.com"));
+        assert!(!glob_match("example.com", "example.com.evil"));
+        assert!(glob_match("*.social", "mastodon.social"));
+        assert!(!glob_match("*.social", "mastodon.socials"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("a*b*c", "abc"));
+        assert!(!glob_match("a*bc*c", "abc"));
+    }
+
+    #[test]
+    fn evaluate() {
+        let expr: Expr = "(rust OR ferris) NOT crypto lang:en has:media from:alice host:*.example"
+            .parse()
+            .unwrap();
+        let note = note(Some("en-US"), &["file"]);
+        let author = acct("Alice", None);
+        let texts = [NoteText::new("Ferris says hi")];
+        let mut context = Context {
+            note: &note,
+            author: &author,
+            texts: &texts,
+            case_sensitive: false,
+            whole_word: false,
+            local_host: "local.example",
+        };
+
+        assert!(expr.matches(&context));
+
+        context.case_sensitive = true;
+        assert!(!expr.matches(&context));
+        context.case_sensitive = false;
+
+        context.whole_word = true;
+        assert!(!"ferr".parse::<Expr>().unwrap().matches(&context));
+        assert!("ferris".parse::<Expr>().unwrap().matches(&context));
+        context.whole_word = false;
+
+        let texts = [NoteText::new("Ferris says hi"), NoteText::new("crypto")];
+        context.texts = &texts;
+        assert!(!expr.matches(&context));
+
+        let remote = acct("alice", Some("remote.example"));
+        context.author = &remote;
+        assert!(!"from:alice".parse::<Expr>().unwrap().matches(&context));
+        assert!("from:@ALICE@Remote.Example"
+            .parse::<Expr>()
+            .unwrap()
+            .matches(&context));
+        assert!("host:*.example".parse::<Expr>().unwrap().matches(&context));
+        assert!(!"lang:e".parse::<Expr>().unwrap().matches(&context));
+        assert!(!"has:poll".parse::<Expr>().unwrap().matches(&context));
+    }
+}
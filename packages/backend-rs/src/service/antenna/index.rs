@@ -0,0 +1,317 @@
+//! Inverted index to find antennas that may accept a note without checking all of them
+
+use crate::{
+    federation::acct::Acct,
+    model::entity::{antenna, sea_orm_active_enums::AntennaSrc},
+    service::antenna::{
+        check_hit::TextFilter,
+        matcher::{fold, KeywordGroup, NoteText},
+    },
+};
+use aho_corasick::AhoCorasick;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// Antennas and the index over their sources and keywords
+pub(super) struct AntennaIndex {
+    pub(super) antennas: Arc<[antenna::Model]>,
+    /// compiled keywords or expression of each antenna
+    filters: Vec<TextFilter>,
+    /// automaton over all distinct (folded) keywords
+    automaton: Option<AhoCorasick>,
+    /// (antenna, keyword group) pairs for each keyword in [AntennaIndex::automaton]
+    keyword_groups: Vec<Vec<(usize, usize)>>,
+    /// number of distinct keywords in each keyword group of each antenna
+    group_sizes: Vec<Vec<usize>>,
+    /// antennas that can't be prefiltered by keywords (those with an expression,
+    /// a regular expression, or an empty keyword group, which matches anything)
+    ///
+    /// antennas with invalid expressions are excluded, as they never match
+    unfiltered: Vec<usize>,
+    /// antennas of the "Users" source by lowercase username and host
+    by_user: HashMap<(String, Option<String>), Vec<usize>>,
+    /// antennas of the "Instances" source by lowercase host
+    by_host: HashMap<String, Vec<usize>>,
+    /// antennas whose sources are not indexed
+    any_author: HashSet<usize>,
+}
+
+impl AntennaIndex {
+    pub(super) fn new(antennas: Arc<[antenna::Model]>) -> Self {
+        let mut keyword_ids: HashMap<String, usize> = HashMap::new();
+        let mut keyword_groups: Vec<Vec<(usize, usize)>> = Vec::new();
+        let filters: Vec<TextFilter> = antennas.iter().map(TextFilter::new).collect();
+        let mut group_sizes = Vec::with_capacity(antennas.len());
+        let mut unfiltered = Vec::new();
+        let mut by_user: HashMap<(String, Option<String>), Vec<usize>> = HashMap::new();
+        let mut by_host: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut any_author = HashSet::new();
+
+        for (index, antenna) in antennas.iter().enumerate() {
+            match antenna.src {
+                AntennaSrc::Users => {
+                    for acct in antenna.users.iter().filter_map(|s| s.parse::<Acct>().ok()) {
+                        let key = (
+                            acct.username.to_lowercase(),
+                            acct.host.map(|host| host.to_ascii_lowercase()),
+                        );
+                        by_user.entry(key).or_default().push(index);
+                    }
+                }
+                AntennaSrc::Instances => {
+                    for host in &antenna.instances {
+                        by_host
+                            .entry(host.to_ascii_lowercase())
+                            .or_default()
+                            .push(index);
+                    }
+                }
+                _ => {
+                    any_author.insert(index);
+                }
+            }
+
+            let mut sizes = Vec::new();
+
+            match &filters[index] {
+                TextFilter::Expression(_) => unfiltered.push(index),
+                TextFilter::Invalid => {}
+                TextFilter::Keywords(keywords) => {
+                    for (group, keyword_group) in keywords.include.iter().enumerate() {
+                        let words: HashSet<String> = match keyword_group {
+                            KeywordGroup::Words(words) => words.iter().map(|w| fold(w)).collect(),
+                            KeywordGroup::Regex(_) => HashSet::new(),
+                        };
+                        if words.is_empty() {
+                            unfiltered.push(index);
+                        }
+                        sizes.push(words.len());
+                        for word in words {
+                            let next = keyword_ids.len();
+                            let id = *keyword_ids.entry(word).or_insert(next);
+                            if id == keyword_groups.len() {
+                                keyword_groups.push(Vec::new());
+                            }
+                            keyword_groups[id].push((index, group));
+                        }
+                    }
+                }
+            }
+            group_sizes.push(sizes);
+        }
+
+        let mut distinct_keywords = vec![String::new(); keyword_ids.len()];
+        for (keyword, id) in keyword_ids {
+            distinct_keywords[id] = keyword;
+        }
+        let automaton = match distinct_keywords.is_empty() {
+            true => None,
+            false => AhoCorasick::new(&distinct_keywords)
+                .inspect_err(|err| tracing::warn!("failed to index antenna keywords: {}", err))
+                .ok(),
+        };
+
+        Self {
+            antennas,
+            filters,
+            automaton,
+            keyword_groups,
+            group_sizes,
+            unfiltered,
+            by_user,
+            by_host,
+            any_author,
+        }
+    }
+
+    /// Returns the indices of antennas whose keywords may match the texts.
+    fn keyword_candidates(&self, texts: &[NoteText]) -> HashSet<usize> {
+        let mut candidates: HashSet<usize> = self.unfiltered.iter().copied().collect();
+
+        let automaton = match &self.automaton {
+            Some(automaton) => automaton,
+            None if self.keyword_groups.is_empty() => return candidates,
+            None => {
+                // the automaton couldn't be built
+                candidates.extend(0..self.antennas.len());
+                return candidates;
+            }
+        };
+
+        let mut found = vec![false; self.keyword_groups.len()];
+        for text in texts {
+            for m in automaton.find_overlapping_iter(&text.folded) {
+                found[m.pattern().as_usize()] = true;
+            }
+        }
+
+        let mut counts: HashMap<(usize, usize), usize> = HashMap::new();
+        for (id, _) in found.iter().enumerate().filter(|(_, &found)| found) {
+            for &(antenna, group) in &self.keyword_groups[id] {
+                let count = counts.entry((antenna, group)).or_default();
+                *count += 1;
+                if *count == self.group_sizes[antenna][group] {
+                    candidates.insert(antenna);
+                }
+            }
+        }
+
+        candidates
+    }
+
+    /// Returns the antennas (with their compiled filters) that may accept the note,
+    /// which need to be checked further.
+    ///
+    /// # Arguments
+    ///
+    /// * `texts` : texts of the note (see [crate::misc::note::elaborate])
+    /// * `author` : author of the note
+    /// * `local_host` : host of this server
+    pub(super) fn candidates(
+        &self,
+        texts: &[NoteText],
+        author: &Acct,
+        local_host: &str,
+    ) -> Vec<(&antenna::Model, &TextFilter)> {
+        let keyword_candidates = self.keyword_candidates(texts);
+
+        let host = author
+            .host
+            .as_deref()
+            .unwrap_or(local_host)
+            .to_ascii_lowercase();
+        let user_key = (
+            author.username.to_lowercase(),
+            author.host.as_ref().map(|host| host.to_ascii_lowercase()),
+        );
+        let source_candidates = self
+            .by_user
+            .get(&user_key)
+            .into_iter()
+            .chain(self.by_host.get(&host))
+            .flatten()
+            .chain(self.any_author.iter());
+
+        let mut candidates: Vec<usize> = source_candidates
+            .filter(|index| keyword_candidates.contains(index))
+            .copied()
+            .collect::<HashSet<usize>>()
+            .into_iter()
+            .collect();
+        candidates.sort_unstable();
+
+        candidates
+            .into_iter()
+            .map(|index| (&self.antennas[index], &self.filters[index]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::AntennaIndex;
+    use crate::{
+        federation::acct::Acct,
+        model::{
+            entity::{antenna, sea_orm_active_enums::AntennaSrc},
+            fixture,
+        },
+        service::antenna::matcher::NoteText,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn antenna(id: &str, src: AntennaSrc, keywords: &[&str]) -> antenna::Model {
+        antenna::Model {
+            src,
+            keywords: keywords.iter().map(|&words| words.to_owned()).collect(),
+            ..fixture::antenna(id)
+        }
+    }
+
+    fn acct(username: &str, host: Option<&str>) -> Acct {
+        Acct {
+            username: username.to_owned(),
+            host: host.map(str::to_owned),
+        }
+    }
+
+    fn ids<T>(antennas: Vec<(&antenna::Model, T)>) -> Vec<&str> {
+        antennas
+            .into_iter()
+            .map(|(antenna, _)| antenna.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn prefilter_keywords() {
+        let mut expression = antenna("expression", AntennaSrc::All, &[]);
+        expression.expression = Some("NOT rust".to_owned());
+        let mut invalid = antenna("invalid", AntennaSrc::All, &[]);
+        invalid.expression = Some("rust AND (".to_owned());
+        let index = AntennaIndex::new(
+            vec![
+                antenna("rust", AntennaSrc::All, &["rust"]),
+                antenna("rust_ferris", AntennaSrc::Home, &["rust ferris", "crab"]),
+                antenna("no_keywords", AntennaSrc::All, &[]),
+                expression,
+                antenna("regex", AntennaSrc::All, &["/c[au]t/"]),
+                invalid,
+            ]
+            .into(),
+        );
+        let author = acct("alice", None);
+        let texts = |texts: &[&'static str]| -> Vec<NoteText> {
+            texts.iter().map(|&text| NoteText::new(text)).collect()
+        };
+
+        assert_eq!(
+            ids(index.candidates(&texts(&["I love Rust"]), &author, "local")),
+            vec!["rust", "expression", "regex"]
+        );
+        assert_eq!(
+            ids(index.candidates(&texts(&["RUST", "Ferris"]), &author, "local")),
+            vec!["rust", "rust_ferris", "expression", "regex"]
+        );
+        assert_eq!(
+            ids(index.candidates(&texts(&["a crab"]), &author, "local")),
+            vec!["rust_ferris", "expression", "regex"]
+        );
+        assert_eq!(
+            ids(index.candidates(&texts(&["nothing"]), &author, "local")),
+            vec!["expression", "regex"]
+        );
+    }
+
+    #[test]
+    fn prefilter_sources() {
+        let mut users = antenna("users", AntennaSrc::Users, &["rust"]);
+        users.users = vec!["@Alice@Example.com".to_owned(), "bob".to_owned()];
+        let mut instances = antenna("instances", AntennaSrc::Instances, &["rust"]);
+        instances.instances = vec!["Example.com".to_owned(), "local".to_owned()];
+        let index = AntennaIndex::new(vec![users, instances].into());
+        let texts = vec![NoteText::new("rust")];
+
+        assert_eq!(
+            ids(index.candidates(&texts, &acct("alice", Some("example.com")), "local")),
+            vec!["users", "instances"]
+        );
+        assert_eq!(
+            ids(index.candidates(&texts, &acct("carol", Some("example.com")), "local")),
+            vec!["instances"]
+        );
+        assert_eq!(
+            ids(index.candidates(&texts, &acct("bob", None), "local")),
+            vec!["users", "instances"]
+        );
+        assert_eq!(
+            ids(index.candidates(&texts, &acct("bob", Some("other.example")), "local")),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            ids(index.candidates(&[], &acct("bob", None), "local")),
+            Vec::<&str>::new()
+        );
+    }
+}
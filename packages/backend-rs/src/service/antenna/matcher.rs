@@ -0,0 +1,295 @@
+//! Keyword and language matching for antennas
+
+use crate::{misc::js_regex, model::entity::antenna};
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use std::collections::HashSet;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Lowercases `text` character by character.
+///
+/// This is a superset of [str::to_lowercase] (which is context-dependent for `Σ`)
+/// in the sense that a substring in the original text remains a substring.
+pub(super) fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| if c == 'ς' { 'σ' } else { c })
+        .collect()
+}
+
+/// Returns the byte offsets of the word boundaries in `text`.
+///
+/// The boundaries are determined by the Unicode word segmentation rules ([UAX #29]),
+/// under which each Han ideograph and Hiragana character is a word by itself, so a
+/// keyword in Chinese or Japanese matches without spaces around it.
+///
+/// [UAX #29]: https://www.unicode.org/reports/tr29/#Word_Boundaries
+fn word_boundaries(text: &str) -> HashSet<usize> {
+    text.split_word_bound_indices()
+        .map(|(offset, _)| offset)
+        .chain([text.len()])
+        .collect()
+}
+
+/// A text of a note, which is folded once and shared by all antennas
+pub struct NoteText<'a> {
+    pub(super) original: &'a str,
+    pub(super) folded: String,
+    original_boundaries: OnceCell<HashSet<usize>>,
+    folded_boundaries: OnceCell<HashSet<usize>>,
+}
+
+impl<'a> NoteText<'a> {
+    pub(super) fn new(text: &'a str) -> Self {
+        Self {
+            original: text,
+            folded: fold(text),
+            original_boundaries: OnceCell::new(),
+            folded_boundaries: OnceCell::new(),
+        }
+    }
+
+    /// Prepares the texts of a note (see [crate::misc::note::elaborate]).
+    pub(super) fn all(texts: &'a [String]) -> Vec<Self> {
+        texts.iter().map(|text| Self::new(text)).collect()
+    }
+
+    /// Returns the original or folded text and its word boundaries, which are computed
+    /// only when needed (i.e., for antennas that match whole words).
+    fn get(&self, folded: bool) -> (&str, &OnceCell<HashSet<usize>>) {
+        match folded {
+            true => (&self.folded, &self.folded_boundaries),
+            false => (self.original, &self.original_boundaries),
+        }
+    }
+}
+
+/// How keywords are compared with texts
+#[derive(Clone, Copy)]
+pub(super) struct MatchOptions {
+    pub case_sensitive: bool,
+    /// whether keywords only match whole words
+    pub whole_word: bool,
+}
+
+impl MatchOptions {
+    pub(super) fn of(antenna: &antenna::Model) -> Self {
+        Self {
+            case_sensitive: antenna.case_sensitive,
+            whole_word: antenna.whole_word,
+        }
+    }
+
+    /// Returns whether `text` contains `word`.
+    pub(super) fn contains(&self, text: &NoteText, word: &str) -> bool {
+        if word.is_empty() {
+            return true;
+        }
+
+        let (text, boundaries) = text.get(!self.case_sensitive);
+        let folded_word;
+        let word = match self.case_sensitive {
+            true => word,
+            false => {
+                folded_word = fold(word);
+                &folded_word
+            }
+        };
+
+        if !self.whole_word {
+            return text.contains(word);
+        }
+
+        let boundaries = boundaries.get_or_init(|| word_boundaries(text));
+        let mut start = 0;
+        while let Some(offset) = text[start..].find(word) {
+            let offset = start + offset;
+            if boundaries.contains(&offset) && boundaries.contains(&(offset + word.len())) {
+                return true;
+            }
+            // matches may overlap
+            start = offset + text[offset..].chars().next().map_or(1, char::len_utf8);
+        }
+        false
+    }
+}
+
+/// An item of `keywords` or `exclude_keywords` of an antenna
+pub(super) enum KeywordGroup {
+    /// space-separated words, all of which must be contained
+    Words(Vec<String>),
+    /// JavaScript-style regular expression (e.g., `/foo|bar/i`)
+    Regex(Regex),
+}
+
+impl KeywordGroup {
+    pub(super) fn new(group: &str) -> Self {
+        if js_regex::split_literal(group).is_ok() {
+            match js_regex::compile(group) {
+                Ok(regex) => return Self::Regex(regex),
+                // treat it as a word (e.g., `/usr/bin`)
+                Err(err) => tracing::debug!("{} is not a valid regex: {}", group, err),
+            }
+        }
+        Self::Words(group.split_whitespace().map(str::to_owned).collect())
+    }
+
+    /// Returns whether the keyword group matches `text`.
+    ///
+    /// The match options don't apply to regular expressions, which have their own flags.
+    pub(super) fn matches(&self, text: &NoteText, options: MatchOptions) -> bool {
+        match self {
+            Self::Words(words) => words.iter().all(|word| options.contains(text, word)),
+            Self::Regex(regex) => regex.is_match(text.original),
+        }
+    }
+}
+
+/// Compiled keywords of an antenna
+pub(super) struct Keywords {
+    pub(super) include: Vec<KeywordGroup>,
+    pub(super) exclude: Vec<KeywordGroup>,
+    options: MatchOptions,
+}
+
+impl Keywords {
+    pub(super) fn new(antenna: &antenna::Model) -> Self {
+        Self {
+            include: antenna
+                .keywords
+                .iter()
+                .map(|g| KeywordGroup::new(g))
+                .collect(),
+            exclude: antenna
+                .exclude_keywords
+                .iter()
+                .map(|g| KeywordGroup::new(g))
+                .collect(),
+            options: MatchOptions::of(antenna),
+        }
+    }
+
+    /// Returns whether any of the texts matches any keyword group and none of the texts
+    /// matches the excluded keyword groups.
+    pub(super) fn matches(&self, texts: &[NoteText]) -> bool {
+        let any_match = |groups: &[KeywordGroup]| {
+            groups
+                .iter()
+                .any(|group| texts.iter().any(|text| group.matches(text, self.options)))
+        };
+        any_match(&self.include) && !any_match(&self.exclude)
+    }
+}
+
+/// Returns whether the language code `lang` (e.g., `en`) matches `note_lang` (e.g., `en-US`).
+pub(super) fn lang_matches(note_lang: &str, lang: &str) -> bool {
+    note_lang.eq_ignore_ascii_case(lang)
+        || note_lang
+            .get(..lang.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(lang))
+            && note_lang[lang.len()..].starts_with('-')
+}
+
+/// Returns whether the language of a note is allowed by the antenna.
+///
+/// If the antenna has a list of allowed languages, notes without languages are rejected.
+pub(super) fn accepts_lang(antenna: &antenna::Model, note_lang: Option<&str>) -> bool {
+    let in_list = |langs: &[String]| {
+        note_lang.is_some_and(|note_lang| langs.iter().any(|lang| lang_matches(note_lang, lang)))
+    };
+    (antenna.langs.is_empty() || in_list(&antenna.langs)) && !in_list(&antenna.exclude_langs)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{accepts_lang, fold, lang_matches, KeywordGroup, MatchOptions, NoteText};
+    use crate::model::fixture;
+    use pretty_assertions::assert_eq;
+
+    const SUBSTRING: MatchOptions = MatchOptions {
+        case_sensitive: false,
+        whole_word: false,
+    };
+    const WHOLE_WORD: MatchOptions = MatchOptions {
+        case_sensitive: false,
+        whole_word: true,
+    };
+
+    fn text(text: &str) -> NoteText<'_> {
+        NoteText::new(text)
+    }
+
+    #[test]
+    fn fold_text() {
+        assert_eq!(fold("HeLLo"), "hello");
+        assert!(fold("ΟΔΟΣ").contains(&fold("ς")));
+        assert!(fold(&"ΑΣ Β".to_lowercase()).contains(&fold("Σ")));
+    }
+
+    #[test]
+    fn match_words() {
+        let case_sensitive = MatchOptions {
+            case_sensitive: true,
+            whole_word: false,
+        };
+
+        assert!(SUBSTRING.contains(&text("apple and banana"), "Apple"));
+        assert!(!case_sensitive.contains(&text("apple and banana"), "Apple"));
+        assert!(SUBSTRING.contains(&text("ΟΔΟΣ"), "οδος"));
+
+        assert!(SUBSTRING.contains(&text("concatenate"), "cat"));
+        assert!(!WHOLE_WORD.contains(&text("concatenate"), "cat"));
+        assert!(WHOLE_WORD.contains(&text("a cat, concatenated"), "CAT"));
+        assert!(WHOLE_WORD.contains(&text("I like C++!"), "c++"));
+        assert!(WHOLE_WORD.contains(&text("café au lait"), "café"));
+        assert!(!WHOLE_WORD.contains(&text("cafés"), "café"));
+        // overlapping candidates
+        assert!(WHOLE_WORD.contains(&text("aaa aa"), "aa"));
+
+        // Chinese and Japanese texts are not separated by spaces
+        assert!(WHOLE_WORD.contains(&text("今日は東京に行きます"), "東京"));
+        assert!(WHOLE_WORD.contains(&text("我喜欢猫"), "猫"));
+        assert!(!WHOLE_WORD.contains(&text("テストケース"), "テスト"));
+    }
+
+    #[test]
+    fn match_keyword_groups() {
+        let words = KeywordGroup::new("Apple Banana");
+        assert!(words.matches(&text("apple and banana"), SUBSTRING));
+        assert!(!words.matches(&text("apple and cinnamon"), SUBSTRING));
+
+        let regex = KeywordGroup::new(r"/\bcats?\b/i");
+        assert!(matches!(regex, KeywordGroup::Regex(_)));
+        assert!(regex.matches(&text("I have two CATS"), SUBSTRING));
+        assert!(!regex.matches(&text("concatenate"), SUBSTRING));
+
+        // not a valid regex
+        let path = KeywordGroup::new("/usr/bin");
+        assert!(matches!(path, KeywordGroup::Words(_)));
+        assert!(path.matches(&text("see /usr/bin/env"), SUBSTRING));
+    }
+
+    #[test]
+    fn match_langs() {
+        assert!(lang_matches("en", "en"));
+        assert!(lang_matches("en-US", "en"));
+        assert!(lang_matches("EN-us", "en-US"));
+        assert!(!lang_matches("eng", "en"));
+        assert!(!lang_matches("en", "en-US"));
+
+        let mut antenna = fixture::antenna("antenna");
+        assert!(accepts_lang(&antenna, None));
+        assert!(accepts_lang(&antenna, Some("ja")));
+
+        antenna.langs = vec!["en".to_owned(), "ja".to_owned()];
+        assert!(accepts_lang(&antenna, Some("en-GB")));
+        assert!(!accepts_lang(&antenna, Some("de")));
+        assert!(!accepts_lang(&antenna, None));
+
+        antenna.langs = vec![];
+        antenna.exclude_langs = vec!["de".to_owned()];
+        assert!(!accepts_lang(&antenna, Some("de-AT")));
+        assert!(accepts_lang(&antenna, Some("en")));
+        assert!(accepts_lang(&antenna, None));
+    }
+}
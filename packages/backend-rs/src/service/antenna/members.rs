@@ -0,0 +1,221 @@
+//! Authors whose notes can be added to "Home", "List", and "Group" antennas
+
+use crate::{
+    cache::Cache,
+    database::db_conn,
+    model::entity::{
+        antenna, following, sea_orm_active_enums::AntennaSrc, user_group_joining, user_list_joining,
+    },
+};
+use chrono::Duration;
+use sea_orm::{prelude::*, QuerySelect};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+static MEMBERS_CACHE: Cache<Arc<Members>> = Cache::new_with_ttl(Duration::minutes(1));
+
+/// Membership sets of antenna sources
+#[derive(Default)]
+pub(super) struct Members {
+    /// users followed by each user (including the user themselves)
+    home: HashMap<String, HashSet<String>>,
+    /// members of each user list
+    list: HashMap<String, HashSet<String>>,
+    /// members of the group of each group joining
+    group: HashMap<String, HashSet<String>>,
+}
+
+impl Members {
+    /// Returns whether the source of `antenna` accepts notes from `user_id`.
+    pub(super) fn accepts(&self, antenna: &antenna::Model, user_id: &str) -> bool {
+        let members = match antenna.src {
+            AntennaSrc::Home => self.home.get(&antenna.user_id),
+            AntennaSrc::List => antenna
+                .user_list_id
+                .as_ref()
+                .and_then(|id| self.list.get(id)),
+            AntennaSrc::Group => antenna
+                .user_group_joining_id
+                .as_ref()
+                .and_then(|id| self.group.get(id)),
+            _ => return true,
+        };
+        members.is_some_and(|members| members.contains(user_id))
+    }
+
+    pub(super) async fn fetch(antennas: &[antenna::Model]) -> Result<Self, DbErr> {
+        let db = db_conn().await?;
+        let mut members = Self::default();
+
+        let home_owners: HashSet<&String> = antennas
+            .iter()
+            .filter(|antenna| antenna.src == AntennaSrc::Home)
+            .map(|antenna| &antenna.user_id)
+            .collect();
+        let list_ids: HashSet<&String> = antennas
+            .iter()
+            .filter(|antenna| antenna.src == AntennaSrc::List)
+            .filter_map(|antenna| antenna.user_list_id.as_ref())
+            .collect();
+        let group_joining_ids: HashSet<&String> = antennas
+            .iter()
+            .filter(|antenna| antenna.src == AntennaSrc::Group)
+            .filter_map(|antenna| antenna.user_group_joining_id.as_ref())
+            .collect();
+
+        if !home_owners.is_empty() {
+            for owner in &home_owners {
+                members
+                    .home
+                    .entry(owner.to_string())
+                    .or_default()
+                    .insert(owner.to_string());
+            }
+            let follows: Vec<(String, String)> = following::Entity::find()
+                .select_only()
+                .columns([following::Column::FollowerId, following::Column::FolloweeId])
+                .filter(following::Column::FollowerId.is_in(home_owners))
+                .into_tuple()
+                .all(db)
+                .await?;
+            for (follower_id, followee_id) in follows {
+                members
+                    .home
+                    .entry(follower_id)
+                    .or_default()
+                    .insert(followee_id);
+            }
+        }
+
+        if !list_ids.is_empty() {
+            let joinings: Vec<(String, String)> = user_list_joining::Entity::find()
+                .select_only()
+                .columns([
+                    user_list_joining::Column::UserListId,
+                    user_list_joining::Column::UserId,
+                ])
+                .filter(user_list_joining::Column::UserListId.is_in(list_ids))
+                .into_tuple()
+                .all(db)
+                .await?;
+            for (list_id, user_id) in joinings {
+                members.list.entry(list_id).or_default().insert(user_id);
+            }
+        }
+
+        if !group_joining_ids.is_empty() {
+            // antennas refer to the owner's membership rather than the group itself
+            let groups: Vec<(String, String)> = user_group_joining::Entity::find()
+                .select_only()
+                .columns([
+                    user_group_joining::Column::Id,
+                    user_group_joining::Column::UserGroupId,
+                ])
+                .filter(user_group_joining::Column::Id.is_in(group_joining_ids))
+                .into_tuple()
+                .all(db)
+                .await?;
+            let joinings: Vec<(String, String)> = user_group_joining::Entity::find()
+                .select_only()
+                .columns([
+                    user_group_joining::Column::UserGroupId,
+                    user_group_joining::Column::UserId,
+                ])
+                .filter(
+                    user_group_joining::Column::UserGroupId
+                        .is_in(groups.iter().map(|(_, group_id)| group_id)),
+                )
+                .into_tuple()
+                .all(db)
+                .await?;
+
+            let mut group_members: HashMap<String, HashSet<String>> = HashMap::new();
+            for (group_id, user_id) in joinings {
+                group_members.entry(group_id).or_default().insert(user_id);
+            }
+            for (joining_id, group_id) in groups {
+                if let Some(users) = group_members.get(&group_id) {
+                    members.group.insert(joining_id, users.clone());
+                }
+            }
+        }
+
+        Ok(members)
+    }
+}
+
+/// Fetches the membership sets of all antennas and caches them.
+pub(super) async fn update(antennas: &[antenna::Model]) -> Result<Arc<Members>, DbErr> {
+    let members = Arc::new(Members::fetch(antennas).await?);
+    MEMBERS_CACHE.set(members.clone());
+    Ok(members)
+}
+
+/// Returns the membership sets of all antennas.
+///
+/// Memberships are cached for a minute so that checking a note doesn't require
+/// extra queries for each antenna.
+pub(super) async fn get_members(antennas: &[antenna::Model]) -> Result<Arc<Members>, DbErr> {
+    if let Some(cache) = MEMBERS_CACHE.get() {
+        return Ok(cache);
+    }
+    update(antennas).await
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::Members;
+    use crate::model::{
+        entity::{antenna, sea_orm_active_enums::AntennaSrc},
+        fixture,
+    };
+    use std::collections::HashSet;
+
+    fn antenna(src: AntennaSrc) -> antenna::Model {
+        antenna::Model {
+            src,
+            user_list_id: Some("list".to_owned()),
+            user_group_joining_id: Some("joining".to_owned()),
+            ..fixture::antenna("antenna")
+        }
+    }
+
+    fn users(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|&id| id.to_owned()).collect()
+    }
+
+    #[test]
+    fn accept_members() {
+        let mut members = Members::default();
+        members
+            .home
+            .insert("owner".to_owned(), users(&["owner", "followee"]));
+        members
+            .list
+            .insert("list".to_owned(), users(&["list_member"]));
+        members
+            .group
+            .insert("joining".to_owned(), users(&["group_member"]));
+
+        let home = antenna(AntennaSrc::Home);
+        assert!(members.accepts(&home, "owner"));
+        assert!(members.accepts(&home, "followee"));
+        assert!(!members.accepts(&home, "list_member"));
+
+        let mut list = antenna(AntennaSrc::List);
+        assert!(members.accepts(&list, "list_member"));
+        assert!(!members.accepts(&list, "followee"));
+        list.user_list_id = None;
+        assert!(!members.accepts(&list, "list_member"));
+
+        let group = antenna(AntennaSrc::Group);
+        assert!(members.accepts(&group, "group_member"));
+        assert!(!members.accepts(&group, "owner"));
+
+        // other sources are not restricted by membership
+        assert!(members.accepts(&antenna(AntennaSrc::All), "anyone"));
+        assert!(!Members::default().accepts(&home, "owner"));
+    }
+}
@@ -1,22 +1,31 @@
 mod check_hit;
+pub mod expression;
+mod index;
+mod matcher;
+mod members;
+pub mod notify;
 pub mod process_new_note;
+pub mod timeline;
 pub mod update;
 
 use crate::{cache::Cache, database::db_conn, model::entity::antenna};
+use index::AntennaIndex;
 use sea_orm::prelude::*;
 use std::sync::Arc;
 
-static ANTENNAS_CACHE: Cache<Arc<[antenna::Model]>> = Cache::new();
+static ANTENNAS_CACHE: Cache<Arc<AntennaIndex>> = Cache::new();
 
-async fn update() -> Result<Arc<[antenna::Model]>, DbErr> {
+async fn update() -> Result<Arc<AntennaIndex>, DbErr> {
     tracing::debug!("updating cache");
     let antennas: Arc<[antenna::Model]> =
         antenna::Entity::find().all(db_conn().await?).await?.into();
-    ANTENNAS_CACHE.set(antennas.clone());
-    Ok(antennas)
+    let index = Arc::new(AntennaIndex::new(antennas));
+    ANTENNAS_CACHE.set(index.clone());
+    members::update(&index.antennas).await?;
+    Ok(index)
 }
 
-async fn get_antennas() -> Result<Arc<[antenna::Model]>, DbErr> {
+async fn get_index() -> Result<Arc<AntennaIndex>, DbErr> {
     if let Some(cache) = ANTENNAS_CACHE.get() {
         return Ok(cache);
     }
@@ -0,0 +1,207 @@
+//! Notifications of notes added to antennas with `notify` enabled
+//!
+//! Each note is notified individually, unless the antenna is in the digest mode
+//! (`notify_digest`) or too many notes have been notified recently, in which case
+//! the notes are batched into a notification returned by [take_antenna_digests].
+//!
+//! The notifications are created by the TypeScript backend (`createNotification`),
+//! so that they are published and pushed in the same way as the other notifications.
+
+use crate::{
+    database::{db_conn, redis::count_in_window, redis_conn, redis_key, RedisConnError},
+    misc::note::summarize,
+    model::entity::{antenna, note},
+    service::stream::{
+        self,
+        main::{publish_event as publish_to_main_stream, MainEvent},
+    },
+};
+use redis::{AsyncCommands, RedisError};
+use sea_orm::prelude::*;
+
+#[error_doc::errors]
+pub enum Error {
+    #[doc = "Database error"]
+    #[error(transparent)]
+    Db(#[from] DbErr),
+    #[error("failed to execute a Redis command")]
+    Redis(#[from] RedisError),
+    #[error("bad Redis connection")]
+    RedisConn(#[from] RedisConnError),
+    #[error("Redis stream operation has failed")]
+    Stream(#[from] stream::Error),
+}
+
+/// `app` notification to be created for the antenna owner
+#[derive(Debug, PartialEq)]
+#[macros::export(object)]
+pub struct AntennaNotification {
+    /// ID of the antenna owner
+    pub notifiee_id: String,
+    /// name of the antenna
+    pub header: String,
+    /// summary of the note, which is [None] for a digest of multiple notes
+    /// or a deleted note so that the client shows the localized number of notes
+    pub body: Option<String>,
+    /// number of notes added to the antenna
+    pub note_count: u32,
+    /// ID of the (latest) note, which is [None] if it has been deleted
+    pub note_id: Option<String>,
+}
+
+impl AntennaNotification {
+    fn new(antenna: &antenna::Model, note_count: u32, latest_note: Option<&note::Model>) -> Self {
+        Self {
+            notifiee_id: antenna.user_id.to_owned(),
+            header: antenna.name.to_owned(),
+            body: latest_note
+                .filter(|_| note_count == 1)
+                .map(|note| summarize!(note)),
+            note_count,
+            note_id: latest_note.map(|note| note.id.to_owned()),
+        }
+    }
+}
+
+/// Maximum number of notes notified individually for each antenna in [RATE_LIMIT_WINDOW]
+const RATE_LIMIT: u32 = 10;
+
+/// Length of the rate limit window in seconds
+const RATE_LIMIT_WINDOW: u64 = 60 * 60;
+
+fn rate_limit_key(antenna_id: &str) -> String {
+    redis_key(format!("antennaNotifyCount:{}", antenna_id))
+}
+
+fn digest_key(antenna_id: &str) -> String {
+    redis_key(format!("antennaDigest:{}", antenna_id))
+}
+
+/// Set of antenna IDs with pending digests
+fn pending_digests_key() -> String {
+    redis_key("antennaDigests")
+}
+
+/// Notifies the antenna owner of the note added to the antenna, and returns the
+/// notification to create if the note is notified individually.
+pub(super) async fn notify(
+    antenna: &antenna::Model,
+    note: &note::Model,
+) -> Result<Option<AntennaNotification>, Error> {
+    // don't notify the owner of their own notes
+    if note.user_id == antenna.user_id {
+        return Ok(None);
+    }
+
+    publish_to_main_stream(
+        antenna.user_id.to_owned(),
+        &MainEvent::NewAntennaPost(Box::new(antenna.to_owned())),
+    )
+    .await?;
+
+    if antenna.notify_digest || !is_within_rate_limit(&antenna.id).await? {
+        add_to_digest(&antenna.id, &note.id).await?;
+        return Ok(None);
+    }
+
+    Ok(Some(AntennaNotification::new(antenna, 1, Some(note))))
+}
+
+/// Counts a notification of the antenna and returns whether it is within the rate limit.
+async fn is_within_rate_limit(antenna_id: &str) -> Result<bool, Error> {
+    let key = rate_limit_key(antenna_id);
+    let count = count_in_window(&mut *redis_conn().await?, &key, RATE_LIMIT_WINDOW).await?;
+
+    Ok(count <= RATE_LIMIT)
+}
+
+async fn add_to_digest(antenna_id: &str, note_id: &str) -> Result<(), Error> {
+    let mut conn = redis_conn().await?;
+    redis::pipe()
+        .atomic()
+        .rpush(digest_key(antenna_id), note_id)
+        .ignore()
+        .sadd(pending_digests_key(), antenna_id)
+        .ignore()
+        .query_async::<()>(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Returns the body of a digest notification.
+/// Takes the notes batched since the last digest and returns a notification to each
+/// antenna owner about them, which is supposed to be called every hour.
+#[macros::export]
+pub async fn take_antenna_digests() -> Result<Vec<AntennaNotification>, Error> {
+    let db = db_conn().await?;
+    let mut conn = redis_conn().await?;
+
+    let antenna_ids: Vec<String> = conn.smembers(pending_digests_key()).await?;
+    let mut notifications = Vec::new();
+
+    for antenna_id in antenna_ids {
+        let (note_ids,): (Vec<String>,) = redis::pipe()
+            .atomic()
+            .lrange(digest_key(&antenna_id), 0, -1)
+            .del(digest_key(&antenna_id))
+            .ignore()
+            .srem(pending_digests_key(), &antenna_id)
+            .ignore()
+            .query_async(&mut *conn)
+            .await?;
+
+        let Some(latest_note_id) = note_ids.last() else {
+            continue;
+        };
+        // the antenna may have been deleted or stopped notifying
+        let Some(antenna) = antenna::Entity::find_by_id(&antenna_id).one(db).await? else {
+            continue;
+        };
+        if !antenna.notify {
+            continue;
+        }
+
+        // the note may have been deleted
+        let latest_note = note::Entity::find_by_id(latest_note_id).one(db).await?;
+
+        notifications.push(AntennaNotification::new(
+            &antenna,
+            note_ids.len() as u32,
+            latest_note.as_ref(),
+        ));
+    }
+
+    Ok(notifications)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::AntennaNotification;
+    use crate::model::{entity::note, fixture};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn digest() {
+        let antenna = fixture::antenna("cats");
+        let note = note::Model {
+            text: Some("hello".to_owned()),
+            ..fixture::note("note", "author")
+        };
+
+        assert_eq!(
+            AntennaNotification::new(&antenna, 1, Some(&note)),
+            AntennaNotification {
+                notifiee_id: "owner".to_owned(),
+                header: "cats".to_owned(),
+                body: Some("hello".to_owned()),
+                note_count: 1,
+                note_id: Some("note".to_owned()),
+            }
+        );
+        assert_eq!(
+            AntennaNotification::new(&antenna, 3, Some(&note)).body,
+            None
+        );
+        assert_eq!(AntennaNotification::new(&antenna, 1, None).body, None);
+    }
+}
@@ -1,18 +1,26 @@
 use crate::{
     cache,
-    database::{redis_conn, redis_key, RedisConnError},
+    config::CONFIG,
     federation::acct::Acct,
     misc::note::elaborate,
-    model::entity::note,
+    model::entity::{antenna::Model as Antenna, note},
     service::{
         antenna,
-        antenna::check_hit::{check_hit_antenna, AntennaCheckError},
+        antenna::{
+            check_hit::{check_hit_antenna, AntennaCheckError, TextFilter},
+            matcher::NoteText,
+            members::Members,
+            notify::{self, AntennaNotification},
+            timeline,
+        },
         stream,
+        visibility_filter::{self, NoteVerdict, VisibilityFilter},
     },
-    util::id::{get_timestamp, InvalidIdError},
+    util::{error_chain::format_error, metrics},
 };
-use redis::{streams::StreamMaxlen, AsyncCommands, RedisError};
+use futures_util::{stream::iter, StreamExt};
 use sea_orm::prelude::*;
+use std::time::Instant;
 
 #[error_doc::errors]
 pub enum Error {
@@ -21,42 +29,111 @@ pub enum Error {
     Db(#[from] DbErr),
     #[error("Redis cache operation has failed")]
     Cache(#[from] cache::redis::Error),
-    #[error("failed to execute a Redis command")]
-    Redis(#[from] RedisError),
-    #[error("bad Redis connection")]
-    RedisConn(#[from] RedisConnError),
-    #[doc = "Provided string is not a valid Firefish ID"]
-    #[error(transparent)]
-    InvalidId(#[from] InvalidIdError),
     #[error("Redis stream operation has failed")]
     Stream(#[from] stream::Error),
     #[error("failed to check if the note should be added to antenna")]
     AntennaCheck(#[from] AntennaCheckError),
+    #[error("failed to check if the note is muted by the antenna owner")]
+    VisibilityFilter(#[from] visibility_filter::Error),
+    #[error("failed to add the note to antenna timeline")]
+    Timeline(#[from] timeline::Error),
 }
 
+/// Maximum number of antennas checked at the same time
+const CONCURRENCY: usize = 16;
+
 // for napi export
 // https://github.com/napi-rs/napi-rs/issues/2060
 type Note = note::Model;
 
+/// Adds the note to the antennas that accept it, and returns the notifications to create
+/// for the owners of the antennas with `notify` enabled.
 #[macros::export]
 pub async fn update_antennas_on_new_note(
     note: &Note,
     note_author: &Acct,
     note_muted_users: &[String],
-) -> Result<(), Error> {
+) -> Result<Vec<AntennaNotification>, Error> {
+    let start = Instant::now();
     let note_all_texts = elaborate!(note, false).await?;
+    // folded once and shared by all candidate antennas
+    let note_texts = NoteText::all(&note_all_texts);
+
+    let index = antenna::get_index().await?;
+    let members = antenna::members::get_members(&index.antennas).await?;
 
-    // TODO: do this in parallel
-    for antenna in antenna::get_antennas().await?.iter() {
+    // only the antennas that may accept the note are checked
+    let mut checks = Vec::new();
+    for (antenna, filter) in index.candidates(&note_texts, note_author, &CONFIG.host) {
         if note_muted_users.contains(&antenna.user_id) {
             continue;
         }
-        if check_hit_antenna(antenna, note, &note_all_texts, note_author).await? {
-            add_note_to_antenna(&antenna.id, note).await?;
+        checks.push(check_and_add(
+            antenna,
+            note,
+            &note_texts,
+            note_author,
+            &members,
+            filter,
+        ));
+    }
+
+    let mut results = iter(checks).buffer_unordered(CONCURRENCY);
+    let mut hits = 0;
+    let mut notifications = Vec::new();
+    while let Some(result) = results.next().await {
+        let (hit, notification) = result?;
+        if hit {
+            hits += 1;
         }
+        notifications.extend(notification);
+    }
+
+    metrics::observe_antenna_evaluation(hits, start.elapsed());
+
+    Ok(notifications)
+}
+
+/// Adds the note to the antenna if it accepts the note, and returns whether it did
+/// and the notification to create, if any.
+async fn check_and_add(
+    antenna: &Antenna,
+    note: &Note,
+    note_texts: &[NoteText<'_>],
+    note_author: &Acct,
+    members: &Members,
+    filter: &TextFilter,
+) -> Result<(bool, Option<AntennaNotification>), Error> {
+    if !check_hit_antenna(antenna, note, note_texts, note_author, members, filter).await? {
+        return Ok((false, None));
+    }
+
+    // word mutes are already taken into account in `note_muted_users`
+    let owner_filter = VisibilityFilter::load(&antenna.user_id).await?;
+    if owner_filter.check(note, &[]).verdict == NoteVerdict::Hidden {
+        return Ok((false, None));
     }
 
-    Ok(())
+    add_note_to_antenna(&antenna.id, note).await?;
+
+    // the note has already been added, so a failed notification doesn't fail the others
+    if !antenna.notify {
+        return Ok((true, None));
+    }
+
+    let notification = notify::notify(antenna, note)
+        .await
+        .inspect_err(|err| {
+            tracing::warn!(
+                "failed to notify the owner of antenna {}: {}",
+                antenna.id,
+                format_error(err)
+            )
+        })
+        .ok()
+        .flatten();
+
+    Ok((true, notification))
 }
 
 async fn add_note_to_antenna(antenna_id: &str, note: &Note) -> Result<(), Error> {
@@ -64,13 +141,5 @@ async fn add_note_to_antenna(antenna_id: &str, note: &Note) -> Result<(), Error>
     stream::antenna::publish(antenna_id.to_owned(), note).await?;
 
     // for timeline API
-    Ok(redis_conn()
-        .await?
-        .xadd_maxlen(
-            redis_key(format!("antennaTimeline:{}", antenna_id)),
-            StreamMaxlen::Approx(200),
-            format!("{}-*", get_timestamp(&note.id)?),
-            &[("note", &note.id)],
-        )
-        .await?)
+    Ok(timeline::push(antenna_id, &note.id).await?)
 }
@@ -0,0 +1,449 @@
+//! Timelines of notes added to antennas
+//!
+//! Each antenna has a Redis stream (`antennaTimeline:{antenna id}`) whose entry IDs are
+//! the timestamps of the notes, so that a note ID can be used as a cursor of the stream.
+
+use crate::{
+    database::{
+        db_conn, db_read_conn, redis::count_in_window, redis_conn, redis_key, RedisConnError,
+    },
+    federation::acct::Acct,
+    misc::note::elaborate,
+    model::entity::{antenna, note, user},
+    service::{
+        antenna::{
+            check_hit::{check_hit_antenna, AntennaCheckError, TextFilter},
+            matcher::NoteText,
+            members::Members,
+        },
+        visibility_filter::{self, NoteVerdict, VisibilityFilter},
+    },
+    util::id::{created_since, get_timestamp, InvalidIdError},
+};
+use chrono::{Duration, Utc};
+use redis::{
+    streams::{StreamMaxlen, StreamRangeReply},
+    AsyncCommands, RedisError,
+};
+use sea_orm::{prelude::*, sea_query::SimpleExpr, QueryOrder, QuerySelect};
+
+#[error_doc::errors]
+pub enum Error {
+    #[doc = "Database error"]
+    #[error(transparent)]
+    Db(#[from] DbErr),
+    #[error("failed to execute a Redis command")]
+    Redis(#[from] RedisError),
+    #[error("bad Redis connection")]
+    RedisConn(#[from] RedisConnError),
+    #[doc = "Provided string is not a valid Firefish ID"]
+    #[error(transparent)]
+    InvalidId(#[from] InvalidIdError),
+    #[error("failed to check if the note should be added to antenna")]
+    AntennaCheck(#[from] AntennaCheckError),
+    #[error("failed to check if the note is muted by the antenna owner")]
+    VisibilityFilter(#[from] visibility_filter::Error),
+    #[doc = "The antenna does not exist"]
+    #[error("antenna {0} does not exist")]
+    NoSuchAntenna(String),
+}
+
+/// Approximate number of notes kept in each antenna timeline
+pub(super) const MAX_LENGTH: usize = 200;
+
+/// Maximum number of hours that can be backfilled
+const MAX_BACKFILL_HOURS: u32 = 24;
+
+/// Number of notes fetched from the database at once while backfilling
+const BACKFILL_BATCH_SIZE: u64 = 100;
+
+/// Maximum number of notes evaluated in a backfill
+const MAX_BACKFILL_NOTES: u64 = 5000;
+
+/// Maximum number of backfills each user can request in [BACKFILL_RATE_LIMIT_WINDOW]
+const BACKFILL_RATE_LIMIT: u32 = 10;
+
+/// Length of the backfill rate limit window in seconds
+const BACKFILL_RATE_LIMIT_WINDOW: u64 = 60 * 60;
+
+fn timeline_key(antenna_id: &str) -> String {
+    redis_key(format!("antennaTimeline:{}", antenna_id))
+}
+
+fn read_key(antenna_id: &str) -> String {
+    redis_key(format!("antennaRead:{}", antenna_id))
+}
+
+fn backfill_count_key(user_id: &str) -> String {
+    redis_key(format!("antennaBackfillCount:{}", user_id))
+}
+
+/// Returns the timestamp part of a stream entry ID (`{timestamp}-{sequence}`).
+fn entry_timestamp(entry_id: &str) -> Option<i64> {
+    entry_id.split_once('-')?.0.parse().ok()
+}
+
+/// Returns (timestamp, note ID) pairs of the stream entries.
+fn entries(reply: StreamRangeReply) -> Vec<(i64, String)> {
+    reply
+        .ids
+        .into_iter()
+        .filter_map(|entry| Some((entry_timestamp(&entry.id)?, entry.get("note")?)))
+        .collect()
+}
+
+/// Adds the note to the antenna timeline.
+pub(super) async fn push(antenna_id: &str, note_id: &str) -> Result<(), Error> {
+    Ok(redis_conn()
+        .await?
+        .xadd_maxlen(
+            timeline_key(antenna_id),
+            StreamMaxlen::Approx(MAX_LENGTH),
+            format!("{}-*", get_timestamp(note_id)?),
+            &[("note", note_id)],
+        )
+        .await?)
+}
+
+/// Returns the IDs of notes in the antenna timeline.
+///
+/// Notes are sorted in descending order, unless only `since_id` is given, in which
+/// case the oldest notes after `since_id` are returned in ascending order (as is the
+/// case with other timelines). Like database queries, the cursors are exclusive.
+///
+/// # Arguments
+///
+/// * `antenna_id` : ID of the antenna
+/// * `limit` : maximum number of notes to return
+/// * `since_id` : return notes newer than this
+/// * `until_id` : return notes older than this
+#[macros::export]
+pub async fn get_antenna_note_ids(
+    antenna_id: &str,
+    limit: u32,
+    since_id: Option<String>,
+    until_id: Option<String>,
+) -> Result<Vec<String>, Error> {
+    let start = match &since_id {
+        Some(id) => get_timestamp(id)?.to_string(),
+        None => "-".to_owned(),
+    };
+    let end = match &until_id {
+        Some(id) => get_timestamp(id)?.to_string(),
+        None => "+".to_owned(),
+    };
+    // the ranges include the notes created at the same millisecond as the cursors
+    let count = limit as usize + usize::from(since_id.is_some()) + usize::from(until_id.is_some());
+
+    let mut conn = redis_conn().await?;
+    let reply: StreamRangeReply = match (&since_id, &until_id) {
+        (Some(_), None) => {
+            conn.xrange_count(timeline_key(antenna_id), start, end, count)
+                .await?
+        }
+        _ => {
+            conn.xrevrange_count(timeline_key(antenna_id), end, start, count)
+                .await?
+        }
+    };
+
+    Ok(entries(reply)
+        .into_iter()
+        .map(|(_, note_id)| note_id)
+        .filter(|note_id| since_id.as_ref().map_or(true, |id| note_id > id))
+        .filter(|note_id| until_id.as_ref().map_or(true, |id| note_id < id))
+        .take(limit as usize)
+        .collect())
+}
+
+/// Marks the notes in the antenna timeline as read.
+///
+/// # Arguments
+///
+/// * `antenna_id` : ID of the antenna
+/// * `until_id` : mark notes up to this note as read, or all notes if [None]
+#[macros::export]
+pub async fn mark_antenna_read(antenna_id: &str, until_id: Option<String>) -> Result<(), Error> {
+    let mut conn = redis_conn().await?;
+
+    let timestamp = match until_id {
+        Some(id) => get_timestamp(&id)?,
+        None => {
+            let latest: StreamRangeReply = conn
+                .xrevrange_count(timeline_key(antenna_id), "+", "-", 1)
+                .await?;
+            match entries(latest).first() {
+                Some((timestamp, _)) => *timestamp,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    // the read position never goes back
+    let read: Option<i64> = conn.get(read_key(antenna_id)).await?;
+    if read.map_or(true, |read| read < timestamp) {
+        conn.set::<_, _, ()>(read_key(antenna_id), timestamp)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Returns whether the antenna timeline has notes that are not marked as read.
+#[macros::export]
+pub async fn has_unread_antenna_notes(antenna_id: &str) -> Result<bool, Error> {
+    let mut conn = redis_conn().await?;
+
+    let latest: StreamRangeReply = conn
+        .xrevrange_count(timeline_key(antenna_id), "+", "-", 1)
+        .await?;
+    let Some((latest, _)) = entries(latest).into_iter().next() else {
+        return Ok(false);
+    };
+    let read: Option<i64> = conn.get(read_key(antenna_id)).await?;
+
+    Ok(read.map_or(true, |read| read < latest))
+}
+
+/// Returns whether any antenna of the user has unread notes.
+#[macros::export]
+pub async fn has_unread_antenna(user_id: &str) -> Result<bool, Error> {
+    let index = super::get_index().await?;
+
+    for antenna in index.antennas.iter().filter(|a| a.user_id == user_id) {
+        if has_unread_antenna_notes(&antenna.id).await? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Counts a backfill requested by the user and returns whether it is within the rate limit.
+#[macros::export]
+pub async fn reserve_antenna_backfill(user_id: &str) -> Result<bool, Error> {
+    let key = backfill_count_key(user_id);
+    let count =
+        count_in_window(&mut *redis_conn().await?, &key, BACKFILL_RATE_LIMIT_WINDOW).await?;
+
+    Ok(count <= BACKFILL_RATE_LIMIT)
+}
+
+/// Merges the given entries into the stream and trims it to the maximum length.
+///
+/// Entries can only be appended to a stream, so the stream is rebuilt in the script,
+/// which runs atomically and therefore doesn't lose the notes pushed in the meantime.
+///
+/// `KEYS[1]` is the stream key, `ARGV[1]` is the maximum length, and the rest of `ARGV`
+/// are the pairs of timestamp and note ID to merge. Returns the number of new entries.
+const MERGE_SCRIPT: &str = r#"
+local timeline = {}
+local seen = {}
+for _, entry in ipairs(redis.call('XRANGE', KEYS[1], '-', '+')) do
+    local timestamp = tonumber(string.match(entry[1], '^(%d+)-'))
+    local note = entry[2][2]
+    if not seen[note] then
+        seen[note] = true
+        table.insert(timeline, { timestamp, note })
+    end
+end
+local added = 0
+for i = 2, #ARGV, 2 do
+    local note = ARGV[i + 1]
+    if not seen[note] then
+        seen[note] = true
+        added = added + 1
+        table.insert(timeline, { tonumber(ARGV[i]), note })
+    end
+end
+if added == 0 then
+    return 0
+end
+table.sort(timeline, function(a, b)
+    if a[1] == b[1] then
+        return a[2] < b[2]
+    end
+    return a[1] < b[1]
+end)
+redis.call('DEL', KEYS[1])
+for i = math.max(1, #timeline - tonumber(ARGV[1]) + 1), #timeline do
+    redis.call('XADD', KEYS[1], string.format('%d-*', timeline[i][1]), 'note', timeline[i][2])
+end
+return added
+"#;
+
+/// Evaluates recent notes against the antenna, adds the matched notes to its timeline,
+/// and returns the number of notes added.
+///
+/// This is intended for newly created or edited antennas, and is run as a queued job
+/// (see [reserve_antenna_backfill] for the rate limit). Notes are evaluated from the
+/// newest one, up to [MAX_BACKFILL_NOTES] notes. The matched notes are merged into the
+/// timeline, so the existing entries and the notes pushed during the backfill are kept.
+///
+/// # Arguments
+///
+/// * `antenna_id` : ID of the antenna
+/// * `hours` : how many hours of notes to evaluate (up to 24)
+#[macros::export]
+pub async fn backfill_antenna(antenna_id: &str, hours: u32) -> Result<u32, Error> {
+    let antenna = antenna::Entity::find_by_id(antenna_id)
+        .one(db_conn().await?)
+        .await?
+        .ok_or_else(|| Error::NoSuchAntenna(antenna_id.to_owned()))?;
+
+    let since = Utc::now() - Duration::hours(hours.min(MAX_BACKFILL_HOURS).into());
+    let hits = find_hits(&antenna, created_since(note::Column::Id, since)).await?;
+
+    merge(antenna_id, &hits).await
+}
+
+/// Merges (timestamp, note ID) pairs into the antenna timeline with [MERGE_SCRIPT],
+/// and returns the number of notes added.
+async fn merge(antenna_id: &str, entries: &[(i64, String)]) -> Result<u32, Error> {
+    if entries.is_empty() {
+        return Ok(0);
+    }
+
+    let mut command = redis::cmd("EVAL");
+    command
+        .arg(MERGE_SCRIPT)
+        .arg(1)
+        .arg(timeline_key(antenna_id))
+        .arg(MAX_LENGTH);
+    for (timestamp, note_id) in entries {
+        command.arg(timestamp).arg(note_id);
+    }
+
+    Ok(command.query_async(&mut *redis_conn().await?).await?)
+}
+
+/// Returns (timestamp, note ID) pairs of the latest notes (up to [MAX_LENGTH] notes)
+/// that match the antenna in descending order.
+async fn find_hits(
+    antenna: &antenna::Model,
+    condition: SimpleExpr,
+) -> Result<Vec<(i64, String)>, Error> {
+    let db = db_read_conn().await?;
+    let members = Members::fetch(std::slice::from_ref(antenna)).await?;
+    let owner_filter = VisibilityFilter::load(&antenna.user_id).await?;
+    let filter = TextFilter::new(antenna);
+
+    let mut hits = Vec::new();
+    let mut cursor: Option<String> = None;
+    let mut evaluated = 0;
+
+    while evaluated < MAX_BACKFILL_NOTES && hits.len() < MAX_LENGTH {
+        let mut query = note::Entity::find()
+            .filter(condition.clone())
+            .find_also_related(user::Entity)
+            .order_by_desc(note::Column::Id)
+            .limit(BACKFILL_BATCH_SIZE.min(MAX_BACKFILL_NOTES - evaluated));
+        if let Some(cursor) = &cursor {
+            query = query.filter(note::Column::Id.lt(cursor));
+        }
+        let notes = query.all(db).await?;
+
+        let Some((last, _)) = notes.last() else {
+            break;
+        };
+        cursor = Some(last.id.clone());
+        evaluated += notes.len() as u64;
+
+        for (note, author) in notes {
+            let Some(author) = author else {
+                continue;
+            };
+            let author = Acct {
+                username: author.username,
+                host: author.host,
+            };
+            let texts = elaborate!(note, false).await?;
+            let note_texts = NoteText::all(&texts);
+
+            if !check_hit_antenna(antenna, &note, &note_texts, &author, &members, &filter).await? {
+                continue;
+            }
+            if owner_filter.check(&note, &texts).verdict == NoteVerdict::Hidden {
+                continue;
+            }
+            hits.push((get_timestamp(&note.id)?, note.id));
+            if hits.len() == MAX_LENGTH {
+                break;
+            }
+        }
+    }
+
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{entry_timestamp, get_antenna_note_ids, merge, push, timeline_key};
+    use crate::{
+        database::redis_conn,
+        util::id::{gen_id_at, get_timestamp},
+    };
+    use chrono::{Duration, Utc};
+    use pretty_assertions::assert_eq;
+    use redis::AsyncCommands;
+
+    #[test]
+    fn parse_entry_id() {
+        assert_eq!(entry_timestamp("1716735338211-0"), Some(1716735338211));
+        assert_eq!(entry_timestamp("1716735338211-12"), Some(1716735338211));
+        assert_eq!(entry_timestamp("1716735338211"), None);
+        assert_eq!(entry_timestamp("invalid-0"), None);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // can't call foreign function `getaddrinfo` on OS `linux`
+    async fn merge_backfilled_notes() {
+        let antenna_id = "CARGO_TEST_ANTENNA";
+        redis_conn()
+            .await
+            .unwrap()
+            .del::<_, ()>(timeline_key(antenna_id))
+            .await
+            .unwrap();
+
+        let now = Utc::now();
+        let ids: Vec<String> = (0..4)
+            .map(|minutes| gen_id_at(now - Duration::minutes(4 - minutes)))
+            .collect();
+        let entry = |id: &String| (get_timestamp(id).unwrap(), id.to_owned());
+
+        // pushed before and during the backfill
+        push(antenna_id, &ids[1]).await.unwrap();
+        push(antenna_id, &ids[3]).await.unwrap();
+
+        assert_eq!(
+            merge(
+                antenna_id,
+                &[entry(&ids[3]), entry(&ids[2]), entry(&ids[0])]
+            )
+            .await
+            .unwrap(),
+            2
+        );
+        assert_eq!(merge(antenna_id, &[entry(&ids[2])]).await.unwrap(), 0);
+
+        let mut expected = ids.clone();
+        expected.reverse();
+        assert_eq!(
+            get_antenna_note_ids(antenna_id, 10, None, None)
+                .await
+                .unwrap(),
+            expected
+        );
+
+        // new notes can still be pushed
+        let latest = gen_id_at(now);
+        push(antenna_id, &latest).await.unwrap();
+        assert_eq!(
+            get_antenna_note_ids(antenna_id, 1, None, None)
+                .await
+                .unwrap(),
+            vec![latest]
+        );
+    }
+}
@@ -4,3 +4,4 @@ pub mod antenna;
 pub mod note;
 pub mod push_notification;
 pub mod stream;
+pub mod visibility_filter;
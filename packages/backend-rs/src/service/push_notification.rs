@@ -6,11 +6,13 @@ use crate::{
     util::{
         http_client,
         id::{get_timestamp, InvalidIdError},
+        metrics,
     },
 };
 use once_cell::sync::OnceCell;
 use sea_orm::prelude::*;
 use serde::Deserialize;
+use tracing::Instrument;
 use web_push::*;
 
 #[error_doc::errors]
@@ -309,6 +311,7 @@ pub async fn send_push_notification(
     for subscription in subscriptions.iter() {
         if !is_safe_url(&subscription.endpoint) {
             unsubscribe(db, &subscription.id).await?;
+            metrics::count_push_notification("unsubscribed");
             continue;
         }
 
@@ -394,11 +397,15 @@ pub async fn send_push_notification(
             });
         }
 
-        if let Err(err) = get_client()?.send(message).await {
+        let span =
+            tracing::info_span!("push_notification.send", subscription.id = %subscription.id);
+        if let Err(err) = get_client()?.send(message).instrument(span).await {
+            metrics::count_push_notification("failed");
             handle_web_push_failure(db, err, &subscription.id, "failed to send").await?;
             continue;
         }
 
+        metrics::count_push_notification("sent");
         tracing::debug!("success; subscription id: {}", subscription.id);
     }
 
@@ -6,11 +6,13 @@ pub mod custom_emoji;
 pub mod drive;
 pub mod group_chat;
 pub mod internal;
+pub mod log;
 pub mod main;
 pub mod moderation;
 pub mod note;
 pub mod note_edit;
 pub mod notes;
+pub mod subscriber;
 pub mod user;
 
 use crate::{
@@ -18,7 +20,13 @@ use crate::{
     database::{redis_conn, RedisConnError},
 };
 use redis::{AsyncCommands, RedisError};
+use serde::Serialize;
 
+/// Object packed by the TypeScript backend (e.g., `Packed<"Note">`), which is
+/// published as it is
+pub type Packed = serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Stream {
     Internal,
     CustomEmoji,
@@ -57,14 +65,6 @@ pub enum Stream {
     },
 }
 
-#[macros::export(string_enum = "camelCase")]
-pub enum ChatEvent {
-    Message,
-    Read,
-    Deleted,
-    Typing,
-}
-
 #[error_doc::errors]
 pub enum Error {
     #[error("failed to execute a Redis command")]
@@ -73,50 +73,205 @@ pub enum Error {
     RedisConn(#[from] RedisConnError),
     #[error("failed to (de)serialize object")]
     Json(#[from] serde_json::Error),
-    #[error("invalid content")]
-    InvalidContent,
 }
 
+impl Stream {
+    /// Returns the name of the channel that the stream is published to.
+    pub fn channel(&self) -> String {
+        match self {
+            Stream::Internal => "internal".to_owned(),
+            Stream::CustomEmoji => "broadcast".to_owned(),
+            Stream::Moderation { moderator_id } => format!("adminStream:{moderator_id}"),
+            Stream::User { user_id } => format!("user:{user_id}"),
+            Stream::Channel { channel_id } => format!("channelStream:{channel_id}"),
+            Stream::Note { note_id } => format!("noteStream:{note_id}"),
+            Stream::NoteEdit => "noteUpdatesStream".to_owned(),
+            Stream::Notes => "notesStream".to_owned(),
+            Stream::Main { user_id } => format!("mainStream:{user_id}"),
+            Stream::Drive { user_id } => format!("driveStream:{user_id}"),
+            Stream::Antenna { antenna_id } => format!("antennaStream:{antenna_id}"),
+            Stream::Chat {
+                sender_user_id,
+                receiver_user_id,
+            } => format!("messagingStream:{sender_user_id}-{receiver_user_id}"),
+            Stream::GroupChat { group_id } => format!("messagingStream:{group_id}"),
+            Stream::ChatIndex { user_id } => format!("messagingIndexStream:{user_id}"),
+        }
+    }
+
+    /// Returns the stream published to the channel, which is the inverse of [Stream::channel].
+    pub fn from_channel(channel: &str) -> Option<Self> {
+        match channel {
+            "internal" => return Some(Stream::Internal),
+            "broadcast" => return Some(Stream::CustomEmoji),
+            "noteUpdatesStream" => return Some(Stream::NoteEdit),
+            "notesStream" => return Some(Stream::Notes),
+            _ => {}
+        }
+
+        let (name, id) = channel.split_once(':')?;
+        let id = id.to_owned();
+
+        Some(match name {
+            "adminStream" => Stream::Moderation { moderator_id: id },
+            "user" => Stream::User { user_id: id },
+            "channelStream" => Stream::Channel { channel_id: id },
+            "noteStream" => Stream::Note { note_id: id },
+            "mainStream" => Stream::Main { user_id: id },
+            "driveStream" => Stream::Drive { user_id: id },
+            "antennaStream" => Stream::Antenna { antenna_id: id },
+            // IDs don't contain hyphens
+            "messagingStream" => match id.split_once('-') {
+                Some((sender_user_id, receiver_user_id)) => Stream::Chat {
+                    sender_user_id: sender_user_id.to_owned(),
+                    receiver_user_id: receiver_user_id.to_owned(),
+                },
+                None => Stream::GroupChat { group_id: id },
+            },
+            "messagingIndexStream" => Stream::ChatIndex { user_id: id },
+            _ => return None,
+        })
+    }
+}
+
+/// Payload published to the Redis channel, which is dispatched to the
+/// WebSocket connections subscribing to `channel`
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    channel: &'a str,
+    message: &'a T,
+    /// ID of the entry in the event log if published in the durable mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a str>,
+}
+
+/// Returns the serialized payload of `message` published to `stream`.
+fn encode(stream: &Stream, message: &impl Serialize, id: Option<&str>) -> Result<String, Error> {
+    Ok(serde_json::to_string(&Envelope {
+        channel: &stream.channel(),
+        message,
+        id,
+    })?)
+}
+
+/// Publishes `message` to `stream`.
+///
+/// Most streams take an event enum that is serialized as `{"type":...,"body":...}`.
+/// In the durable mode, the message is also appended to the event log of the stream
+/// so that clients can replay it after reconnecting (see [log]).
 pub async fn publish_to_stream(
     stream: &Stream,
-    kind: Option<&str>,
-    value: Option<String>,
+    message: &impl Serialize,
+    durable: bool,
 ) -> Result<(), Error> {
-    let channel = match stream {
-        Stream::Internal => "internal".to_owned(),
-        Stream::CustomEmoji => "broadcast".to_owned(),
-        Stream::Moderation { moderator_id } => format!("adminStream:{moderator_id}"),
-        Stream::User { user_id } => format!("user:{user_id}"),
-        Stream::Channel { channel_id } => format!("channelStream:{channel_id}"),
-        Stream::Note { note_id } => format!("noteStream:{note_id}"),
-        Stream::NoteEdit => "noteUpdatesStream".to_owned(),
-        Stream::Notes => "notesStream".to_owned(),
-        Stream::Main { user_id } => format!("mainStream:{user_id}"),
-        Stream::Drive { user_id } => format!("driveStream:{user_id}"),
-        Stream::Antenna { antenna_id } => format!("antennaStream:{antenna_id}"),
-        Stream::Chat {
-            sender_user_id,
-            receiver_user_id,
-        } => format!("messagingStream:{sender_user_id}-{receiver_user_id}"),
-        Stream::GroupChat { group_id } => format!("messagingStream:{group_id}"),
-        Stream::ChatIndex { user_id } => format!("messagingIndexStream:{user_id}"),
-    };
-
-    let message = if let Some(kind) = kind {
-        format!(
-            "{{\"type\":\"{}\",\"body\":{}}}",
-            kind,
-            value.unwrap_or_else(|| "null".to_owned()),
-        )
-    } else {
-        value.ok_or(Error::InvalidContent)?
+    let id = match durable {
+        true => Some(log::append(stream, &serde_json::to_string(message)?).await?),
+        false => None,
     };
 
     Ok(redis_conn()
         .await?
-        .publish(
-            &CONFIG.host,
-            format!("{{\"channel\":\"{}\",\"message\":{}}}", channel, message),
-        )
+        .publish(&CONFIG.host, encode(stream, message, id.as_deref())?)
         .await?)
 }
+
+#[cfg(test)]
+mod unit_test {
+    use super::{antenna::AntennaEvent, chat::ChatEvent, encode, note_edit::NoteEditEvent, Stream};
+    use crate::model::{entity::note, fixture};
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn parse_channel() {
+        let id = || "9tr87i03euwphnsw".to_owned();
+
+        for stream in [
+            Stream::Internal,
+            Stream::CustomEmoji,
+            Stream::Moderation { moderator_id: id() },
+            Stream::User { user_id: id() },
+            Stream::Channel { channel_id: id() },
+            Stream::Note { note_id: id() },
+            Stream::Notes,
+            Stream::NoteEdit,
+            Stream::Main { user_id: id() },
+            Stream::Drive { user_id: id() },
+            Stream::Antenna { antenna_id: id() },
+            Stream::Chat {
+                sender_user_id: id(),
+                receiver_user_id: "9tr87i03euwphnsx".to_owned(),
+            },
+            Stream::GroupChat { group_id: id() },
+            Stream::ChatIndex { user_id: id() },
+        ] {
+            assert_eq!(Stream::from_channel(&stream.channel()), Some(stream));
+        }
+
+        assert_eq!(Stream::from_channel("unknown"), None);
+        assert_eq!(Stream::from_channel("unknownStream:9tr87i03euwphnsw"), None);
+    }
+
+    #[test]
+    fn encode_envelope() {
+        assert_eq!(
+            encode(
+                &Stream::Chat {
+                    sender_user_id: "sender".to_owned(),
+                    receiver_user_id: "receiver".to_owned(),
+                },
+                &ChatEvent::Read(json!(["message1", "message2"])),
+                None,
+            )
+            .unwrap(),
+            r#"{"channel":"messagingStream:sender-receiver","message":{"type":"read","body":["message1","message2"]}}"#,
+        );
+        assert_eq!(
+            encode(
+                &Stream::GroupChat {
+                    group_id: "group".to_owned()
+                },
+                &ChatEvent::Typing(json!({"id": "user"})),
+                None,
+            )
+            .unwrap(),
+            r#"{"channel":"messagingStream:group","message":{"type":"typing","body":{"id":"user"}}}"#,
+        );
+        assert_eq!(
+            encode(
+                &Stream::ChatIndex {
+                    user_id: "user".to_owned()
+                },
+                &ChatEvent::Read(json!(["message"])),
+                Some("1716735338211-0"),
+            )
+            .unwrap(),
+            r#"{"channel":"messagingIndexStream:user","message":{"type":"read","body":["message"]},"id":"1716735338211-0"}"#,
+        );
+    }
+
+    #[test]
+    fn note_messages() {
+        let note = note::Model {
+            created_at: Utc.timestamp_millis_opt(1716735338211).unwrap().into(),
+            text: Some("I like cats".to_owned()),
+            lang: Some("en".to_owned()),
+            ..fixture::note("9tr87i03euwphnsw", "user")
+        };
+        let packed = r#"{"id":"9tr87i03euwphnsw","createdAt":"2024-05-26T14:55:38.211Z","replyId":null,"renoteId":null,"text":"I like cats","name":null,"cw":null,"userId":"user","localOnly":false,"renoteCount":0,"repliesCount":0,"reactions":{},"visibility":"public","uri":null,"score":0,"fileIds":[],"attachedFileTypes":[],"visibleUserIds":[],"mentions":[],"mentionedRemoteUsers":"[]","emojis":[],"tags":[],"hasPoll":false,"userHost":null,"replyUserId":null,"replyUserHost":null,"renoteUserId":null,"renoteUserHost":null,"url":null,"channelId":null,"threadId":null,"updatedAt":null,"lang":"en","scheduledAt":null}"#;
+
+        assert_eq!(
+            encode(&Stream::Notes, &note, None).unwrap(),
+            format!(r#"{{"channel":"notesStream","message":{}}}"#, packed),
+        );
+        assert_eq!(
+            serde_json::to_string(&AntennaEvent::Note(note.clone())).unwrap(),
+            format!(r#"{{"type":"note","body":{}}}"#, packed),
+        );
+        assert_eq!(
+            serde_json::to_string(&NoteEditEvent::Updated(note)).unwrap(),
+            format!(r#"{{"type":"updated","body":{}}}"#, packed),
+        );
+    }
+}
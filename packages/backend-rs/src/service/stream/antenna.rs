@@ -2,12 +2,21 @@ use crate::{
     model::entity::note,
     service::stream::{publish_to_stream, Error, Stream},
 };
+use serde::{Deserialize, Serialize};
+
+/// Event of the antenna stream, which is received by the antenna owner
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum AntennaEvent {
+    /// Note added to the antenna
+    Note(note::Model),
+}
 
 pub async fn publish(antenna_id: String, note: &note::Model) -> Result<(), Error> {
     publish_to_stream(
         &Stream::Antenna { antenna_id },
-        Some("note"),
-        Some(serde_json::to_string(note)?),
+        &AntennaEvent::Note(note.to_owned()),
+        false,
     )
     .await
 }
@@ -1,11 +1,34 @@
 use crate::service::stream::{publish_to_stream, Error, Stream};
+use serde::{Deserialize, Serialize};
+
+/// Event of the channel stream
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum ChannelEvent {
+    /// ID of the user typing a note in the channel
+    Typing(String),
+}
 
 #[macros::export(js_name = "publishToChannelStream")]
 pub async fn publish(channel_id: String, user_id: String) -> Result<(), Error> {
     publish_to_stream(
         &Stream::Channel { channel_id },
-        Some("typing"),
-        Some(format!("\"{}\"", user_id)),
+        &ChannelEvent::Typing(user_id),
+        false,
     )
     .await
 }
+
+#[cfg(test)]
+mod unit_test {
+    use super::ChannelEvent;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn channel_events() {
+        assert_eq!(
+            serde_json::to_string(&ChannelEvent::Typing("9tr87i03euwphnsw".to_owned())).unwrap(),
+            r#"{"type":"typing","body":"9tr87i03euwphnsw"}"#,
+        );
+    }
+}
@@ -1,29 +1,100 @@
-use crate::service::stream::{publish_to_stream, ChatEvent, Error, Stream};
+use crate::{
+    config::CONFIG,
+    service::stream::{publish_to_stream, Error, Packed, Stream},
+};
+use serde::{Deserialize, Serialize};
 
-// We want to merge `kind` and `object` into a single enum
-// https://github.com/napi-rs/napi-rs/issues/2036
+#[macros::export(string_enum = "camelCase", js_name = "ChatEvent")]
+pub enum ChatEventKind {
+    Message,
+    Read,
+    Deleted,
+    Typing,
+}
 
-#[macros::export(js_name = "publishToChatStream")]
-pub async fn publish(
+/// Event of the chat and group chat streams
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum ChatEvent {
+    Message(Packed),
+    Read(Packed),
+    Deleted(Packed),
+    Typing(Packed),
+}
+
+impl ChatEvent {
+    pub(super) fn new(kind: ChatEventKind, object: Packed) -> Self {
+        match kind {
+            ChatEventKind::Message => Self::Message(object),
+            ChatEventKind::Read => Self::Read(object),
+            ChatEventKind::Deleted => Self::Deleted(object),
+            ChatEventKind::Typing => Self::Typing(object),
+        }
+    }
+}
+
+/// Publishes the event to the chat stream between the two users.
+pub async fn publish_event(
     sender_user_id: String,
     receiver_user_id: String,
-    kind: ChatEvent,
-    object: &serde_json::Value,
+    event: &ChatEvent,
 ) -> Result<(), Error> {
-    let kind = match kind {
-        ChatEvent::Message => "message",
-        ChatEvent::Read => "read",
-        ChatEvent::Deleted => "deleted",
-        ChatEvent::Typing => "typing",
-    };
-
     publish_to_stream(
         &Stream::Chat {
             sender_user_id,
             receiver_user_id,
         },
-        Some(kind),
-        Some(serde_json::to_string(object)?),
+        event,
+        CONFIG.durable_streams,
     )
     .await
 }
+
+// We want to merge `kind` and `object` into a single enum
+// https://github.com/napi-rs/napi-rs/issues/2036
+
+#[macros::export(js_name = "publishToChatStream")]
+pub async fn publish(
+    sender_user_id: String,
+    receiver_user_id: String,
+    kind: ChatEventKind,
+    object: serde_json::Value,
+) -> Result<(), Error> {
+    publish_event(
+        sender_user_id,
+        receiver_user_id,
+        &ChatEvent::new(kind, object),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::ChatEvent;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn chat_events() {
+        for (event, expected) in [
+            (
+                ChatEvent::Message(json!({"id": "message"})),
+                r#"{"type":"message","body":{"id":"message"}}"#,
+            ),
+            (
+                ChatEvent::Read(json!(["message"])),
+                r#"{"type":"read","body":["message"]}"#,
+            ),
+            (
+                ChatEvent::Deleted(json!("message")),
+                r#"{"type":"deleted","body":"message"}"#,
+            ),
+            (
+                ChatEvent::Typing(json!({"id": "user"})),
+                r#"{"type":"typing","body":{"id":"user"}}"#,
+            ),
+        ] {
+            assert_eq!(serde_json::to_string(&event).unwrap(), expected);
+        }
+    }
+}
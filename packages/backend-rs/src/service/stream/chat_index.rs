@@ -1,29 +1,74 @@
-use crate::service::stream::{publish_to_stream, Error, Stream};
+use crate::{
+    config::CONFIG,
+    service::stream::{publish_to_stream, Error, Packed, Stream},
+};
+use serde::{Deserialize, Serialize};
 
-#[macros::export(string_enum = "camelCase")]
-pub enum ChatIndexEvent {
+#[macros::export(string_enum = "camelCase", js_name = "ChatIndexEvent")]
+pub enum ChatIndexEventKind {
     Message,
     Read,
 }
 
+/// Event of the chat index stream, which is received by the user
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum ChatIndexEvent {
+    Message(Packed),
+    Read(Packed),
+}
+
+impl ChatIndexEvent {
+    fn new(kind: ChatIndexEventKind, object: Packed) -> Self {
+        match kind {
+            ChatIndexEventKind::Message => Self::Message(object),
+            ChatIndexEventKind::Read => Self::Read(object),
+        }
+    }
+}
+
+/// Publishes the event to the chat index stream of the user.
+pub async fn publish_event(user_id: String, event: &ChatIndexEvent) -> Result<(), Error> {
+    publish_to_stream(
+        &Stream::ChatIndex { user_id },
+        event,
+        CONFIG.durable_streams,
+    )
+    .await
+}
+
 // We want to merge `kind` and `object` into a single enum
 // https://github.com/napi-rs/napi-rs/issues/2036
 
 #[macros::export(js_name = "publishToChatIndexStream")]
 pub async fn publish(
     user_id: String,
-    kind: ChatIndexEvent,
-    object: &serde_json::Value,
+    kind: ChatIndexEventKind,
+    object: serde_json::Value,
 ) -> Result<(), Error> {
-    let kind = match kind {
-        ChatIndexEvent::Message => "message",
-        ChatIndexEvent::Read => "read",
-    };
+    publish_event(user_id, &ChatIndexEvent::new(kind, object)).await
+}
 
-    publish_to_stream(
-        &Stream::ChatIndex { user_id },
-        Some(kind),
-        Some(serde_json::to_string(object)?),
-    )
-    .await
+#[cfg(test)]
+mod unit_test {
+    use super::{ChatIndexEvent, ChatIndexEventKind};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn chat_index_events() {
+        for (kind, expected) in [
+            (
+                ChatIndexEventKind::Message,
+                r#"{"type":"message","body":{"id":"x"}}"#,
+            ),
+            (
+                ChatIndexEventKind::Read,
+                r#"{"type":"read","body":{"id":"x"}}"#,
+            ),
+        ] {
+            let event = ChatIndexEvent::new(kind, json!({"id": "x"}));
+            assert_eq!(serde_json::to_string(&event).unwrap(), expected);
+        }
+    }
 }
@@ -1,8 +1,8 @@
 use crate::service::stream::{publish_to_stream, Error, Stream};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 // TODO: define schema type in other place
-#[derive(Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object)]
 pub struct PackedEmoji {
@@ -17,12 +17,47 @@ pub struct PackedEmoji {
     pub height: Option<i32>,
 }
 
+/// Event of the broadcast stream, which is received by all users
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum BroadcastEvent {
+    EmojiAdded { emoji: PackedEmoji },
+}
+
 #[macros::export(js_name = "publishToBroadcastStream")]
 pub async fn publish(emoji: &PackedEmoji) -> Result<(), Error> {
     publish_to_stream(
         &Stream::CustomEmoji,
-        Some("emojiAdded"),
-        Some(format!("{{\"emoji\":{}}}", serde_json::to_string(emoji)?)),
+        &BroadcastEvent::EmojiAdded {
+            emoji: emoji.to_owned(),
+        },
+        false,
     )
     .await
 }
+
+#[cfg(test)]
+mod unit_test {
+    use super::{BroadcastEvent, PackedEmoji};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn broadcast_events() {
+        let emoji = PackedEmoji {
+            id: "9tr87i03euwphnsw".to_owned(),
+            aliases: vec!["cat".to_owned()],
+            name: "blobcat".to_owned(),
+            category: None,
+            host: None,
+            url: "https://example.com/files/blobcat.png".to_owned(),
+            license: Some("CC BY 4.0".to_owned()),
+            width: Some(128),
+            height: Some(128),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&BroadcastEvent::EmojiAdded { emoji }).unwrap(),
+            r#"{"type":"emojiAdded","body":{"emoji":{"id":"9tr87i03euwphnsw","aliases":["cat"],"name":"blobcat","category":null,"host":null,"url":"https://example.com/files/blobcat.png","license":"CC BY 4.0","width":128,"height":128}}}"#,
+        );
+    }
+}
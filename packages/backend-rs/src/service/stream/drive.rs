@@ -1,4 +1,5 @@
-use crate::service::stream::{publish_to_stream, Error, Stream};
+use crate::service::stream::{publish_to_stream, Error, Packed, Stream};
+use serde::{Deserialize, Serialize};
 
 #[macros::export(string_enum = "camelCase")]
 pub enum DriveFileEvent {
@@ -14,6 +15,43 @@ pub enum DriveFolderEvent {
     Delete,
 }
 
+/// Event of the drive stream, which is received by the user
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum DriveEvent {
+    FileCreated(Packed),
+    FileUpdated(Packed),
+    /// ID of the deleted file
+    FileDeleted(String),
+    FolderCreated(Packed),
+    FolderUpdated(Packed),
+    /// ID of the deleted folder
+    FolderDeleted(String),
+}
+
+impl DriveEvent {
+    fn file(kind: DriveFileEvent, object: Packed) -> Result<Self, Error> {
+        Ok(match kind {
+            DriveFileEvent::Create => Self::FileCreated(object),
+            DriveFileEvent::Update => Self::FileUpdated(object),
+            DriveFileEvent::Delete => Self::FileDeleted(serde_json::from_value(object)?),
+        })
+    }
+
+    fn folder(kind: DriveFolderEvent, object: Packed) -> Result<Self, Error> {
+        Ok(match kind {
+            DriveFolderEvent::Create => Self::FolderCreated(object),
+            DriveFolderEvent::Update => Self::FolderUpdated(object),
+            DriveFolderEvent::Delete => Self::FolderDeleted(serde_json::from_value(object)?),
+        })
+    }
+}
+
+/// Publishes the event to the drive stream of the user.
+pub async fn publish_event(user_id: String, event: &DriveEvent) -> Result<(), Error> {
+    publish_to_stream(&Stream::Drive { user_id }, event, false).await
+}
+
 // We want to merge `kind` and `object` into a single enum and merge the 2 functions
 // https://github.com/napi-rs/napi-rs/issues/2036
 
@@ -21,38 +59,61 @@ pub enum DriveFolderEvent {
 pub async fn publish_file(
     user_id: String,
     kind: DriveFileEvent,
-    object: &serde_json::Value, // file (create, update) or file id (delete)
+    object: serde_json::Value, // file (create, update) or file id (delete)
 ) -> Result<(), Error> {
-    let kind = match kind {
-        DriveFileEvent::Create => "fileCreated",
-        DriveFileEvent::Update => "fileUpdated",
-        DriveFileEvent::Delete => "fileDeleted",
-    };
-
-    publish_to_stream(
-        &Stream::Drive { user_id },
-        Some(kind),
-        Some(serde_json::to_string(object)?),
-    )
-    .await
+    publish_event(user_id, &DriveEvent::file(kind, object)?).await
 }
 
 #[macros::export(js_name = "publishToDriveFolderStream")]
 pub async fn publish_folder(
     user_id: String,
     kind: DriveFolderEvent,
-    object: &serde_json::Value, // folder (create, update) or folder id (delete)
+    object: serde_json::Value, // folder (create, update) or folder id (delete)
 ) -> Result<(), Error> {
-    let kind = match kind {
-        DriveFolderEvent::Create => "folderCreated",
-        DriveFolderEvent::Update => "folderUpdated",
-        DriveFolderEvent::Delete => "folderDeleted",
-    };
-
-    publish_to_stream(
-        &Stream::Drive { user_id },
-        Some(kind),
-        Some(serde_json::to_string(object)?),
-    )
-    .await
+    publish_event(user_id, &DriveEvent::folder(kind, object)?).await
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{DriveEvent, DriveFileEvent, DriveFolderEvent};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn drive_events() {
+        let file = json!({"id": "file", "name": "cat.png"});
+        let folder = json!({"id": "folder", "name": "cats"});
+
+        for (event, expected) in [
+            (
+                DriveEvent::file(DriveFileEvent::Create, file.clone()),
+                r#"{"type":"fileCreated","body":{"id":"file","name":"cat.png"}}"#,
+            ),
+            (
+                DriveEvent::file(DriveFileEvent::Update, file),
+                r#"{"type":"fileUpdated","body":{"id":"file","name":"cat.png"}}"#,
+            ),
+            (
+                DriveEvent::file(DriveFileEvent::Delete, json!("file")),
+                r#"{"type":"fileDeleted","body":"file"}"#,
+            ),
+            (
+                DriveEvent::folder(DriveFolderEvent::Create, folder.clone()),
+                r#"{"type":"folderCreated","body":{"id":"folder","name":"cats"}}"#,
+            ),
+            (
+                DriveEvent::folder(DriveFolderEvent::Update, folder),
+                r#"{"type":"folderUpdated","body":{"id":"folder","name":"cats"}}"#,
+            ),
+            (
+                DriveEvent::folder(DriveFolderEvent::Delete, json!("folder")),
+                r#"{"type":"folderDeleted","body":"folder"}"#,
+            ),
+        ] {
+            assert_eq!(serde_json::to_string(&event.unwrap()).unwrap(), expected);
+        }
+
+        // the ID of the deleted file must be a string
+        assert!(DriveEvent::file(DriveFileEvent::Delete, json!({"id": "file"})).is_err());
+    }
 }
@@ -1,4 +1,20 @@
-use crate::service::stream::{publish_to_stream, ChatEvent, Error, Stream};
+use crate::{
+    config::CONFIG,
+    service::stream::{
+        chat::{ChatEvent, ChatEventKind},
+        publish_to_stream, Error, Stream,
+    },
+};
+
+/// Publishes the event to the chat stream of the group.
+pub async fn publish_event(group_id: String, event: &ChatEvent) -> Result<(), Error> {
+    publish_to_stream(
+        &Stream::GroupChat { group_id },
+        event,
+        CONFIG.durable_streams,
+    )
+    .await
+}
 
 // We want to merge `kind` and `object` into a single enum
 // https://github.com/napi-rs/napi-rs/issues/2036
@@ -6,20 +22,8 @@ use crate::service::stream::{publish_to_stream, ChatEvent, Error, Stream};
 #[macros::export(js_name = "publishToGroupChatStream")]
 pub async fn publish(
     group_id: String,
-    kind: ChatEvent,
-    object: &serde_json::Value,
+    kind: ChatEventKind,
+    object: serde_json::Value,
 ) -> Result<(), Error> {
-    let kind = match kind {
-        ChatEvent::Message => "message",
-        ChatEvent::Read => "read",
-        ChatEvent::Deleted => "deleted",
-        ChatEvent::Typing => "typing",
-    };
-
-    publish_to_stream(
-        &Stream::GroupChat { group_id },
-        Some(kind),
-        Some(serde_json::to_string(object)?),
-    )
-    .await
+    publish_event(group_id, &ChatEvent::new(kind, object)).await
 }
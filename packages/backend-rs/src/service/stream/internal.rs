@@ -1,7 +1,8 @@
-use crate::service::stream::{publish_to_stream, Error, Stream};
+use crate::service::stream::{publish_to_stream, Error, Packed, Stream};
+use serde::{Deserialize, Serialize};
 
-#[macros::export]
-pub enum InternalEvent {
+#[macros::export(js_name = "InternalEvent")]
+pub enum InternalEventKind {
     Suspend,
     Silence,
     Moderator,
@@ -16,30 +17,128 @@ pub enum InternalEvent {
     AntennaDeleted,
 }
 
+/// Event of the internal stream, which is received by the backend processes
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body")]
+pub enum InternalEvent {
+    #[serde(rename = "userChangeSuspendedState")]
+    Suspend(Packed),
+    #[serde(rename = "userChangeSilencedState")]
+    Silence(Packed),
+    #[serde(rename = "userChangeModeratorState")]
+    Moderator(Packed),
+    #[serde(rename = "userTokenRegenerated")]
+    Token(Packed),
+    #[serde(rename = "localUserUpdated")]
+    LocalUser(Packed),
+    #[serde(rename = "remoteUserUpdated")]
+    RemoteUser(Packed),
+    #[serde(rename = "webhookCreated")]
+    WebhookCreated(Packed),
+    #[serde(rename = "webhookUpdated")]
+    WebhookUpdated(Packed),
+    #[serde(rename = "webhookDeleted")]
+    WebhookDeleted(Packed),
+    #[serde(rename = "antennaCreated")]
+    AntennaCreated(Packed),
+    #[serde(rename = "antennaUpdated")]
+    AntennaUpdated(Packed),
+    #[serde(rename = "antennaDeleted")]
+    AntennaDeleted(Packed),
+}
+
+impl InternalEvent {
+    fn new(kind: InternalEventKind, object: Packed) -> Self {
+        match kind {
+            InternalEventKind::Suspend => Self::Suspend(object),
+            InternalEventKind::Silence => Self::Silence(object),
+            InternalEventKind::Moderator => Self::Moderator(object),
+            InternalEventKind::Token => Self::Token(object),
+            InternalEventKind::LocalUser => Self::LocalUser(object),
+            InternalEventKind::RemoteUser => Self::RemoteUser(object),
+            InternalEventKind::WebhookCreated => Self::WebhookCreated(object),
+            InternalEventKind::WebhookUpdated => Self::WebhookUpdated(object),
+            InternalEventKind::WebhookDeleted => Self::WebhookDeleted(object),
+            InternalEventKind::AntennaCreated => Self::AntennaCreated(object),
+            InternalEventKind::AntennaUpdated => Self::AntennaUpdated(object),
+            InternalEventKind::AntennaDeleted => Self::AntennaDeleted(object),
+        }
+    }
+}
+
+/// Publishes the event to the internal stream.
+pub async fn publish_event(event: &InternalEvent) -> Result<(), Error> {
+    publish_to_stream(&Stream::Internal, event, false).await
+}
+
 // We want to merge `kind` and `object` into a single enum
 // https://github.com/napi-rs/napi-rs/issues/2036
 
 #[macros::export(js_name = "publishToInternalStream")]
-pub async fn publish(kind: InternalEvent, object: &serde_json::Value) -> Result<(), Error> {
-    let kind = match kind {
-        InternalEvent::Suspend => "userChangeSuspendedState",
-        InternalEvent::Silence => "userChangeSilencedState",
-        InternalEvent::Moderator => "userChangeModeratorState",
-        InternalEvent::Token => "userTokenRegenerated",
-        InternalEvent::LocalUser => "localUserUpdated",
-        InternalEvent::RemoteUser => "remoteUserUpdated",
-        InternalEvent::WebhookCreated => "webhookCreated",
-        InternalEvent::WebhookUpdated => "webhookUpdated",
-        InternalEvent::WebhookDeleted => "webhookDeleted",
-        InternalEvent::AntennaCreated => "antennaCreated",
-        InternalEvent::AntennaUpdated => "antennaUpdated",
-        InternalEvent::AntennaDeleted => "antennaDeleted",
-    };
-
-    publish_to_stream(
-        &Stream::Internal,
-        Some(kind),
-        Some(serde_json::to_string(&object)?),
-    )
-    .await
+pub async fn publish(kind: InternalEventKind, object: serde_json::Value) -> Result<(), Error> {
+    publish_event(&InternalEvent::new(kind, object)).await
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{InternalEvent, InternalEventKind};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn internal_events() {
+        for (kind, expected) in [
+            (
+                InternalEventKind::Suspend,
+                r#"{"type":"userChangeSuspendedState","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::Silence,
+                r#"{"type":"userChangeSilencedState","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::Moderator,
+                r#"{"type":"userChangeModeratorState","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::Token,
+                r#"{"type":"userTokenRegenerated","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::LocalUser,
+                r#"{"type":"localUserUpdated","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::RemoteUser,
+                r#"{"type":"remoteUserUpdated","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::WebhookCreated,
+                r#"{"type":"webhookCreated","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::WebhookUpdated,
+                r#"{"type":"webhookUpdated","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::WebhookDeleted,
+                r#"{"type":"webhookDeleted","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::AntennaCreated,
+                r#"{"type":"antennaCreated","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::AntennaUpdated,
+                r#"{"type":"antennaUpdated","body":{"id":"x"}}"#,
+            ),
+            (
+                InternalEventKind::AntennaDeleted,
+                r#"{"type":"antennaDeleted","body":{"id":"x"}}"#,
+            ),
+        ] {
+            let event = InternalEvent::new(kind, json!({"id": "x"}));
+            assert_eq!(serde_json::to_string(&event).unwrap(), expected);
+        }
+    }
 }
@@ -0,0 +1,232 @@
+//! Event logs of streams
+//!
+//! Redis Pub/Sub doesn't keep messages, so clients miss the events published while they
+//! are disconnected. Messages published in the durable mode are also appended to the
+//! capped Redis stream of the channel (`streamLog:{channel}`), and the ID of the entry is
+//! published together with the message, so that reconnecting clients can catch up with
+//! [replay_stream]. The main and chat streams are published in the durable mode only if
+//! `durableStreams` is enabled in the config, as it costs an extra Redis write per event
+//! and keeps private messages in Redis.
+
+use crate::{
+    database::{db_conn, redis_conn, redis_key, RedisConnError},
+    model::entity::user_group_joining,
+    service::stream::{self, Stream},
+};
+use chrono::Utc;
+use redis::{
+    streams::{StreamMaxlen, StreamRangeReply},
+    AsyncCommands, RedisError,
+};
+use sea_orm::prelude::*;
+
+#[error_doc::errors]
+pub enum Error {
+    #[doc = "Database error"]
+    #[error(transparent)]
+    Db(#[from] DbErr),
+    #[error("failed to execute a Redis command")]
+    Redis(#[from] RedisError),
+    #[error("bad Redis connection")]
+    RedisConn(#[from] RedisConnError),
+    #[error("failed to deserialize the message")]
+    Json(#[from] serde_json::Error),
+    #[doc = "The channel is not a stream channel"]
+    #[error("unknown stream channel: {0}")]
+    UnknownChannel(String),
+    #[doc = "The viewer is not allowed to replay the channel"]
+    #[error("the viewer is not allowed to replay the channel: {0}")]
+    Forbidden(String),
+    #[doc = "The string is not a valid stream entry ID"]
+    #[error("invalid stream entry ID: {0}")]
+    InvalidEntryId(String),
+}
+
+/// Approximate number of messages kept in each event log
+const MAX_LENGTH: usize = 500;
+
+/// Number of seconds for which an event log is kept after the last message
+const TTL: i64 = 24 * 60 * 60;
+
+/// Maximum number of messages returned by [replay_stream] at once
+const MAX_REPLAY_COUNT: u32 = 100;
+
+fn log_key(stream: &Stream) -> String {
+    redis_key(format!("streamLog:{}", stream.channel()))
+}
+
+/// Parses a stream entry ID (`{timestamp}-{sequence}`).
+fn parse_entry_id(id: &str) -> Option<(u64, u64)> {
+    let (timestamp, sequence) = id.split_once('-')?;
+    Some((timestamp.parse().ok()?, sequence.parse().ok()?))
+}
+
+/// Appends the serialized message to the event log of the stream and returns the entry ID.
+pub(super) async fn append(stream: &Stream, message: &str) -> Result<String, stream::Error> {
+    let key = log_key(stream);
+
+    let (id,): (String,) = redis::pipe()
+        .atomic()
+        .xadd_maxlen(
+            &key,
+            StreamMaxlen::Approx(MAX_LENGTH),
+            "*",
+            &[("message", message)],
+        )
+        .expire(&key, TTL)
+        .ignore()
+        .query_async(&mut *redis_conn().await?)
+        .await?;
+
+    Ok(id)
+}
+
+/// Message in an event log
+#[macros::export(object)]
+pub struct StreamEvent {
+    /// ID of the stream entry
+    pub id: String,
+    /// message published to the channel
+    pub message: serde_json::Value,
+}
+
+#[macros::export(object)]
+pub struct StreamReplay {
+    /// messages published after the given entry in ascending order
+    pub events: Vec<StreamEvent>,
+    /// whether some messages after the given entry have already been removed from
+    /// the event log, in which case the client should reload everything
+    pub missed: bool,
+}
+
+/// Returns whether the user subscribes to the stream as its owner, which doesn't
+/// apply to the streams that are not published in the durable mode.
+///
+/// Group chats need to be checked against the database (see [can_replay]).
+fn is_owner(stream: &Stream, user_id: &str) -> bool {
+    match stream {
+        Stream::Main { user_id: owner_id } | Stream::ChatIndex { user_id: owner_id } => {
+            owner_id == user_id
+        }
+        // the channel is subscribed by the sender of the messages
+        Stream::Chat { sender_user_id, .. } => sender_user_id == user_id,
+        _ => false,
+    }
+}
+
+/// Returns whether the user is allowed to replay the stream.
+async fn can_replay(stream: &Stream, user_id: &str) -> Result<bool, Error> {
+    let Stream::GroupChat { group_id } = stream else {
+        return Ok(is_owner(stream, user_id));
+    };
+
+    Ok(user_group_joining::Entity::find()
+        .filter(user_group_joining::Column::UserId.eq(user_id))
+        .filter(user_group_joining::Column::UserGroupId.eq(group_id))
+        .one(db_conn().await?)
+        .await?
+        .is_some())
+}
+
+/// Returns the messages published to the channel in the durable mode after the given
+/// stream entry.
+///
+/// Only the main, chat, and chat index streams of the viewer and the chat streams of
+/// the groups that the viewer has joined can be replayed.
+///
+/// A client should subscribe to the channel before calling this, and ignore the messages
+/// received with IDs up to the ID of the last event returned.
+///
+/// # Arguments
+///
+/// * `viewer_id` : ID of the user who replays the channel
+/// * `channel` : name of the channel (e.g., `mainStream:9tr87i03euwphnsw`)
+/// * `since_id` : ID of the last stream entry received by the client
+/// * `limit` : maximum number of messages to return (up to 100)
+#[macros::export]
+pub async fn replay_stream(
+    viewer_id: &str,
+    channel: &str,
+    since_id: &str,
+    limit: u32,
+) -> Result<StreamReplay, Error> {
+    let stream =
+        Stream::from_channel(channel).ok_or_else(|| Error::UnknownChannel(channel.to_owned()))?;
+    if !can_replay(&stream, viewer_id).await? {
+        return Err(Error::Forbidden(channel.to_owned()));
+    }
+    let since =
+        parse_entry_id(since_id).ok_or_else(|| Error::InvalidEntryId(since_id.to_owned()))?;
+    let limit = limit.min(MAX_REPLAY_COUNT) as usize;
+
+    let key = log_key(&stream);
+    let mut conn = redis_conn().await?;
+
+    let oldest: StreamRangeReply = conn.xrange_count(&key, "-", "+", 1).await?;
+    // the range includes `since_id` itself
+    let reply: StreamRangeReply = conn.xrange_count(&key, since_id, "+", limit + 1).await?;
+
+    let missed = match oldest
+        .ids
+        .first()
+        .and_then(|entry| parse_entry_id(&entry.id))
+    {
+        Some(oldest) => oldest > since,
+        // the event log has expired
+        None => since.0 < (Utc::now().timestamp_millis() - TTL * 1000) as u64,
+    };
+
+    let mut events = Vec::new();
+    for entry in reply.ids {
+        if entry.id == since_id {
+            continue;
+        }
+        let Some(message) = entry.get::<String>("message") else {
+            continue;
+        };
+        events.push(StreamEvent {
+            id: entry.id,
+            message: serde_json::from_str(&message)?,
+        });
+    }
+    events.truncate(limit);
+
+    Ok(StreamReplay { events, missed })
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{is_owner, parse_entry_id};
+    use crate::service::stream::Stream;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn entry_id() {
+        assert_eq!(parse_entry_id("1716735338211-0"), Some((1716735338211, 0)));
+        assert_eq!(
+            parse_entry_id("1716735338211-12"),
+            Some((1716735338211, 12))
+        );
+        assert!(parse_entry_id("1716735338211-2") < parse_entry_id("1716735338211-12"));
+        assert_eq!(parse_entry_id("1716735338211"), None);
+        assert_eq!(parse_entry_id("invalid-0"), None);
+    }
+
+    #[test]
+    fn stream_owner() {
+        let stream = |channel: &str| Stream::from_channel(channel).unwrap();
+
+        assert!(is_owner(&stream("mainStream:alice"), "alice"));
+        assert!(!is_owner(&stream("mainStream:alice"), "bob"));
+        assert!(is_owner(&stream("messagingIndexStream:alice"), "alice"));
+        assert!(!is_owner(&stream("messagingIndexStream:alice"), "bob"));
+        assert!(is_owner(&stream("messagingStream:alice-bob"), "alice"));
+        assert!(!is_owner(&stream("messagingStream:alice-bob"), "bob"));
+        assert!(!is_owner(&stream("messagingStream:alice-bob"), "carol"));
+        // checked against the database
+        assert!(!is_owner(&stream("messagingStream:group"), "alice"));
+        // not published in the durable mode
+        assert!(!is_owner(&stream("driveStream:alice"), "alice"));
+        assert!(!is_owner(&stream("notesStream"), "alice"));
+    }
+}
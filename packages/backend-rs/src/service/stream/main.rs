@@ -1,4 +1,16 @@
-use crate::service::stream::{publish_to_stream, Error, Stream};
+//! Main stream of each user (`mainStream:{user id}`)
+//!
+//! Most events are published by the TypeScript backend with objects packed there
+//! (e.g., `Packed<"Notification">`). Packing depends on the viewer and the repositories
+//! of the TypeScript backend, so these bodies are [Packed] and relayed as they are.
+//! Events published by this crate, such as [MainEvent::NewAntennaPost], have typed bodies.
+
+use crate::{
+    config::CONFIG,
+    model::entity::antenna,
+    service::stream::{publish_to_stream, Error, Packed, Stream},
+};
+use serde::{Deserialize, Serialize};
 
 #[macros::export]
 pub enum Event {
@@ -35,53 +47,267 @@ pub enum Event {
     Registry,
 }
 
-// We want to merge `kind` and `object` into a single enum
-// https://github.com/napi-rs/napi-rs/issues/2036
+/// Event of the main stream, which is received by the user
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body")]
+pub enum MainEvent {
+    #[serde(rename = "notification")]
+    Notification(Packed),
+    #[serde(rename = "mention")]
+    Mention(Packed),
+    #[serde(rename = "reply")]
+    Reply(Packed),
+    #[serde(rename = "renote")]
+    Renote(Packed),
+    #[serde(rename = "follow")]
+    Follow(Packed),
+    #[serde(rename = "followed")]
+    Followed(Packed),
+    #[serde(rename = "unfollow")]
+    Unfollow(Packed),
+    #[serde(rename = "meUpdated")]
+    Me(Packed),
+    #[serde(rename = "pageEvent")]
+    Page(Packed),
+    #[serde(rename = "urlUploadFinished")]
+    UrlUploadFinished(Packed),
+    #[serde(rename = "readAllNotifications")]
+    ReadAllNotifications(Packed),
+    #[serde(rename = "readNotifications")]
+    ReadNotifications(Packed),
+    #[serde(rename = "unreadNotification")]
+    NewNotification(Packed),
+    #[serde(rename = "unreadMention")]
+    NewMention(Packed),
+    #[serde(rename = "readAllUnreadMentions")]
+    ReadAllMentions(Packed),
+    #[serde(rename = "readAllUnreadSpecifiedNotes")]
+    ReadAllDms(Packed),
+    #[serde(rename = "unreadSpecifiedNote")]
+    NewDm(Packed),
+    #[serde(rename = "readAllMessagingMessages")]
+    ReadAllChats(Packed),
+    #[serde(rename = "messagingMessage")]
+    Chat(Packed),
+    #[serde(rename = "unreadMessagingMessage")]
+    NewChat(Packed),
+    #[serde(rename = "readAllAntennas")]
+    ReadAllAntennaPosts(Packed),
+    #[serde(rename = "unreadAntenna")]
+    NewAntennaPost(Box<antenna::Model>),
+    #[serde(rename = "readAllAnnouncements")]
+    ReadAllAnnouncements(Packed),
+    #[serde(rename = "readAllChannels")]
+    ReadAllChannelPosts(Packed),
+    #[serde(rename = "unreadChannel")]
+    NewChannelPost(Packed),
+    #[serde(rename = "myTokenRegenerated")]
+    RegenerateMyToken(Packed),
+    #[serde(rename = "signin")]
+    Signin(Packed),
+    #[serde(rename = "registryUpdated")]
+    Registry(Packed),
+    #[serde(rename = "driveFileCreated")]
+    DriveFile(Packed),
+    #[serde(rename = "readAntenna")]
+    ReadAntenna(Packed),
+    #[serde(rename = "receiveFollowRequest")]
+    NewFollowRequest(Packed),
+}
+
+impl MainEvent {
+    fn new(kind: Event, object: Packed) -> Result<Self, Error> {
+        Ok(match kind {
+            Event::Notification => Self::Notification(object),
+            Event::Mention => Self::Mention(object),
+            Event::Reply => Self::Reply(object),
+            Event::Renote => Self::Renote(object),
+            Event::Follow => Self::Follow(object),
+            Event::Followed => Self::Followed(object),
+            Event::Unfollow => Self::Unfollow(object),
+            Event::Me => Self::Me(object),
+            Event::Page => Self::Page(object),
+            Event::UrlUploadFinished => Self::UrlUploadFinished(object),
+            Event::ReadAllNotifications => Self::ReadAllNotifications(object),
+            Event::ReadNotifications => Self::ReadNotifications(object),
+            Event::NewNotification => Self::NewNotification(object),
+            Event::NewMention => Self::NewMention(object),
+            Event::ReadAllMentions => Self::ReadAllMentions(object),
+            Event::ReadAllDms => Self::ReadAllDms(object),
+            Event::NewDm => Self::NewDm(object),
+            Event::ReadAllChats => Self::ReadAllChats(object),
+            Event::Chat => Self::Chat(object),
+            Event::NewChat => Self::NewChat(object),
+            Event::ReadAllAntennaPosts => Self::ReadAllAntennaPosts(object),
+            Event::NewAntennaPost => Self::NewAntennaPost(serde_json::from_value(object)?),
+            Event::ReadAllAnnouncements => Self::ReadAllAnnouncements(object),
+            Event::ReadAllChannelPosts => Self::ReadAllChannelPosts(object),
+            Event::NewChannelPost => Self::NewChannelPost(object),
+            Event::RegenerateMyToken => Self::RegenerateMyToken(object),
+            Event::Signin => Self::Signin(object),
+            Event::Registry => Self::Registry(object),
+            Event::DriveFile => Self::DriveFile(object),
+            Event::ReadAntenna => Self::ReadAntenna(object),
+            Event::NewFollowRequest => Self::NewFollowRequest(object),
+        })
+    }
+}
+
+/// Publishes the event to the main stream of the user.
+pub async fn publish_event(user_id: String, event: &MainEvent) -> Result<(), Error> {
+    publish_to_stream(&Stream::Main { user_id }, event, CONFIG.durable_streams).await
+}
 
+/// Publishes the event to the main stream of the user, which is called by the TypeScript backend.
+///
+/// `kind` and `object` are separate arguments because napi-rs can't take [MainEvent] from
+/// JavaScript (<https://github.com/napi-rs/napi-rs/issues/2036>); the conversion checks
+/// that the object matches the kind for the typed bodies.
 #[macros::export(js_name = "publishToMainStream")]
-pub async fn publish(
-    user_id: String,
-    kind: Event,
-    object: &serde_json::Value,
-) -> Result<(), Error> {
-    let kind = match kind {
-        Event::Notification => "notification",
-        Event::Mention => "mention",
-        Event::Reply => "reply",
-        Event::Renote => "renote",
-        Event::Follow => "follow",
-        Event::Followed => "followed",
-        Event::Unfollow => "unfollow",
-        Event::Me => "meUpdated",
-        Event::Page => "pageEvent",
-        Event::UrlUploadFinished => "urlUploadFinished",
-        Event::ReadAllNotifications => "readAllNotifications",
-        Event::ReadNotifications => "readNotifications",
-        Event::NewNotification => "unreadNotification",
-        Event::NewMention => "unreadMention",
-        Event::ReadAllMentions => "readAllUnreadMentions",
-        Event::ReadAllDms => "readAllUnreadSpecifiedNotes",
-        Event::NewDm => "unreadSpecifiedNote",
-        Event::ReadAllChats => "readAllMessagingMessages",
-        Event::Chat => "messagingMessage",
-        Event::NewChat => "unreadMessagingMessage",
-        Event::ReadAllAntennaPosts => "readAllAntennas",
-        Event::NewAntennaPost => "unreadAntenna",
-        Event::ReadAllAnnouncements => "readAllAnnouncements",
-        Event::ReadAllChannelPosts => "readAllChannels",
-        Event::NewChannelPost => "unreadChannel",
-        Event::RegenerateMyToken => "myTokenRegenerated",
-        Event::Signin => "signin",
-        Event::Registry => "registryUpdated",
-        Event::DriveFile => "driveFileCreated",
-        Event::ReadAntenna => "readAntenna",
-        Event::NewFollowRequest => "receiveFollowRequest",
-    };
+pub async fn publish(user_id: String, kind: Event, object: serde_json::Value) -> Result<(), Error> {
+    publish_event(user_id, &MainEvent::new(kind, object)?).await
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{Event, MainEvent};
+    use crate::model::{entity::antenna, fixture};
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn main_events() {
+        for (kind, expected) in [
+            (
+                Event::Notification,
+                r#"{"type":"notification","body":{"id":"x"}}"#,
+            ),
+            (Event::Mention, r#"{"type":"mention","body":{"id":"x"}}"#),
+            (Event::Reply, r#"{"type":"reply","body":{"id":"x"}}"#),
+            (Event::Renote, r#"{"type":"renote","body":{"id":"x"}}"#),
+            (Event::Follow, r#"{"type":"follow","body":{"id":"x"}}"#),
+            (Event::Followed, r#"{"type":"followed","body":{"id":"x"}}"#),
+            (Event::Unfollow, r#"{"type":"unfollow","body":{"id":"x"}}"#),
+            (Event::Me, r#"{"type":"meUpdated","body":{"id":"x"}}"#),
+            (Event::Page, r#"{"type":"pageEvent","body":{"id":"x"}}"#),
+            (
+                Event::UrlUploadFinished,
+                r#"{"type":"urlUploadFinished","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadAllNotifications,
+                r#"{"type":"readAllNotifications","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadNotifications,
+                r#"{"type":"readNotifications","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::NewNotification,
+                r#"{"type":"unreadNotification","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::NewMention,
+                r#"{"type":"unreadMention","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadAllMentions,
+                r#"{"type":"readAllUnreadMentions","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadAllDms,
+                r#"{"type":"readAllUnreadSpecifiedNotes","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::NewDm,
+                r#"{"type":"unreadSpecifiedNote","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadAllChats,
+                r#"{"type":"readAllMessagingMessages","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::Chat,
+                r#"{"type":"messagingMessage","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::NewChat,
+                r#"{"type":"unreadMessagingMessage","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadAllAntennaPosts,
+                r#"{"type":"readAllAntennas","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadAllAnnouncements,
+                r#"{"type":"readAllAnnouncements","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadAllChannelPosts,
+                r#"{"type":"readAllChannels","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::NewChannelPost,
+                r#"{"type":"unreadChannel","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::RegenerateMyToken,
+                r#"{"type":"myTokenRegenerated","body":{"id":"x"}}"#,
+            ),
+            (Event::Signin, r#"{"type":"signin","body":{"id":"x"}}"#),
+            (
+                Event::Registry,
+                r#"{"type":"registryUpdated","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::DriveFile,
+                r#"{"type":"driveFileCreated","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::ReadAntenna,
+                r#"{"type":"readAntenna","body":{"id":"x"}}"#,
+            ),
+            (
+                Event::NewFollowRequest,
+                r#"{"type":"receiveFollowRequest","body":{"id":"x"}}"#,
+            ),
+        ] {
+            let event = MainEvent::new(kind, json!({"id": "x"})).unwrap();
+            assert_eq!(serde_json::to_string(&event).unwrap(), expected);
+        }
+
+        // payloads are published as they are
+        assert_eq!(
+            serde_json::to_string(&MainEvent::ReadAllNotifications(json!(null))).unwrap(),
+            r#"{"type":"readAllNotifications","body":null}"#,
+        );
+        assert_eq!(
+            serde_json::to_string(&MainEvent::ReadNotifications(json!(["a", "b"]))).unwrap(),
+            r#"{"type":"readNotifications","body":["a","b"]}"#,
+        );
+    }
+
+    #[test]
+    fn new_antenna_post() {
+        let antenna = antenna::Model {
+            created_at: Utc.timestamp_millis_opt(1716735338211).unwrap().into(),
+            name: "cats".to_owned(),
+            notify: true,
+            keywords: vec!["cat".to_owned()],
+            ..fixture::antenna("antenna")
+        };
+        let event = MainEvent::NewAntennaPost(Box::new(antenna));
+        let expected = r#"{"type":"unreadAntenna","body":{"id":"antenna","createdAt":"2024-05-26T14:55:38.211Z","userId":"owner","name":"cats","src":"all","userListId":null,"withFile":false,"expression":null,"notify":true,"caseSensitive":false,"withReplies":false,"userGroupJoiningId":null,"users":[],"instances":[],"keywords":["cat"],"excludeKeywords":[],"wholeWord":false,"langs":[],"excludeLangs":[],"notifyDigest":false}}"#;
+        assert_eq!(serde_json::to_string(&event).unwrap(), expected);
 
-    publish_to_stream(
-        &Stream::Main { user_id },
-        Some(kind),
-        Some(serde_json::to_string(&object)?),
-    )
-    .await
+        // the napi binding takes the antenna as an object
+        let object = serde_json::from_str(expected)
+            .map(|v: serde_json::Value| v["body"].clone())
+            .unwrap();
+        assert_eq!(
+            MainEvent::new(Event::NewAntennaPost, object).unwrap(),
+            event
+        );
+    }
 }
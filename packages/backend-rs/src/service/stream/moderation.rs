@@ -1,7 +1,7 @@
 use crate::service::stream::{publish_to_stream, Error, Stream};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[macros::export(object)]
 pub struct AbuseUserReportLike {
@@ -11,12 +11,40 @@ pub struct AbuseUserReportLike {
     pub comment: String,
 }
 
+/// Event of the moderation stream, which is received by the moderator
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum ModerationEvent {
+    NewAbuseUserReport(AbuseUserReportLike),
+}
+
 #[macros::export(js_name = "publishToModerationStream")]
 pub async fn publish(moderator_id: String, report: &AbuseUserReportLike) -> Result<(), Error> {
     publish_to_stream(
         &Stream::Moderation { moderator_id },
-        Some("newAbuseUserReport"),
-        Some(serde_json::to_string(report)?),
+        &ModerationEvent::NewAbuseUserReport(report.to_owned()),
+        false,
     )
     .await
 }
+
+#[cfg(test)]
+mod unit_test {
+    use super::{AbuseUserReportLike, ModerationEvent};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn moderation_events() {
+        let report = AbuseUserReportLike {
+            id: "report".to_owned(),
+            target_user_id: "target".to_owned(),
+            reporter_id: "reporter".to_owned(),
+            comment: "spam".to_owned(),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&ModerationEvent::NewAbuseUserReport(report)).unwrap(),
+            r#"{"type":"newAbuseUserReport","body":{"id":"report","targetUserId":"target","reporterId":"reporter","comment":"spam"}}"#,
+        );
+    }
+}
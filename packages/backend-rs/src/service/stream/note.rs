@@ -1,8 +1,9 @@
-use crate::service::stream::{publish_to_stream, Error, Stream};
+use crate::service::stream::{publish_to_stream, Error, Packed, Stream};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 use serde_json::json;
 
-#[macros::export]
-pub enum NoteEvent {
+#[macros::export(js_name = "NoteEvent")]
+pub enum NoteEventKind {
     Delete,
     React,
     Unreact,
@@ -11,33 +12,208 @@ pub enum NoteEvent {
     Vote,
 }
 
+/// Custom emoji used in a reaction
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReactionEmoji {
+    /// `{name}@{host}` (`{name}@.` for local emojis)
+    pub name: String,
+    pub url: String,
+}
+
+/// Event of the note stream, which is received by the clients showing the note
+///
+/// The fields are sorted in alphabetical order, as they used to be published.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum NoteEvent {
+    #[serde(rename_all = "camelCase")]
+    Deleted {
+        /// date passed from the TypeScript backend as it is
+        deleted_at: Packed,
+    },
+    #[serde(rename_all = "camelCase")]
+    Reacted {
+        emoji: Option<ReactionEmoji>,
+        reaction: String,
+        user_id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unreacted { reaction: String, user_id: String },
+    Replied {
+        /// ID of the reply
+        id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Updated {
+        /// date passed from the TypeScript backend as it is
+        updated_at: Packed,
+    },
+    #[serde(rename_all = "camelCase")]
+    PollVoted { choice: i32, user_id: String },
+}
+
+impl NoteEvent {
+    fn new(kind: NoteEventKind, object: Packed) -> Result<Self, Error> {
+        let kind = match kind {
+            NoteEventKind::Delete => "deleted",
+            NoteEventKind::React => "reacted",
+            NoteEventKind::Unreact => "unreacted",
+            NoteEventKind::Reply => "replied",
+            NoteEventKind::Update => "updated",
+            NoteEventKind::Vote => "pollVoted",
+        };
+        Ok(serde_json::from_value(json!({
+            "type": kind,
+            "body": object,
+        }))?)
+    }
+}
+
+/// Message of the note stream, whose body is `{"body":<event body>,"id":<note id>}`
+struct Message<'a> {
+    note_id: &'a str,
+    event: &'a NoteEvent,
+}
+
+impl Serialize for Message<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let event = serde_json::to_value(self.event).map_err(serde::ser::Error::custom)?;
+
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("type", &event["type"])?;
+        map.serialize_entry(
+            "body",
+            &json!({
+                "id": self.note_id,
+                "body": event["body"],
+            }),
+        )?;
+        map.end()
+    }
+}
+
+/// Publishes the event to the stream of the note.
+pub async fn publish_event(note_id: String, event: &NoteEvent) -> Result<(), Error> {
+    let message = Message {
+        note_id: &note_id,
+        event,
+    };
+    publish_to_stream(
+        &Stream::Note {
+            note_id: note_id.to_owned(),
+        },
+        &message,
+        false,
+    )
+    .await
+}
+
 // We want to merge `kind` and `object` into a single enum
 // https://github.com/napi-rs/napi-rs/issues/2036
 
 #[macros::export(js_name = "publishToNoteStream")]
 pub async fn publish(
     note_id: String,
-    kind: NoteEvent,
-    object: &serde_json::Value,
+    kind: NoteEventKind,
+    object: serde_json::Value,
 ) -> Result<(), Error> {
-    let kind = match kind {
-        NoteEvent::Delete => "deleted",
-        NoteEvent::React => "reacted",
-        NoteEvent::Unreact => "unreacted",
-        NoteEvent::Reply => "replied",
-        NoteEvent::Update => "updated",
-        NoteEvent::Vote => "pollVoted",
-    };
+    publish_event(note_id, &NoteEvent::new(kind, object)?).await
+}
 
-    let value = json!({
-        "id": note_id.clone(),
-        "body": object,
-    });
+#[cfg(test)]
+mod unit_test {
+    use super::{Message, NoteEvent, NoteEventKind, ReactionEmoji};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
 
-    publish_to_stream(
-        &Stream::Note { note_id },
-        Some(kind),
-        Some(serde_json::to_string(&value)?),
-    )
-    .await
+    fn message(event: &NoteEvent) -> String {
+        serde_json::to_string(&Message {
+            note_id: "9tr87i03euwphnsw",
+            event,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn note_events() {
+        for (event, expected) in [
+            (
+                NoteEvent::Deleted {
+                    deleted_at: json!({}),
+                },
+                r#"{"type":"deleted","body":{"body":{"deletedAt":{}},"id":"9tr87i03euwphnsw"}}"#,
+            ),
+            (
+                NoteEvent::Reacted {
+                    emoji: Some(ReactionEmoji {
+                        name: "blobcat@.".to_owned(),
+                        url: "https://example.com/files/blobcat.png".to_owned(),
+                    }),
+                    reaction: ":blobcat@.:".to_owned(),
+                    user_id: "user".to_owned(),
+                },
+                r#"{"type":"reacted","body":{"body":{"emoji":{"name":"blobcat@.","url":"https://example.com/files/blobcat.png"},"reaction":":blobcat@.:","userId":"user"},"id":"9tr87i03euwphnsw"}}"#,
+            ),
+            (
+                NoteEvent::Reacted {
+                    emoji: None,
+                    reaction: "👍".to_owned(),
+                    user_id: "user".to_owned(),
+                },
+                r#"{"type":"reacted","body":{"body":{"emoji":null,"reaction":"👍","userId":"user"},"id":"9tr87i03euwphnsw"}}"#,
+            ),
+            (
+                NoteEvent::Unreacted {
+                    reaction: "👍".to_owned(),
+                    user_id: "user".to_owned(),
+                },
+                r#"{"type":"unreacted","body":{"body":{"reaction":"👍","userId":"user"},"id":"9tr87i03euwphnsw"}}"#,
+            ),
+            (
+                NoteEvent::Replied {
+                    id: "reply".to_owned(),
+                },
+                r#"{"type":"replied","body":{"body":{"id":"reply"},"id":"9tr87i03euwphnsw"}}"#,
+            ),
+            (
+                NoteEvent::Updated {
+                    updated_at: json!({}),
+                },
+                r#"{"type":"updated","body":{"body":{"updatedAt":{}},"id":"9tr87i03euwphnsw"}}"#,
+            ),
+            (
+                NoteEvent::PollVoted {
+                    choice: 1,
+                    user_id: "user".to_owned(),
+                },
+                r#"{"type":"pollVoted","body":{"body":{"choice":1,"userId":"user"},"id":"9tr87i03euwphnsw"}}"#,
+            ),
+        ] {
+            assert_eq!(message(&event), expected);
+        }
+    }
+
+    #[test]
+    fn from_napi_object() {
+        assert_eq!(
+            NoteEvent::new(
+                NoteEventKind::React,
+                json!({"reaction": "👍", "emoji": null, "userId": "user"}),
+            )
+            .unwrap(),
+            NoteEvent::Reacted {
+                emoji: None,
+                reaction: "👍".to_owned(),
+                user_id: "user".to_owned(),
+            },
+        );
+        assert_eq!(
+            NoteEvent::new(NoteEventKind::Vote, json!({"choice": 0, "userId": "user"})).unwrap(),
+            NoteEvent::PollVoted {
+                choice: 0,
+                user_id: "user".to_owned(),
+            },
+        );
+        assert!(NoteEvent::new(NoteEventKind::Vote, json!({"userId": "user"})).is_err());
+    }
 }
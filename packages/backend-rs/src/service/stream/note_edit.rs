@@ -2,17 +2,26 @@ use crate::{
     model::entity::note,
     service::stream::{publish_to_stream, Error, Stream},
 };
+use serde::{Deserialize, Serialize};
 
 // for napi export
 // https://github.com/napi-rs/napi-rs/issues/2060
 type Note = note::Model;
 
+/// Event of the note updates stream
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "camelCase")]
+pub enum NoteEditEvent {
+    /// Note after the edit
+    Updated(Note),
+}
+
 #[macros::export(js_name = "publishToNoteUpdatesStream")]
 pub async fn publish(note: &Note) -> Result<(), Error> {
     publish_to_stream(
         &Stream::NoteEdit,
-        Some("updated"),
-        Some(serde_json::to_string(note)?),
+        &NoteEditEvent::Updated(note.to_owned()),
+        false,
     )
     .await
 }
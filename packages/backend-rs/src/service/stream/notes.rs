@@ -7,7 +7,8 @@ use crate::{
 // https://github.com/napi-rs/napi-rs/issues/2060
 type Note = note::Model;
 
+/// Publishes the note to the notes stream, which has no event types.
 #[macros::export(js_name = "publishToNotesStream")]
 pub async fn publish(note: &Note) -> Result<(), Error> {
-    publish_to_stream(&Stream::Notes, None, Some(serde_json::to_string(note)?)).await
+    publish_to_stream(&Stream::Notes, note, false).await
 }
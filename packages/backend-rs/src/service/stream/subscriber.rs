@@ -0,0 +1,280 @@
+//! Subscriber of stream messages
+//!
+//! Messages published by [publish_to_stream](super::publish_to_stream) (in any process,
+//! including the TypeScript backend) are received from the Redis channel and dispatched
+//! to the in-process listeners of each [Stream].
+//!
+//! Each listener has a buffer of [BUFFER_SIZE] messages. Dispatching never waits for
+//! listeners, so a slow listener does not hold up the others. Instead, a listener that
+//! falls behind misses the oldest messages and is notified by [RecvError::Lagged].
+
+use crate::{
+    config::CONFIG,
+    database::redis::get_pubsub,
+    service::stream::Stream,
+    util::{error_chain::format_error, metrics},
+};
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use redis::RedisError;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Once, PoisonError},
+    time::Duration,
+};
+use tokio::sync::broadcast;
+
+#[error_doc::errors]
+pub enum Error {
+    #[error("failed to execute a Redis command")]
+    Redis(#[from] RedisError),
+    #[error("failed to deserialize the message")]
+    Json(#[from] serde_json::Error),
+}
+
+#[error_doc::errors]
+pub enum RecvError {
+    #[doc = "The listener has fallen behind and missed some messages"]
+    #[error("{0} messages have been dropped")]
+    Lagged(u64),
+    #[error("the subscriber has been shut down")]
+    Closed,
+}
+
+/// Message published to a stream
+#[derive(Debug, PartialEq)]
+pub struct Message {
+    /// ID of the entry in the event log if published in the durable mode
+    /// (see [log](super::log))
+    pub id: Option<String>,
+    /// message body (e.g., `{"type":"note","body":{...}}`), which can be deserialized into
+    /// the event type of the stream (e.g., [AntennaEvent](super::antenna::AntennaEvent))
+    pub body: serde_json::Value,
+}
+
+/// Maximum number of messages buffered for each listener
+const BUFFER_SIZE: usize = 256;
+
+/// Time to wait before reconnecting to the Redis server
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct Envelope {
+    channel: String,
+    message: serde_json::Value,
+    id: Option<String>,
+}
+
+static LISTENERS: Lazy<Mutex<HashMap<Stream, broadcast::Sender<Arc<Message>>>>> =
+    Lazy::new(Default::default);
+
+static START: Once = Once::new();
+
+/// Listener of a stream
+pub struct Subscription {
+    stream: Stream,
+    receiver: broadcast::Receiver<Arc<Message>>,
+}
+
+impl Subscription {
+    pub fn stream(&self) -> &Stream {
+        &self.stream
+    }
+
+    /// Receives the next message.
+    ///
+    /// If the listener has fallen behind, [RecvError::Lagged] is returned with the number
+    /// of missed messages, and the following calls return the oldest messages buffered.
+    pub async fn recv(&mut self) -> Result<Arc<Message>, RecvError> {
+        match self.receiver.recv().await {
+            Ok(message) => Ok(message),
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                metrics::count_dropped_stream_messages(count);
+                Err(RecvError::Lagged(count))
+            }
+            Err(broadcast::error::RecvError::Closed) => Err(RecvError::Closed),
+        }
+    }
+
+    /// Converts the subscription into a [Stream](futures_util::Stream) of messages, which
+    /// is useful to forward messages to WebSocket or Server-Sent Events connections.
+    pub fn into_stream(self) -> impl futures_util::Stream<Item = Result<Arc<Message>, RecvError>> {
+        futures_util::stream::unfold(self, |mut subscription| async move {
+            match subscription.recv().await {
+                Err(RecvError::Closed) => None,
+                result => Some((result, subscription)),
+            }
+        })
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut listeners = LISTENERS.lock().unwrap_or_else(PoisonError::into_inner);
+        // the receiver of this subscription has not been dropped yet
+        if listeners
+            .get(&self.stream)
+            .is_some_and(|sender| sender.receiver_count() <= 1)
+        {
+            listeners.remove(&self.stream);
+        }
+    }
+}
+
+/// Returns a new listener of the stream.
+///
+/// The subscriber starts listening to the Redis channel when this is called for the first
+/// time, which must be within a Tokio runtime. Messages published before subscribing are
+/// not received.
+pub fn subscribe(stream: Stream) -> Subscription {
+    START.call_once(|| {
+        tokio::spawn(run());
+    });
+    add_listener(stream)
+}
+
+fn add_listener(stream: Stream) -> Subscription {
+    let receiver = LISTENERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(stream.clone())
+        .or_insert_with(|| broadcast::channel(BUFFER_SIZE).0)
+        .subscribe();
+
+    Subscription { stream, receiver }
+}
+
+/// Dispatches the message received from the Redis channel to the listeners.
+fn dispatch(payload: &str) -> Result<(), Error> {
+    let Envelope {
+        channel,
+        message,
+        id,
+    } = serde_json::from_str(payload)?;
+
+    let Some(stream) = Stream::from_channel(&channel) else {
+        tracing::debug!("unknown stream channel: {}", channel);
+        return Ok(());
+    };
+
+    let mut listeners = LISTENERS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(sender) = listeners.get(&stream) {
+        // this fails if all listeners have been dropped
+        if sender
+            .send(Arc::new(Message { id, body: message }))
+            .is_err()
+        {
+            listeners.remove(&stream);
+        }
+    }
+
+    Ok(())
+}
+
+/// Receives messages until the connection is closed.
+async fn listen() -> Result<(), Error> {
+    let mut pubsub = get_pubsub().await?;
+    pubsub.subscribe(&CONFIG.host).await?;
+    tracing::info!("subscribed to stream messages");
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let result = message
+            .get_payload::<String>()
+            .map_err(Error::from)
+            .and_then(|payload| dispatch(&payload));
+
+        if let Err(err) = result {
+            tracing::warn!(
+                "failed to dispatch a stream message: {}",
+                format_error(&err)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn run() {
+    loop {
+        match listen().await {
+            Ok(()) => tracing::warn!("connection to the Redis server was closed"),
+            Err(err) => tracing::error!(
+                "failed to subscribe to stream messages: {}",
+                format_error(&err)
+            ),
+        }
+        // messages published until reconnected are lost
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{add_listener, dispatch, Message, RecvError, BUFFER_SIZE};
+    use crate::service::stream::Stream;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn payload(channel: &str, index: usize) -> String {
+        json!({
+            "channel": channel,
+            "message": { "type": "note", "body": index },
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn dispatch_messages() {
+        let stream = Stream::Antenna {
+            antenna_id: "dispatch".to_owned(),
+        };
+        let other = Stream::Antenna {
+            antenna_id: "other".to_owned(),
+        };
+        let mut first = add_listener(stream.clone());
+        let mut second = add_listener(stream.clone());
+        let unrelated = add_listener(other);
+
+        dispatch(&payload("antennaStream:dispatch", 0)).unwrap();
+        dispatch(&payload("unknownStream", 1)).unwrap();
+        assert!(dispatch("not a json").is_err());
+
+        let expected = Message {
+            id: None,
+            body: json!({ "type": "note", "body": 0 }),
+        };
+        assert_eq!(*first.recv().await.unwrap(), expected);
+        assert_eq!(*second.recv().await.unwrap(), expected);
+
+        // durable messages have IDs
+        let durable = json!({
+            "channel": "antennaStream:dispatch",
+            "message": { "type": "note", "body": 1 },
+            "id": "1716735338211-0",
+        });
+        dispatch(&durable.to_string()).unwrap();
+        assert_eq!(
+            first.recv().await.unwrap().id.as_deref(),
+            Some("1716735338211-0")
+        );
+        assert!(unrelated.receiver.is_empty());
+        assert_eq!(first.stream(), &stream);
+    }
+
+    #[tokio::test]
+    async fn lagging_listener() {
+        let mut listener = add_listener(Stream::Antenna {
+            antenna_id: "lagging".to_owned(),
+        });
+
+        for index in 0..BUFFER_SIZE + 3 {
+            dispatch(&payload("antennaStream:lagging", index)).unwrap();
+        }
+
+        // the oldest messages are dropped
+        assert!(matches!(listener.recv().await, Err(RecvError::Lagged(3))));
+        assert_eq!(listener.recv().await.unwrap().body["body"], 3);
+    }
+}
@@ -1,7 +1,8 @@
-use crate::service::stream::{publish_to_stream, Error, Stream};
+use crate::service::stream::{publish_to_stream, Error, Packed, Stream};
+use serde::{Deserialize, Serialize};
 
-#[macros::export]
-pub enum UserEvent {
+#[macros::export(js_name = "UserEvent")]
+pub enum UserEventKind {
     Disconnect,
     FollowChannel,
     UnfollowChannel,
@@ -12,30 +13,101 @@ pub enum UserEvent {
     Unfollow,
 }
 
+/// Event of the user stream, which is received by the user
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "body")]
+pub enum UserEvent {
+    #[serde(rename = "terminate")]
+    Disconnect(Packed),
+    #[serde(rename = "followChannel")]
+    FollowChannel(Packed),
+    #[serde(rename = "unfollowChannel")]
+    UnfollowChannel(Packed),
+    #[serde(rename = "updateUserProfile")]
+    UpdateProfile(Packed),
+    #[serde(rename = "mute")]
+    Mute(Packed),
+    #[serde(rename = "unmute")]
+    Unmute(Packed),
+    #[serde(rename = "follow")]
+    Follow(Packed),
+    #[serde(rename = "unfollow")]
+    Unfollow(Packed),
+}
+
+impl UserEvent {
+    fn new(kind: UserEventKind, object: Packed) -> Self {
+        match kind {
+            UserEventKind::Disconnect => Self::Disconnect(object),
+            UserEventKind::FollowChannel => Self::FollowChannel(object),
+            UserEventKind::UnfollowChannel => Self::UnfollowChannel(object),
+            UserEventKind::UpdateProfile => Self::UpdateProfile(object),
+            UserEventKind::Mute => Self::Mute(object),
+            UserEventKind::Unmute => Self::Unmute(object),
+            UserEventKind::Follow => Self::Follow(object),
+            UserEventKind::Unfollow => Self::Unfollow(object),
+        }
+    }
+}
+
+/// Publishes the event to the stream of the user.
+pub async fn publish_event(user_id: String, event: &UserEvent) -> Result<(), Error> {
+    publish_to_stream(&Stream::User { user_id }, event, false).await
+}
+
 // We want to merge `kind` and `object` into a single enum
 // https://github.com/napi-rs/napi-rs/issues/2036
 
 #[macros::export(js_name = "publishToUserStream")]
 pub async fn publish(
     user_id: String,
-    kind: UserEvent,
-    object: &serde_json::Value,
+    kind: UserEventKind,
+    object: serde_json::Value,
 ) -> Result<(), Error> {
-    let kind = match kind {
-        UserEvent::Disconnect => "terminate",
-        UserEvent::FollowChannel => "followChannel",
-        UserEvent::UnfollowChannel => "unfollowChannel",
-        UserEvent::UpdateProfile => "updateUserProfile",
-        UserEvent::Mute => "mute",
-        UserEvent::Unmute => "unmute",
-        UserEvent::Follow => "follow",
-        UserEvent::Unfollow => "unfollow",
-    };
-
-    publish_to_stream(
-        &Stream::User { user_id },
-        Some(kind),
-        Some(serde_json::to_string(&object)?),
-    )
-    .await
+    publish_event(user_id, &UserEvent::new(kind, object)).await
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{UserEvent, UserEventKind};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn user_events() {
+        for (kind, expected) in [
+            (
+                UserEventKind::Disconnect,
+                r#"{"type":"terminate","body":{"id":"x"}}"#,
+            ),
+            (
+                UserEventKind::FollowChannel,
+                r#"{"type":"followChannel","body":{"id":"x"}}"#,
+            ),
+            (
+                UserEventKind::UnfollowChannel,
+                r#"{"type":"unfollowChannel","body":{"id":"x"}}"#,
+            ),
+            (
+                UserEventKind::UpdateProfile,
+                r#"{"type":"updateUserProfile","body":{"id":"x"}}"#,
+            ),
+            (UserEventKind::Mute, r#"{"type":"mute","body":{"id":"x"}}"#),
+            (
+                UserEventKind::Unmute,
+                r#"{"type":"unmute","body":{"id":"x"}}"#,
+            ),
+            (
+                UserEventKind::Follow,
+                r#"{"type":"follow","body":{"id":"x"}}"#,
+            ),
+            (
+                UserEventKind::Unfollow,
+                r#"{"type":"unfollow","body":{"id":"x"}}"#,
+            ),
+        ] {
+            let event = UserEvent::new(kind, json!({"id": "x"}));
+            assert_eq!(serde_json::to_string(&event).unwrap(), expected);
+        }
+    }
 }
@@ -0,0 +1,442 @@
+//! Decide whether a note should be shown to a user based on their mute settings
+
+use crate::{
+    cache,
+    database::db_conn,
+    misc::{mute_filter::MuteFilter, note::elaborate},
+    model::entity::{muting, note, note_thread_muting, renote_muting, reply_muting, user_profile},
+};
+use chrono::{Duration, Utc};
+use sea_orm::{prelude::*, Condition, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, sync::Arc};
+
+#[error_doc::errors]
+pub enum Error {
+    #[doc = "Database error"]
+    #[error(transparent)]
+    Db(#[from] DbErr),
+    #[error("Redis cache operation has failed")]
+    Cache(#[from] cache::redis::Error),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[macros::export(string_enum = "camelCase")]
+pub enum NoteVerdict {
+    Visible,
+    /// the note can be shown in timelines but should not trigger notifications
+    SoftMuted,
+    Hidden,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[macros::export(string_enum = "camelCase")]
+pub enum MuteReason {
+    /// the note or its parent contains muted words
+    Word,
+    /// the note author, the reply target author, or the renoted user is muted
+    User,
+    /// the note author, the reply target author, or the renoted user is on a muted instance
+    Instance,
+    /// the note is a pure renote by a user whose renotes are muted
+    Renote,
+    /// the note is a reply by a user whose replies are muted
+    Reply,
+    /// the note belongs to a muted thread
+    Thread,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[macros::export(object)]
+pub struct NoteFilterResult {
+    pub verdict: NoteVerdict,
+    pub reason: Option<MuteReason>,
+    /// user ID, host, thread ID, or word mute rule that caused the verdict
+    pub subject: Option<String>,
+}
+
+impl NoteFilterResult {
+    const VISIBLE: Self = Self {
+        verdict: NoteVerdict::Visible,
+        reason: None,
+        subject: None,
+    };
+
+    fn new(verdict: NoteVerdict, reason: MuteReason, subject: &str) -> Self {
+        Self {
+            verdict,
+            reason: Some(reason),
+            subject: Some(subject.to_owned()),
+        }
+    }
+}
+
+/// Mute settings of a user as stored in the cache
+#[derive(Default, Serialize, Deserialize)]
+struct MuteSettings {
+    muted_users: Vec<String>,
+    renote_muted_users: Vec<String>,
+    reply_muted_users: Vec<String>,
+    muted_threads: Vec<String>,
+    muted_instances: Vec<String>,
+    muted_words: Vec<String>,
+    muted_patterns: Vec<String>,
+}
+
+impl MuteSettings {
+    async fn fetch(user_id: &str) -> Result<Self, DbErr> {
+        let db = db_conn().await?;
+
+        let muted_users = muting::Entity::find()
+            .select_only()
+            .column(muting::Column::MuteeId)
+            .filter(muting::Column::MuterId.eq(user_id))
+            .filter(
+                Condition::any()
+                    .add(muting::Column::ExpiresAt.is_null())
+                    .add(muting::Column::ExpiresAt.gt(Utc::now())),
+            )
+            .into_tuple::<String>()
+            .all(db)
+            .await?;
+        let renote_muted_users = renote_muting::Entity::find()
+            .select_only()
+            .column(renote_muting::Column::MuteeId)
+            .filter(renote_muting::Column::MuterId.eq(user_id))
+            .into_tuple::<String>()
+            .all(db)
+            .await?;
+        let reply_muted_users = reply_muting::Entity::find()
+            .select_only()
+            .column(reply_muting::Column::MuteeId)
+            .filter(reply_muting::Column::MuterId.eq(user_id))
+            .into_tuple::<String>()
+            .all(db)
+            .await?;
+        let muted_threads = note_thread_muting::Entity::find()
+            .select_only()
+            .column(note_thread_muting::Column::ThreadId)
+            .filter(note_thread_muting::Column::UserId.eq(user_id))
+            .into_tuple::<String>()
+            .all(db)
+            .await?;
+
+        let mut settings = Self {
+            muted_users,
+            renote_muted_users,
+            reply_muted_users,
+            muted_threads,
+            ..Default::default()
+        };
+
+        if let Some(profile) = user_profile::Entity::find_by_id(user_id).one(db).await? {
+            settings.muted_instances = profile.muted_instances;
+            if profile.enable_word_mute {
+                settings.muted_words = profile.muted_words;
+                settings.muted_patterns = profile.muted_patterns;
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+/// Mute settings of a user compiled for checking notes
+///
+/// Settings are cached for a minute, so changes may take that long to take effect.
+pub struct VisibilityFilter {
+    viewer_id: String,
+    muted_users: HashSet<String>,
+    renote_muted_users: HashSet<String>,
+    reply_muted_users: HashSet<String>,
+    muted_threads: HashSet<String>,
+    /// lowercase hosts
+    muted_instances: HashSet<String>,
+    words: Arc<MuteFilter>,
+}
+
+impl VisibilityFilter {
+    fn new(viewer_id: &str, settings: MuteSettings) -> Self {
+        Self {
+            viewer_id: viewer_id.to_owned(),
+            muted_users: settings.muted_users.into_iter().collect(),
+            renote_muted_users: settings.renote_muted_users.into_iter().collect(),
+            reply_muted_users: settings.reply_muted_users.into_iter().collect(),
+            muted_threads: settings.muted_threads.into_iter().collect(),
+            muted_instances: settings
+                .muted_instances
+                .iter()
+                .map(|host| host.to_ascii_lowercase())
+                .collect(),
+            words: MuteFilter::cached(&settings.muted_words, &settings.muted_patterns),
+        }
+    }
+
+    /// Loads the mute settings of the user.
+    pub async fn load(viewer_id: &str) -> Result<Self, Error> {
+        let settings = match cache::get_one(cache::Category::Mute, viewer_id).await? {
+            Some(settings) => settings,
+            None => {
+                // cache miss
+                let settings = MuteSettings::fetch(viewer_id).await?;
+                cache::set_one(
+                    cache::Category::Mute,
+                    viewer_id,
+                    &settings,
+                    Duration::minutes(1),
+                )
+                .await?;
+                settings
+            }
+        };
+
+        Ok(Self::new(viewer_id, settings))
+    }
+
+    /// Checks the users related to the note and their instances.
+    fn check_relations(&self, note: &note::Model) -> Option<NoteFilterResult> {
+        use {MuteReason::*, NoteVerdict::*};
+
+        let users = [
+            Some(&note.user_id),
+            note.reply_user_id.as_ref(),
+            note.renote_user_id.as_ref(),
+        ];
+        if let Some(user_id) = users
+            .into_iter()
+            .flatten()
+            .find(|&user_id| self.muted_users.contains(user_id))
+        {
+            return Some(NoteFilterResult::new(Hidden, User, user_id));
+        }
+
+        // local users have no host
+        let hosts = [
+            note.user_host.as_ref(),
+            note.reply_user_host.as_ref(),
+            note.renote_user_host.as_ref(),
+        ];
+        if let Some(host) = hosts
+            .into_iter()
+            .flatten()
+            .find(|host| self.muted_instances.contains(&host.to_ascii_lowercase()))
+        {
+            return Some(NoteFilterResult::new(Hidden, Instance, host));
+        }
+
+        if note.renote_id.is_some()
+            && note.text.is_none()
+            && self.renote_muted_users.contains(&note.user_id)
+        {
+            return Some(NoteFilterResult::new(Hidden, Renote, &note.user_id));
+        }
+
+        if note.reply_id.is_some() && self.reply_muted_users.contains(&note.user_id) {
+            return Some(NoteFilterResult::new(Hidden, Reply, &note.user_id));
+        }
+
+        None
+    }
+
+    fn check_thread(&self, note: &note::Model) -> NoteFilterResult {
+        match [Some(&note.id), note.thread_id.as_ref()]
+            .into_iter()
+            .flatten()
+            .find(|&id| self.muted_threads.contains(id))
+        {
+            Some(thread_id) => {
+                NoteFilterResult::new(NoteVerdict::SoftMuted, MuteReason::Thread, thread_id)
+            }
+            None => NoteFilterResult::VISIBLE,
+        }
+    }
+
+    /// Decides whether `note` should be shown to the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `note` : the note to check
+    /// * `texts` : texts of the note and its parent ([elaborate]) to check word mutes against
+    pub fn check(&self, note: &note::Model, texts: &[String]) -> NoteFilterResult {
+        // users can't mute themselves
+        if note.user_id == self.viewer_id {
+            return NoteFilterResult::VISIBLE;
+        }
+        if let Some(result) = self.check_relations(note) {
+            return result;
+        }
+        if let Some(muted) = self.words.why_muted(texts) {
+            return NoteFilterResult::new(NoteVerdict::Hidden, MuteReason::Word, &muted.rule);
+        }
+        self.check_thread(note)
+    }
+
+    /// Same as [VisibilityFilter::check], but fetches the texts only if they are needed.
+    pub async fn filter(&self, note: &note::Model) -> Result<NoteFilterResult, Error> {
+        if note.user_id == self.viewer_id {
+            return Ok(NoteFilterResult::VISIBLE);
+        }
+        if let Some(result) = self.check_relations(note) {
+            return Ok(result);
+        }
+        if !self.words.is_empty() {
+            let texts = elaborate!(note, true).await?;
+            if let Some(muted) = self.words.why_muted(&texts) {
+                return Ok(NoteFilterResult::new(
+                    NoteVerdict::Hidden,
+                    MuteReason::Word,
+                    &muted.rule,
+                ));
+            }
+        }
+        Ok(self.check_thread(note))
+    }
+}
+
+// for napi export
+// https://github.com/napi-rs/napi-rs/issues/2060
+type Note = note::Model;
+
+/// Decides whether `note` should be hidden from, soft-muted for, or shown to the user,
+/// based on their muted words, instances, users, renotes, replies, and threads.
+#[macros::export]
+pub async fn filter_note(viewer_id: &str, note: &Note) -> Result<NoteFilterResult, Error> {
+    VisibilityFilter::load(viewer_id).await?.filter(note).await
+}
+
+/// Drops the cached mute settings of the user.
+///
+/// This must be called after the user's mutes, renote/reply/thread mutes,
+/// muted words, or muted instances are changed.
+#[macros::export]
+pub async fn invalidate_mute_cache(user_id: &str) -> Result<(), Error> {
+    Ok(cache::delete_one(cache::Category::Mute, user_id).await?)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{MuteReason, MuteSettings, NoteFilterResult, NoteVerdict, VisibilityFilter};
+    use crate::model::{entity::note, fixture};
+    use pretty_assertions::assert_eq;
+
+    fn note(id: &str, user_id: &str, user_host: Option<&str>) -> note::Model {
+        note::Model {
+            text: Some("hello".to_owned()),
+            user_host: user_host.map(str::to_owned),
+            ..fixture::note(id, user_id)
+        }
+    }
+
+    fn filter() -> VisibilityFilter {
+        let strings = |items: &[&str]| items.iter().map(|&item| item.to_owned()).collect();
+        VisibilityFilter::new(
+            "viewer",
+            MuteSettings {
+                muted_users: strings(&["muted"]),
+                renote_muted_users: strings(&["renoter"]),
+                reply_muted_users: strings(&["replier"]),
+                muted_threads: strings(&["thread"]),
+                muted_instances: strings(&["Muted.Example.com"]),
+                muted_words: strings(&["spoiler"]),
+                muted_patterns: strings(&["/^secret/"]),
+            },
+        )
+    }
+
+    fn hidden(reason: MuteReason, subject: &str) -> NoteFilterResult {
+        NoteFilterResult::new(NoteVerdict::Hidden, reason, subject)
+    }
+
+    #[test]
+    fn filter_by_users_and_instances() {
+        let filter = filter();
+
+        assert_eq!(
+            filter.check(&note("1", "someone", None), &[]),
+            NoteFilterResult::VISIBLE
+        );
+        assert_eq!(
+            filter.check(&note("1", "muted", None), &[]),
+            hidden(MuteReason::User, "muted")
+        );
+        assert_eq!(
+            filter.check(&note("1", "someone", Some("muted.example.com")), &[]),
+            hidden(MuteReason::Instance, "muted.example.com")
+        );
+
+        let mut reply = note("1", "someone", None);
+        reply.reply_id = Some("2".to_owned());
+        reply.reply_user_id = Some("muted".to_owned());
+        assert_eq!(filter.check(&reply, &[]), hidden(MuteReason::User, "muted"));
+
+        let mut renote = note("1", "someone", None);
+        renote.renote_id = Some("2".to_owned());
+        renote.renote_user_id = Some("other".to_owned());
+        renote.renote_user_host = Some("MUTED.example.com".to_owned());
+        assert_eq!(
+            filter.check(&renote, &[]),
+            hidden(MuteReason::Instance, "MUTED.example.com")
+        );
+    }
+
+    #[test]
+    fn filter_renotes_and_replies() {
+        let filter = filter();
+
+        let mut renote = note("1", "renoter", None);
+        renote.renote_id = Some("2".to_owned());
+        renote.text = None;
+        assert_eq!(
+            filter.check(&renote, &[]),
+            hidden(MuteReason::Renote, "renoter")
+        );
+
+        // quotes are not affected
+        renote.text = Some("quote".to_owned());
+        assert_eq!(filter.check(&renote, &[]), NoteFilterResult::VISIBLE);
+
+        let mut reply = note("1", "replier", None);
+        assert_eq!(filter.check(&reply, &[]), NoteFilterResult::VISIBLE);
+        reply.reply_id = Some("2".to_owned());
+        assert_eq!(
+            filter.check(&reply, &[]),
+            hidden(MuteReason::Reply, "replier")
+        );
+    }
+
+    #[test]
+    fn filter_by_words_and_threads() {
+        let filter = filter();
+        let texts = |text: &str| vec![text.to_owned()];
+
+        assert_eq!(
+            filter.check(&note("1", "someone", None), &texts("SPOILER alert")),
+            hidden(MuteReason::Word, "spoiler")
+        );
+        assert_eq!(
+            filter.check(&note("1", "someone", None), &texts("secret")),
+            hidden(MuteReason::Word, "/^secret/")
+        );
+        assert_eq!(
+            filter.check(&note("1", "someone", None), &texts("not a secret")),
+            NoteFilterResult::VISIBLE
+        );
+
+        let mut reply = note("1", "someone", None);
+        reply.thread_id = Some("thread".to_owned());
+        assert_eq!(
+            filter.check(&reply, &[]),
+            NoteFilterResult::new(NoteVerdict::SoftMuted, MuteReason::Thread, "thread")
+        );
+        assert_eq!(
+            filter.check(&note("thread", "someone", None), &[]),
+            NoteFilterResult::new(NoteVerdict::SoftMuted, MuteReason::Thread, "thread")
+        );
+
+        // own notes are always visible
+        assert_eq!(
+            filter.check(&note("thread", "viewer", None), &texts("spoiler")),
+            NoteFilterResult::VISIBLE
+        );
+    }
+}
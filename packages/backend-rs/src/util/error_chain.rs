@@ -20,6 +20,18 @@ pub fn format_error(mut error: &dyn Error) -> String {
     to_return
 }
 
+/// Returns the messages of [`Error`] and its sources (from outermost to innermost)
+pub fn error_messages(mut error: &dyn Error) -> Vec<String> {
+    let mut messages = vec![error.to_string()];
+
+    while let Some(source) = error.source() {
+        messages.push(source.to_string());
+        error = source;
+    }
+
+    messages
+}
+
 #[cfg(test)]
 mod unit_test {
     use pretty_assertions::assert_eq;
@@ -78,5 +90,10 @@ caused by: unexpected string 'foo'"#;
 
         assert_eq!(error_message_1, expected_message_1[1..]);
         assert_eq!(error_message_2, expected_message_2[1..]);
+
+        assert_eq!(
+            super::error_messages(&error_2),
+            vec!["error 2 occured", "unexpected string 'foo'"]
+        );
     }
 }
@@ -1,9 +1,36 @@
-//! Shared [isahc] HTTP client
+//! Shared [isahc] HTTP client and SSRF-safe [fetch]
 
-use crate::config::CONFIG;
-use isahc::{config::*, HttpClient};
-use once_cell::sync::OnceCell;
-use std::time::Duration;
+use crate::{
+    config::{
+        reload::{runtime_config, RuntimeConfig},
+        CONFIG,
+    },
+    misc::ssrf_policy::{ssrf_policy, SsrfPolicy},
+    util::metrics,
+};
+use arc_swap::ArcSwapOption;
+use futures_util::AsyncReadExt;
+use isahc::{
+    config::*,
+    http::{
+        header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION},
+        HeaderMap, Method, Request, Response, StatusCode,
+    },
+    interceptor::{Context, Interceptor, InterceptorFuture},
+    AsyncBody, HttpClient, HttpClientBuilder,
+};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex, Once},
+    time::{Duration, Instant},
+};
+use tracing::Instrument;
+use url::{Host, Url};
 
 #[error_doc::errors]
 pub enum Error {
@@ -13,10 +40,56 @@ pub enum Error {
     UrlParse(#[from] isahc::http::uri::InvalidUri),
 }
 
-static CLIENT: OnceCell<HttpClient> = OnceCell::new();
+/// Records a span and metrics for each request
+struct Telemetry;
+
+impl Interceptor for Telemetry {
+    type Err = isahc::Error;
+
+    fn intercept<'a>(
+        &'a self,
+        request: Request<AsyncBody>,
+        ctx: Context<'a>,
+    ) -> InterceptorFuture<'a, Self::Err> {
+        let method = request.method().to_string();
+        let span = tracing::info_span!(
+            "http_client.request",
+            http.method = %method,
+            server.address = request.uri().host().unwrap_or_default(),
+            http.status_code = tracing::field::Empty,
+        );
+
+        Box::pin(
+            async move {
+                let start = Instant::now();
+                let result = ctx.send(request).await;
+
+                let status = match &result {
+                    Ok(response) => {
+                        let status = response.status().as_u16();
+                        tracing::Span::current().record("http.status_code", status);
+                        status.to_string()
+                    }
+                    Err(_) => "error".to_owned(),
+                };
+                metrics::observe_http_client_request(&method, &status, start.elapsed());
+
+                result
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// HTTP client and the runtime config used to build it
+static CLIENT: ArcSwapOption<(Arc<RuntimeConfig>, HttpClient)> = ArcSwapOption::const_empty();
 
 /// Returns an [HttpClient] that takes the proxy configuration into account.
 ///
+/// The client is rebuilt when the config is reloaded. Each request is recorded as a span
+/// and in the metrics (see [crate::util::metrics]). Use [fetch] to access URLs given by
+/// remote servers or users.
+///
 /// # Example
 /// ```no_run
 /// # use backend_rs::util::http_client::client;
@@ -32,21 +105,484 @@ static CLIENT: OnceCell<HttpClient> = OnceCell::new();
 /// # }
 /// ```
 pub fn client() -> Result<HttpClient, Error> {
-    CLIENT
-        .get_or_try_init(|| {
-            let mut builder = HttpClient::builder()
-                .timeout(Duration::from_secs(10))
-                .default_header("user-agent", &CONFIG.user_agent)
-                .dns_cache(DnsCache::Timeout(Duration::from_secs(60 * 60)));
-
-            if let Some(proxy_url) = &CONFIG.proxy {
-                builder = builder.proxy(Some(proxy_url.parse()?));
-                if let Some(proxy_bypass_hosts) = &CONFIG.proxy_bypass_hosts {
-                    builder = builder.proxy_blacklist(proxy_bypass_hosts);
-                }
+    let runtime_config = runtime_config();
+
+    if let Some(cached) = CLIENT.load().as_ref() {
+        if Arc::ptr_eq(&cached.0, &runtime_config) {
+            return Ok(cached.1.clone());
+        }
+    }
+
+    let client = builder(&runtime_config)?
+        .dns_cache(DnsCache::Timeout(Duration::from_secs(60 * 60)))
+        .build()?;
+    CLIENT.store(Some(Arc::new((runtime_config, client.clone()))));
+
+    Ok(client)
+}
+
+/// Maximum number of clients pinned to resolved addresses (see [pinned_client])
+const PINNED_CLIENT_CAPACITY: usize = 64;
+
+/// Returns a client that connects to `address` when accessing `domain`.
+///
+/// isahc can only pin addresses per client, and each client has its own agent thread and
+/// connection pool, so clients are reused for the same host and address.
+fn pinned_client(domain: &str, address: SocketAddr) -> Result<HttpClient, Error> {
+    type Entry = (Arc<RuntimeConfig>, HttpClient);
+    static CLIENTS: Lazy<Mutex<HashMap<(String, SocketAddr), Entry>>> = Lazy::new(Default::default);
+
+    let runtime_config = runtime_config();
+    let key = (domain.to_owned(), address);
+
+    if let Ok(clients) = CLIENTS.lock() {
+        if let Some((config, client)) = clients.get(&key) {
+            if Arc::ptr_eq(config, &runtime_config) {
+                return Ok(client.clone());
             }
+        }
+    }
+
+    let client = builder(&runtime_config)?
+        .dns_resolve(ResolveMap::new().add(domain, address.port(), address.ip()))
+        .build()?;
+
+    if let Ok(mut clients) = CLIENTS.lock() {
+        if clients.len() >= PINNED_CLIENT_CAPACITY {
+            clients.clear();
+        }
+        clients.insert(key, (runtime_config, client.clone()));
+    }
+
+    Ok(client)
+}
+
+/// Returns whether requests to `host` are sent via the proxy.
+fn uses_proxy(host: &str, runtime_config: &RuntimeConfig) -> bool {
+    CONFIG.proxy.is_some()
+        && !runtime_config
+            .proxy_bypass_hosts
+            .iter()
+            .flatten()
+            .any(|pattern| matches_bypass_host(host, pattern))
+}
+
+/// Returns whether `host` matches the `proxy_bypass_hosts` entry in the same way as curl's
+/// `NOPROXY` (which isahc uses), i.e., the host itself or its subdomains.
+fn matches_bypass_host(host: &str, pattern: &str) -> bool {
+    let pattern = pattern.trim_start_matches('.');
 
-            Ok(builder.build()?)
+    pattern == "*"
+        || host.eq_ignore_ascii_case(pattern)
+        || host.len().checked_sub(pattern.len() + 1).is_some_and(|i| {
+            host.as_bytes()[i] == b'.' && host[i + 1..].eq_ignore_ascii_case(pattern)
         })
-        .cloned()
+}
+
+/// Returns a client builder with the common settings (user agent, proxy, telemetry).
+fn builder(runtime_config: &RuntimeConfig) -> Result<HttpClientBuilder, Error> {
+    let mut builder = HttpClient::builder()
+        .timeout(Duration::from_secs(10))
+        .default_header("user-agent", &CONFIG.user_agent)
+        .interceptor(Telemetry);
+
+    if let Some(proxy_url) = &CONFIG.proxy {
+        builder = builder.proxy(Some(proxy_url.parse()?));
+        if let Some(proxy_bypass_hosts) = &runtime_config.proxy_bypass_hosts {
+            builder = builder.proxy_blacklist(proxy_bypass_hosts);
+        }
+    }
+
+    Ok(builder)
+}
+
+#[error_doc::errors]
+pub enum FetchError {
+    #[error("failed to acquire an HTTP client")]
+    HttpClient(#[from] Error),
+    #[error("HTTP request failed")]
+    Isahc(#[from] isahc::Error),
+    #[error("invalid HTTP request")]
+    Request(#[from] isahc::http::Error),
+    #[error("failed to read the HTTP response body")]
+    Io(#[from] io::Error),
+    #[error("invalid URL")]
+    InvalidUrl(#[from] url::ParseError),
+    #[doc = "The URL is not an HTTP(S) URL with a host"]
+    #[error("unsupported URL ({0})")]
+    UnsupportedUrl(String),
+    #[error("failed to resolve {0}")]
+    Resolve(String, #[source] io::Error),
+    #[doc = "The host has no addresses"]
+    #[error("{0} has no addresses")]
+    NoAddress(String),
+    #[doc = "The host resolves to an address that is not allowed"]
+    #[error("{0} resolves to a forbidden address ({1})")]
+    ForbiddenAddress(String, IpAddr),
+    #[doc = "The port is not allowed"]
+    #[error("access to port {1} of {0} is not allowed")]
+    ForbiddenPort(String, u16),
+    #[doc = "The redirect limit is exceeded"]
+    #[error("too many redirects (last location: {0})")]
+    TooManyRedirects(String),
+    #[doc = "The redirect response has no valid `Location` header"]
+    #[error("invalid redirect from {0}")]
+    InvalidRedirect(String),
+    #[doc = "The response body exceeds the size limit"]
+    #[error("response body from {0} exceeds {1} bytes")]
+    BodyTooLarge(String, u64),
+    #[doc = "The time limit is exceeded"]
+    #[error("request to {0} timed out")]
+    Timeout(String),
+    #[doc = "Bad HTTP status"]
+    #[error("{0} returned {1}")]
+    BadStatus(String, StatusCode),
+}
+
+/// Limits applied to [fetch]
+#[derive(Clone, Debug)]
+pub struct FetchOptions {
+    /// Time limit for the whole fetch, including redirects and reading the body
+    pub timeout: Duration,
+    /// Maximum size of the response body in bytes
+    pub max_body_size: u64,
+    /// Maximum number of redirects to follow
+    pub max_redirects: u32,
+    /// Whether to return the first `max_body_size` bytes instead of failing if the body is larger
+    pub truncate_body: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_body_size: 8 * 1024 * 1024,
+            max_redirects: 5,
+            truncate_body: false,
+        }
+    }
+}
+
+/// Response returned by [fetch]
+#[derive(Debug)]
+pub struct FetchResponse {
+    /// URL of the response (differs from the requested one if redirected)
+    pub url: Url,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl FetchResponse {
+    /// Returns [FetchError::BadStatus] unless the status code is 2xx.
+    pub fn error_for_status(self) -> Result<Self, FetchError> {
+        if self.status.is_success() {
+            Ok(self)
+        } else {
+            Err(FetchError::BadStatus(self.url.to_string(), self.status))
+        }
+    }
+
+    /// Decodes the body as UTF-8, replacing invalid sequences.
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+
+    /// Deserializes the body as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+/// Sends a request to a remote server without letting it reach internal addresses.
+///
+/// The host is resolved once and the connection is pinned to the validated address, so a
+/// host cannot pass the check and then resolve to another address (DNS rebinding).
+/// Redirects are followed manually and each location is validated again.
+///
+/// Note that requests sent via the proxy (`proxy` in the config) are not pinned, because
+/// the proxy resolves the hosts by itself. The proxy should deny internal addresses.
+///
+/// # Example
+/// ```no_run
+/// # use backend_rs::util::http_client::{fetch, FetchOptions};
+/// use isahc::Request;
+///
+/// # async fn f() -> Result<(), Box<dyn std::error::Error>> {
+/// let request = Request::get("https://example.com/").body(Vec::new())?;
+/// let options = FetchOptions {
+///     max_body_size: 1024 * 1024,
+///     ..Default::default()
+/// };
+/// let response = fetch(request, &options).await?.error_for_status()?;
+///
+/// println!("{}", response.text());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn fetch(
+    request: Request<Vec<u8>>,
+    options: &FetchOptions,
+) -> Result<FetchResponse, FetchError> {
+    let deadline = Instant::now() + options.timeout;
+    let (parts, mut body) = request.into_parts();
+    let mut method = parts.method;
+    let mut headers = parts.headers;
+    let mut url = Url::parse(&parts.uri.to_string())?;
+
+    for _ in 0..=options.max_redirects {
+        let timed_out = || FetchError::Timeout(url.to_string());
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let address = tokio::time::timeout(remaining, resolve(&url, ssrf_policy()))
+            .await
+            .map_err(|_| timed_out())??;
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(timed_out());
+        }
+
+        let client = match url.host() {
+            Some(Host::Domain(domain)) if uses_proxy(domain, &runtime_config()) => {
+                static WARN: Once = Once::new();
+                WARN.call_once(|| {
+                    tracing::warn!(
+                        "requests via the proxy are not protected from DNS rebinding \
+                        (the proxy should deny internal addresses)"
+                    )
+                });
+                client()?
+            }
+            Some(Host::Domain(domain)) => pinned_client(domain, address)?,
+            _ => client()?,
+        };
+
+        let mut request = Request::builder()
+            .method(method.clone())
+            .uri(url.as_str())
+            .redirect_policy(RedirectPolicy::None)
+            .timeout(remaining)
+            .body(body.clone())?;
+        request.headers_mut().extend(headers.clone());
+
+        let response = client
+            .send_async(request)
+            .await
+            .map_err(|err| match err.is_timeout() {
+                true => timed_out(),
+                false => FetchError::Isahc(err),
+            })?;
+
+        let Some(next_method) = redirect_method(response.status(), &method) else {
+            let status = response.status();
+            let response_headers = response.headers().clone();
+            let body = read_body(response, &url, options).await?;
+
+            return Ok(FetchResponse {
+                url,
+                status,
+                headers: response_headers,
+                body,
+            });
+        };
+
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok())
+            .ok_or_else(|| FetchError::InvalidRedirect(url.to_string()))?;
+
+        if next_method != method {
+            body = Vec::new();
+            headers.remove(CONTENT_TYPE);
+            headers.remove(CONTENT_LENGTH);
+            method = next_method;
+        }
+        // don't leak credentials to another server
+        if location.origin() != url.origin() {
+            headers.remove(AUTHORIZATION);
+            headers.remove(COOKIE);
+        }
+
+        tracing::debug!("redirected from {} to {}", url, location);
+        url = location;
+    }
+
+    Err(FetchError::TooManyRedirects(url.to_string()))
+}
+
+/// Resolves the host of `url` and ensures that the policy allows all of its addresses.
+async fn resolve(url: &Url, policy: &SsrfPolicy) -> Result<SocketAddr, FetchError> {
+    let unsupported = || FetchError::UnsupportedUrl(url.to_string());
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(unsupported());
+    }
+    let (Some(host), Some(port)) = (url.host(), url.port_or_known_default()) else {
+        return Err(unsupported());
+    };
+    if !policy.is_allowed_port(port) {
+        return Err(FetchError::ForbiddenPort(host.to_string(), port));
+    }
+
+    let addrs: Vec<SocketAddr> = match host {
+        Host::Ipv4(ip) => vec![SocketAddr::new(ip.into(), port)],
+        Host::Ipv6(ip) => vec![SocketAddr::new(ip.into(), port)],
+        Host::Domain(domain) => tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|err| FetchError::Resolve(domain.to_owned(), err))?
+            .collect(),
+    };
+
+    if let Some(addr) = addrs.iter().find(|addr| !policy.is_allowed_ip(addr.ip())) {
+        return Err(FetchError::ForbiddenAddress(host.to_string(), addr.ip()));
+    }
+
+    addrs
+        .into_iter()
+        .next()
+        .ok_or_else(|| FetchError::NoAddress(host.to_string()))
+}
+
+/// Returns the method used to follow the redirect, or [None] if `status` is not a redirect.
+fn redirect_method(status: StatusCode, method: &Method) -> Option<Method> {
+    match status {
+        StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND if method == Method::POST => {
+            Some(Method::GET)
+        }
+        StatusCode::SEE_OTHER if method != Method::HEAD => Some(Method::GET),
+        StatusCode::MOVED_PERMANENTLY
+        | StatusCode::FOUND
+        | StatusCode::SEE_OTHER
+        | StatusCode::TEMPORARY_REDIRECT
+        | StatusCode::PERMANENT_REDIRECT => Some(method.clone()),
+        _ => None,
+    }
+}
+
+/// Reads the response body, failing if it is larger than `max_body_size` bytes.
+async fn read_body(
+    response: Response<AsyncBody>,
+    url: &Url,
+    options: &FetchOptions,
+) -> Result<Vec<u8>, FetchError> {
+    let max_size = options.max_body_size;
+    let too_large = || FetchError::BodyTooLarge(url.to_string(), max_size);
+
+    let content_length = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok());
+    if !options.truncate_body && content_length.is_some_and(|length| length > max_size) {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    response
+        .into_body()
+        .take(max_size + 1)
+        .read_to_end(&mut body)
+        .await
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::TimedOut => FetchError::Timeout(url.to_string()),
+            _ => FetchError::Io(err),
+        })?;
+
+    if body.len() as u64 > max_size {
+        if !options.truncate_body {
+            return Err(too_large());
+        }
+        body.truncate(max_size as usize);
+    }
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{matches_bypass_host, redirect_method, resolve, FetchError};
+    use crate::misc::ssrf_policy::SsrfPolicy;
+    use isahc::http::{Method, StatusCode};
+    use pretty_assertions::assert_eq;
+    use url::Url;
+
+    #[test]
+    fn bypass_host() {
+        assert!(matches_bypass_host("example.com", "example.com"));
+        assert!(matches_bypass_host("a.example.com", "example.com"));
+        assert!(matches_bypass_host("a.example.com", ".example.com"));
+        assert!(matches_bypass_host("EXAMPLE.com", "example.COM"));
+        assert!(matches_bypass_host("anything.test", "*"));
+        assert!(!matches_bypass_host("badexample.com", "example.com"));
+        assert!(!matches_bypass_host("example.com", "a.example.com"));
+        assert!(!matches_bypass_host("ä.example.org", "example.com"));
+    }
+
+    #[test]
+    fn redirect() {
+        assert_eq!(redirect_method(StatusCode::OK, &Method::GET), None);
+        assert_eq!(
+            redirect_method(StatusCode::NOT_MODIFIED, &Method::GET),
+            None
+        );
+        assert_eq!(
+            redirect_method(StatusCode::MOVED_PERMANENTLY, &Method::GET),
+            Some(Method::GET)
+        );
+        assert_eq!(
+            redirect_method(StatusCode::FOUND, &Method::POST),
+            Some(Method::GET)
+        );
+        assert_eq!(
+            redirect_method(StatusCode::SEE_OTHER, &Method::PUT),
+            Some(Method::GET)
+        );
+        assert_eq!(
+            redirect_method(StatusCode::SEE_OTHER, &Method::HEAD),
+            Some(Method::HEAD)
+        );
+        assert_eq!(
+            redirect_method(StatusCode::TEMPORARY_REDIRECT, &Method::POST),
+            Some(Method::POST)
+        );
+        assert_eq!(
+            redirect_method(StatusCode::PERMANENT_REDIRECT, &Method::POST),
+            Some(Method::POST)
+        );
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)] // can't call foreign function `getaddrinfo` on OS `linux`
+    async fn reject_forbidden_destinations() {
+        let policy = SsrfPolicy::default();
+        let resolve = |url: &str| {
+            let url = Url::parse(url).unwrap();
+            let policy = &policy;
+            async move { resolve(&url, policy).await }
+        };
+
+        assert!(matches!(
+            resolve("http://127.0.0.1:3000/").await,
+            Err(FetchError::ForbiddenAddress(..))
+        ));
+        assert!(matches!(
+            resolve("http://[::ffff:10.0.0.1]/").await,
+            Err(FetchError::ForbiddenAddress(..))
+        ));
+        assert!(matches!(
+            resolve("http://localhost:3000/").await,
+            Err(FetchError::ForbiddenAddress(..))
+        ));
+        assert!(matches!(
+            resolve("ftp://example.com/").await,
+            Err(FetchError::UnsupportedUrl(_))
+        ));
+        assert_eq!(
+            resolve("http://8.8.8.8/").await.unwrap(),
+            "8.8.8.8:80".parse().unwrap()
+        );
+    }
 }
@@ -0,0 +1,250 @@
+//! ID generation utility
+//!
+//! New IDs are generated in the scheme selected by `id` in the server config ([cuid2]-based
+//! Firefish IDs by default). The timestamps of IDs in the other supported schemes can also
+//! be extracted, so that the data migrated from other Misskey forks keeps working.
+
+mod scheme;
+
+pub use scheme::{Aid, Aidx, Cuid, IdScheme, Meid, ObjectId, Ulid};
+
+use crate::config::CONFIG;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use sea_orm::{sea_query::SimpleExpr, ColumnTrait};
+use std::{cmp, sync::OnceLock};
+
+static FINGERPRINT: OnceLock<String> = OnceLock::new();
+static GENERATOR: OnceLock<cuid2::CuidConstructor> = OnceLock::new();
+
+const TIMESTAMP_LENGTH: u8 = 8;
+
+/// Supported schemes in the order of detection (see [detect_scheme])
+const SCHEMES: [&dyn IdScheme; 6] = [&Ulid, &Meid, &ObjectId, &Aid, &Aidx, &Cuid];
+
+/// Initializes Cuid2 generator.
+fn init_id_generator(length: u8, fingerprint: &str) {
+    FINGERPRINT.get_or_init(move || format!("{}{}", fingerprint, cuid2::create_id()));
+    GENERATOR.get_or_init(move || {
+        cuid2::CuidConstructor::new()
+            // length to pass shoule be greater than or equal to 8.
+            .with_length(cmp::max(length - TIMESTAMP_LENGTH, 8).into())
+            .with_fingerprinter(|| FINGERPRINT.get().unwrap().clone())
+    });
+}
+
+/// Returns Cuid2 with the length specified by [init_id_generator].
+/// It automatically calls [init_id_generator], if the generator has not been initialized.
+fn create_cuid() -> String {
+    if GENERATOR.get().is_none() {
+        let length = match CONFIG.cuid.as_ref() {
+            Some(cuid) => cuid.length.unwrap_or(16).clamp(16, 24),
+            None => 16,
+        };
+        let fingerprint = match CONFIG.cuid.as_ref() {
+            Some(cuid) => cuid.fingerprint.as_deref().unwrap_or_default(),
+            None => "",
+        };
+        init_id_generator(length, fingerprint);
+    }
+    GENERATOR.get().unwrap().create_id()
+}
+
+/// Returns the scheme with the name (e.g., `aidx`).
+pub fn scheme_by_name(name: &str) -> Option<&'static dyn IdScheme> {
+    SCHEMES.into_iter().find(|scheme| scheme.name() == name)
+}
+
+/// Guesses the scheme of the ID.
+///
+/// `aid`, `aidx`, and Firefish IDs are indistinguishable in some cases, but they contain
+/// the timestamp in the same way. Likewise, `meid` and `objectid` are told apart by the
+/// first character of their timestamps, which works until the year 2038.
+pub fn detect_scheme(id: &str) -> Option<&'static dyn IdScheme> {
+    SCHEMES.into_iter().find(|scheme| scheme.matches(id))
+}
+
+static ID_SCHEME: Lazy<&'static dyn IdScheme> = Lazy::new(|| {
+    match CONFIG.id.as_deref() {
+        Some(name) => scheme_by_name(name).unwrap_or_else(|| {
+            // `check_config` reports this
+            tracing::error!("unknown ID scheme `{}`, falling back to `cuid`", name);
+            &Cuid
+        }),
+        None => &Cuid,
+    }
+});
+
+/// Returns the scheme used to generate new IDs.
+pub fn id_scheme() -> &'static dyn IdScheme {
+    *ID_SCHEME
+}
+
+#[derive(thiserror::Error, Debug)]
+#[doc = "Error type to indicate invalid Firefish ID"]
+#[error("'{id}' is not a valid Firefish ID")]
+pub struct InvalidIdError {
+    id: String,
+}
+
+/// Returns the timestamp (Unix time in milliseconds) contained in the ID of any supported scheme.
+#[macros::export]
+pub fn get_timestamp(id: &str) -> Result<i64, InvalidIdError> {
+    detect_scheme(id)
+        .and_then(|scheme| scheme.timestamp(id))
+        .ok_or_else(|| InvalidIdError { id: id.to_owned() })
+}
+
+/// Returns whether the string is an ID of any supported scheme.
+#[macros::export]
+pub fn is_valid_id(id: &str) -> bool {
+    get_timestamp(id).is_ok()
+}
+
+/// Returns the smallest possible ID (in the configured scheme) generated at the datetime.
+#[macros::export]
+pub fn min_id_at(date: DateTime<Utc>) -> String {
+    id_scheme().min_id(date)
+}
+
+/// Returns the largest possible ID (in the configured scheme) generated at the datetime.
+#[macros::export]
+pub fn max_id_at(date: DateTime<Utc>) -> String {
+    id_scheme().max_id(date)
+}
+
+/// Returns a condition that the ID in `column` was generated between `from` and `to` (inclusive).
+///
+/// # Example
+/// ```no_run
+/// # use backend_rs::{model::entity::note, util::id::created_between};
+/// use chrono::{Duration, Utc};
+/// use sea_orm::{EntityTrait, QueryFilter};
+///
+/// let now = Utc::now();
+/// let notes_in_last_hour = note::Entity::find()
+///     .filter(created_between(note::Column::Id, now - Duration::hours(1), now));
+/// ```
+pub fn created_between<C: ColumnTrait>(
+    column: C,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> SimpleExpr {
+    column.between(min_id_at(from), max_id_at(to))
+}
+
+/// Returns a condition that the ID in `column` was generated at or after `datetime`.
+pub fn created_since<C: ColumnTrait>(column: C, datetime: DateTime<Utc>) -> SimpleExpr {
+    column.gte(min_id_at(datetime))
+}
+
+/// Returns a condition that the ID in `column` was generated at or before `datetime`.
+pub fn created_until<C: ColumnTrait>(column: C, datetime: DateTime<Utc>) -> SimpleExpr {
+    column.lte(max_id_at(datetime))
+}
+
+/// Generates an ID in the configured scheme.
+///
+/// By default, the generated ID results in the form of `[8 chars timestamp] + [cuid2]`.
+/// The minimum and maximum lengths are 16 and 24, respectively.
+/// With the length of 16, namely 8 for cuid2, roughly 1427399 IDs are needed
+/// in the same millisecond to reach 50% chance of collision.
+///
+/// Ref: <https://github.com/paralleldrive/cuid2#parameterized-length>
+#[macros::export]
+pub fn gen_id() -> String {
+    id_scheme().generate(Utc::now())
+}
+
+/// Generate an ID using a specific datetime
+#[macros::export]
+pub fn gen_id_at(date: DateTime<Utc>) -> String {
+    id_scheme().generate(date)
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{
+        created_between, gen_id, gen_id_at, get_timestamp, is_valid_id, max_id_at, min_id_at,
+    };
+    use crate::model::entity::note;
+    use chrono::{Duration, Utc};
+    use pretty_assertions::{assert_eq, assert_ne};
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+    use std::thread;
+
+    #[test]
+    fn create_and_decode_id() {
+        let now = Utc::now();
+        assert_eq!(gen_id().len(), 16);
+        assert_ne!(gen_id_at(now), gen_id_at(now));
+        assert_ne!(gen_id(), gen_id());
+
+        let id1 = thread::spawn(move || gen_id_at(now));
+        let id2 = thread::spawn(move || gen_id_at(now));
+        assert_ne!(id1.join().unwrap(), id2.join().unwrap());
+
+        let test_id = gen_id_at(now);
+        let timestamp = get_timestamp(&test_id).unwrap();
+        assert_eq!(now.timestamp_millis(), timestamp);
+
+        let now_id = gen_id_at(now);
+        let old_id = gen_id_at(now - Duration::milliseconds(1));
+        let future_id = gen_id_at(now + Duration::milliseconds(1));
+        assert!(old_id < now_id);
+        assert!(now_id < future_id);
+
+        assert!(min_id_at(now) <= now_id && now_id <= max_id_at(now));
+        assert!(max_id_at(now) < future_id);
+    }
+
+    #[test]
+    fn query_time_range() {
+        let now = Utc::now();
+        let query = note::Entity::find()
+            .filter(created_between(note::Column::Id, now, now))
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(query.ends_with(&format!(
+            r#"WHERE "note"."id" BETWEEN '{}' AND '{}'"#,
+            min_id_at(now),
+            max_id_at(now)
+        )));
+    }
+
+    #[test]
+    fn get_timestamp_from_id() {
+        assert_eq!(get_timestamp("9e112pilk1").unwrap(), 1682499501741);
+        assert_eq!(get_timestamp("9e183znmxa").unwrap(), 1682511318850);
+        assert_eq!(get_timestamp("9e9srqr79p").unwrap(), 1683029748787);
+        assert_eq!(get_timestamp("9tr87i03euwphnsw").unwrap(), 1716735338211);
+        assert_eq!(get_timestamp("9s9wn2e9cio09do1").unwrap(), 1713511201761);
+        assert_eq!(get_timestamp("9tr7slj2tt2ml1wp").unwrap(), 1716734642942);
+
+        // aidx, meid, objectid, and ulid
+        assert_eq!(get_timestamp("9tr87i030a1b0001").unwrap(), 1716735338211);
+        assert_eq!(
+            get_timestamp("818fb56666e3a1b2c3d4e5f6").unwrap(),
+            1716735338211
+        );
+        assert_eq!(
+            get_timestamp("66534d6a0123456789abcdef").unwrap(),
+            1716735338000
+        );
+        assert_eq!(
+            get_timestamp("01HYTPCSQ3ABCDEFGHJKMNPQRS").unwrap(),
+            1716735338211
+        );
+
+        assert!(get_timestamp("").is_err());
+        assert!(get_timestamp("9tr87").is_err());
+        assert!(get_timestamp("9TR87I03EUWPHNSW").is_err());
+        assert!(get_timestamp("あいうえおかきくけこ").is_err());
+
+        assert!(is_valid_id("9tr87i03euwphnsw"));
+        assert!(is_valid_id("01HYTPCSQ3ABCDEFGHJKMNPQRS"));
+        assert!(!is_valid_id("9tr87"));
+        assert!(!is_valid_id("9tr87i03-uwphnsw"));
+    }
+}
@@ -0,0 +1,379 @@
+//! ID formats used by Firefish and other Misskey forks
+
+use super::create_cuid;
+use basen::BASE36;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rand::{thread_rng, Rng};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+const TIME_2000: i64 = 946_684_800_000;
+const MEID_OFFSET: i64 = 0x800000000000;
+const CROCKFORD_BASE32: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// ID format
+///
+/// The IDs generated by each scheme sort lexicographically in the order of their timestamps.
+pub trait IdScheme: Send + Sync {
+    /// Name used in the server config (e.g., `aidx`)
+    fn name(&self) -> &'static str;
+    /// Generates an ID that contains the timestamp.
+    fn generate(&self, datetime: DateTime<Utc>) -> String;
+    /// Returns whether `id` looks like an ID in this format.
+    fn matches(&self, id: &str) -> bool;
+    /// Returns the timestamp (Unix time in milliseconds) contained in `id`.
+    ///
+    /// `id` must be an ID that [IdScheme::matches].
+    fn timestamp(&self, id: &str) -> Option<i64>;
+    /// Returns the smallest possible ID generated at `datetime`.
+    fn min_id(&self, datetime: DateTime<Utc>) -> String;
+    /// Returns the largest possible ID generated at `datetime`.
+    fn max_id(&self, datetime: DateTime<Utc>) -> String;
+}
+
+/// Pads the timestamp part with `c` up to `length` characters.
+fn pad(mut time: String, c: char, length: usize) -> String {
+    let padding = length.saturating_sub(time.len());
+    time.extend(std::iter::repeat(c).take(padding));
+    time
+}
+
+fn is_base36(id: &str) -> bool {
+    id.bytes()
+        .all(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+}
+
+fn is_hex(id: &str) -> bool {
+    id.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn base36(n: u64, width: usize) -> String {
+    format!("{:0>width$}", BASE36.encode_var_len(&n), width = width)
+}
+
+/// Returns the first 8 characters that represent the time since 2000-01-01 in base36.
+fn base36_time(datetime: DateTime<Utc>) -> String {
+    base36(0.max(datetime.timestamp_millis() - TIME_2000) as u64, 8)
+}
+
+fn base36_timestamp(id: &str) -> Option<i64> {
+    let n: u64 = BASE36.decode_var_len(id.get(..8)?)?;
+    Some(n as i64 + TIME_2000)
+}
+
+fn random_hex(length: usize) -> String {
+    let mut rng = thread_rng();
+    (0..length)
+        .map(|_| char::from_digit(rng.gen_range(0..16), 16).unwrap())
+        .collect()
+}
+
+/// Firefish ID (`[8 chars timestamp] + [cuid2]`, 16 to 24 characters)
+pub struct Cuid;
+
+impl IdScheme for Cuid {
+    fn name(&self) -> &'static str {
+        "cuid"
+    }
+
+    fn generate(&self, datetime: DateTime<Utc>) -> String {
+        format!("{}{}", base36_time(datetime), create_cuid())
+    }
+
+    fn matches(&self, id: &str) -> bool {
+        (16..=24).contains(&id.len()) && is_base36(id)
+    }
+
+    fn timestamp(&self, id: &str) -> Option<i64> {
+        base36_timestamp(id)
+    }
+
+    fn min_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(base36_time(datetime), '0', 16)
+    }
+
+    fn max_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(base36_time(datetime), 'z', 24)
+    }
+}
+
+/// Misskey's `aid` (`[8 chars timestamp] + [2 chars counter]` in base36)
+pub struct Aid;
+
+static AID_COUNTER: Lazy<AtomicU32> = Lazy::new(|| AtomicU32::new(thread_rng().gen()));
+
+impl IdScheme for Aid {
+    fn name(&self) -> &'static str {
+        "aid"
+    }
+
+    fn generate(&self, datetime: DateTime<Utc>) -> String {
+        let noise = AID_COUNTER.fetch_add(1, Ordering::Relaxed) % 36u32.pow(2);
+        format!("{}{}", base36_time(datetime), base36(noise.into(), 2))
+    }
+
+    fn matches(&self, id: &str) -> bool {
+        id.len() == 10 && is_base36(id)
+    }
+
+    fn timestamp(&self, id: &str) -> Option<i64> {
+        base36_timestamp(id)
+    }
+
+    fn min_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(base36_time(datetime), '0', 10)
+    }
+
+    fn max_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(base36_time(datetime), 'z', 10)
+    }
+}
+
+/// Misskey's `aidx` (`[8 chars timestamp] + [4 chars node ID] + [4 chars counter]` in base36)
+pub struct Aidx;
+
+static AIDX_NODE_ID: Lazy<String> =
+    Lazy::new(|| base36(thread_rng().gen_range(0..36u64.pow(4)), 4));
+static AIDX_COUNTER: Lazy<AtomicU32> = Lazy::new(|| AtomicU32::new(thread_rng().gen()));
+
+impl IdScheme for Aidx {
+    fn name(&self) -> &'static str {
+        "aidx"
+    }
+
+    fn generate(&self, datetime: DateTime<Utc>) -> String {
+        let counter = AIDX_COUNTER.fetch_add(1, Ordering::Relaxed) % 36u32.pow(4);
+        format!(
+            "{}{}{}",
+            base36_time(datetime),
+            *AIDX_NODE_ID,
+            base36(counter.into(), 4)
+        )
+    }
+
+    fn matches(&self, id: &str) -> bool {
+        id.len() == 16 && is_base36(id)
+    }
+
+    fn timestamp(&self, id: &str) -> Option<i64> {
+        base36_timestamp(id)
+    }
+
+    fn min_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(base36_time(datetime), '0', 16)
+    }
+
+    fn max_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(base36_time(datetime), 'z', 16)
+    }
+}
+
+fn meid_time(datetime: DateTime<Utc>) -> String {
+    format!("{:012x}", 0.max(datetime.timestamp_millis()) + MEID_OFFSET)
+}
+
+/// Misskey's `meid` (`[12 chars timestamp + 0x800000000000] + [12 chars random]` in hex)
+pub struct Meid;
+
+impl IdScheme for Meid {
+    fn name(&self) -> &'static str {
+        "meid"
+    }
+
+    fn generate(&self, datetime: DateTime<Utc>) -> String {
+        format!("{}{}", meid_time(datetime), random_hex(12))
+    }
+
+    fn matches(&self, id: &str) -> bool {
+        // the timestamp part starts with `8` until the year 2527
+        id.len() == 24 && id.starts_with('8') && is_hex(id)
+    }
+
+    fn timestamp(&self, id: &str) -> Option<i64> {
+        let time = i64::from_str_radix(id.get(..12)?, 16).ok()?;
+        Some(time - MEID_OFFSET)
+    }
+
+    fn min_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(meid_time(datetime), '0', 24)
+    }
+
+    fn max_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(meid_time(datetime), 'f', 24)
+    }
+}
+
+fn object_id_time(datetime: DateTime<Utc>) -> String {
+    format!("{:08x}", 0.max(datetime.timestamp()))
+}
+
+/// MongoDB's ObjectId (`[8 chars timestamp in seconds] + [16 chars random]` in hex)
+pub struct ObjectId;
+
+impl IdScheme for ObjectId {
+    fn name(&self) -> &'static str {
+        "objectid"
+    }
+
+    fn generate(&self, datetime: DateTime<Utc>) -> String {
+        format!("{}{}", object_id_time(datetime), random_hex(16))
+    }
+
+    fn matches(&self, id: &str) -> bool {
+        // the timestamp part starts with `0`-`7` until the year 2038
+        id.len() == 24 && matches!(id.as_bytes()[0], b'0'..=b'7') && is_hex(id)
+    }
+
+    fn timestamp(&self, id: &str) -> Option<i64> {
+        let time = i64::from_str_radix(id.get(..8)?, 16).ok()?;
+        Some(time * 1000)
+    }
+
+    fn min_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(object_id_time(datetime), '0', 24)
+    }
+
+    fn max_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(object_id_time(datetime), 'f', 24)
+    }
+}
+
+/// [ULID](https://github.com/ulid/spec) (`[10 chars timestamp] + [16 chars random]` in Crockford's base32)
+pub struct Ulid;
+
+fn ulid_time(datetime: DateTime<Utc>) -> String {
+    let mut time = 0.max(datetime.timestamp_millis()) as u64;
+    let mut chars = [0u8; 10];
+
+    for c in chars.iter_mut().rev() {
+        *c = CROCKFORD_BASE32[(time & 31) as usize];
+        time >>= 5;
+    }
+
+    String::from_utf8_lossy(&chars).into_owned()
+}
+
+fn crockford_digit(c: u8) -> Option<u64> {
+    let c = c.to_ascii_uppercase();
+    CROCKFORD_BASE32
+        .iter()
+        .position(|&d| d == c)
+        .map(|n| n as u64)
+}
+
+impl IdScheme for Ulid {
+    fn name(&self) -> &'static str {
+        "ulid"
+    }
+
+    fn generate(&self, datetime: DateTime<Utc>) -> String {
+        let mut rng = thread_rng();
+        let random: String = (0..16)
+            .map(|_| char::from(CROCKFORD_BASE32[rng.gen_range(0..32)]))
+            .collect();
+
+        format!("{}{}", ulid_time(datetime), random)
+    }
+
+    fn matches(&self, id: &str) -> bool {
+        // the first character is at most `7` since the timestamp is 48-bit
+        id.len() == 26
+            && matches!(id.as_bytes()[0], b'0'..=b'7')
+            && id.bytes().all(|c| crockford_digit(c).is_some())
+    }
+
+    fn timestamp(&self, id: &str) -> Option<i64> {
+        id.get(..10)?
+            .bytes()
+            .try_fold(0u64, |time, c| Some(time << 5 | crockford_digit(c)?))
+            .map(|time| time as i64)
+    }
+
+    fn min_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(ulid_time(datetime), '0', 26)
+    }
+
+    fn max_id(&self, datetime: DateTime<Utc>) -> String {
+        pad(ulid_time(datetime), 'Z', 26)
+    }
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{Aid, Aidx, Cuid, IdScheme, Meid, ObjectId, Ulid};
+    use chrono::{DateTime, Duration};
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    fn check_scheme(scheme: &dyn IdScheme, length: usize, precision_ms: i64) {
+        let now = DateTime::from_timestamp_millis(1716735338211).unwrap();
+
+        let id = scheme.generate(now);
+        assert_eq!(id.len(), length);
+        assert!(scheme.matches(&id), "{}", id);
+        assert_eq!(
+            scheme.timestamp(&id).unwrap(),
+            now.timestamp_millis() / precision_ms * precision_ms
+        );
+        assert_ne!(scheme.generate(now), scheme.generate(now));
+
+        let later = now + Duration::milliseconds(precision_ms);
+        assert!(scheme.generate(now) < scheme.generate(later));
+
+        let (min_id, max_id) = (scheme.min_id(now), scheme.max_id(now));
+        assert!(scheme.matches(&min_id), "{}", min_id);
+        assert!(scheme.matches(&max_id), "{}", max_id);
+        assert_eq!(scheme.timestamp(&min_id), scheme.timestamp(&id));
+        assert_eq!(scheme.timestamp(&max_id), scheme.timestamp(&id));
+        assert!(min_id <= id && id <= max_id);
+        assert!(scheme.max_id(now - Duration::milliseconds(precision_ms)) < id);
+        assert!(id < scheme.min_id(later));
+    }
+
+    #[test]
+    fn generate_and_decode() {
+        check_scheme(&Aid, 10, 1);
+        check_scheme(&Aidx, 16, 1);
+        check_scheme(&Meid, 24, 1);
+        check_scheme(&ObjectId, 24, 1000);
+        check_scheme(&Ulid, 26, 1);
+    }
+
+    #[test]
+    fn cuid_bounds() {
+        // Cuid::generate requires the server config
+        let now = DateTime::from_timestamp_millis(1716735338211).unwrap();
+        let (min_id, max_id) = (Cuid.min_id(now), Cuid.max_id(now));
+
+        assert_eq!(min_id, "9tr87i0300000000");
+        assert_eq!(max_id, "9tr87i03zzzzzzzzzzzzzzzz");
+        for id in [
+            "9tr87i03euwphnsw",
+            "9tr87i03a0000000",
+            "9tr87i03zzzzzzzzzzzzzzzz",
+        ] {
+            assert!(min_id.as_str() <= id && id <= max_id.as_str());
+        }
+        assert!(Cuid.max_id(now - Duration::milliseconds(1)).as_str() < "9tr87i03a0000000");
+    }
+
+    #[test]
+    fn known_ids() {
+        assert_eq!(
+            Meid.timestamp("818fb56666e3a1b2c3d4e5f6"),
+            Some(1716735338211)
+        );
+        assert_eq!(
+            ObjectId.timestamp("66534d6a0123456789abcdef"),
+            Some(1716735338000)
+        );
+        assert_eq!(
+            Ulid.timestamp("01HYTPCSQ3ABCDEFGHJKMNPQRS"),
+            Some(1716735338211)
+        );
+        assert_eq!(
+            Ulid.timestamp("01hytpcsq3abcdefghjkmnpqrs"),
+            Some(1716735338211)
+        );
+        assert_eq!(Aid.timestamp("9e112pilk1"), Some(1682499501741));
+    }
+}
@@ -0,0 +1,239 @@
+//! Prometheus metrics
+
+use once_cell::sync::Lazy;
+use prometheus::{
+    core::Collector, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::time::Duration;
+
+#[error_doc::errors]
+pub enum Error {
+    #[error("failed to encode metrics")]
+    Prometheus(#[from] prometheus::Error),
+}
+
+static REGISTRY: Lazy<Registry> = Lazy::new(|| {
+    Registry::new_custom(Some("firefish".to_owned()), None).expect("invalid metric prefix")
+});
+
+fn register<T: Collector + Clone + 'static>(collector: T) -> T {
+    if let Err(err) = REGISTRY.register(Box::new(collector.clone())) {
+        tracing::warn!("failed to register a metric: {}", err);
+    }
+    collector
+}
+
+fn counter(name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    register(IntCounterVec::new(Opts::new(name, help), labels).expect("invalid metric"))
+}
+
+fn gauge(name: &str, help: &str, labels: &[&str]) -> IntGaugeVec {
+    register(IntGaugeVec::new(Opts::new(name, help), labels).expect("invalid metric"))
+}
+
+fn histogram(name: &str, help: &str, labels: &[&str]) -> HistogramVec {
+    register(HistogramVec::new(HistogramOpts::new(name, help), labels).expect("invalid metric"))
+}
+
+static HTTP_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    counter(
+        "http_requests_total",
+        "Number of HTTP requests handled by the server",
+        &["method", "status"],
+    )
+});
+static HTTP_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    histogram(
+        "http_request_duration_seconds",
+        "Time taken to handle HTTP requests",
+        &["method"],
+    )
+});
+static HTTP_CLIENT_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    counter(
+        "http_client_requests_total",
+        "Number of outgoing HTTP requests",
+        &["method", "status"],
+    )
+});
+static HTTP_CLIENT_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    histogram(
+        "http_client_request_duration_seconds",
+        "Time taken to receive responses to outgoing HTTP requests",
+        &["method"],
+    )
+});
+static DB_QUERY_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    histogram(
+        "db_query_duration_seconds",
+        "Time taken to execute PostgreSQL queries",
+        &["failed"],
+    )
+});
+static REDIS_COMMAND_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    histogram(
+        "redis_command_duration_seconds",
+        "Time taken to execute Redis commands",
+        &["command"],
+    )
+});
+static CACHE_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    counter(
+        "cache_requests_total",
+        "Number of cache lookups",
+        &["cache", "result"],
+    )
+});
+static QUEUE_JOBS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    gauge(
+        "queue_jobs",
+        "Number of jobs in the job queues",
+        &["queue", "state"],
+    )
+});
+static PUSH_NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    counter(
+        "push_notifications_total",
+        "Number of web push notifications",
+        &["result"],
+    )
+});
+static ANTENNA_EVALUATION_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    register(
+        Histogram::with_opts(HistogramOpts::new(
+            "antenna_evaluation_duration_seconds",
+            "Time taken to check a new note against all antennas",
+        ))
+        .expect("invalid metric"),
+    )
+});
+static ANTENNA_HITS: Lazy<IntCounter> = Lazy::new(|| {
+    register(
+        IntCounter::new("antenna_hits_total", "Number of notes added to antennas")
+            .expect("invalid metric"),
+    )
+});
+static STREAM_MESSAGES_DROPPED: Lazy<IntCounter> = Lazy::new(|| {
+    register(
+        IntCounter::new(
+            "stream_messages_dropped_total",
+            "Number of stream messages dropped for listeners that have fallen behind",
+        )
+        .expect("invalid metric"),
+    )
+});
+
+/// Returns the method as is if it is a standard HTTP method, or `OTHER` otherwise,
+/// so that arbitrary methods sent by clients don't create new time series.
+fn normalize_method(method: &str) -> &'static str {
+    const METHODS: [&str; 9] = [
+        "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH",
+    ];
+
+    METHODS
+        .into_iter()
+        .find(|known| known.eq_ignore_ascii_case(method))
+        .unwrap_or("OTHER")
+}
+
+/// Records an HTTP request handled by the server.
+#[macros::export]
+pub fn record_http_request(method: &str, status: u32, duration_ms: f64) {
+    let method = normalize_method(method);
+    HTTP_REQUESTS
+        .with_label_values(&[method, &status.to_string()])
+        .inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[method])
+        .observe(duration_ms / 1000.0);
+}
+
+/// Records the number of jobs in the `state` (e.g., `waiting`, `active`) of the job queue.
+#[macros::export]
+pub fn set_queue_jobs(queue: &str, state: &str, count: u32) {
+    QUEUE_JOBS
+        .with_label_values(&[queue, state])
+        .set(count.into());
+}
+
+/// Returns the metrics in the Prometheus text format.
+#[macros::export]
+pub fn render_metrics() -> Result<String, Error> {
+    Ok(TextEncoder::new().encode_to_string(&REGISTRY.gather())?)
+}
+
+/// Records an outgoing HTTP request (`status` is `error` if no response was received).
+pub(crate) fn observe_http_client_request(method: &str, status: &str, elapsed: Duration) {
+    HTTP_CLIENT_REQUESTS
+        .with_label_values(&[method, status])
+        .inc();
+    HTTP_CLIENT_REQUEST_DURATION
+        .with_label_values(&[method])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Records a PostgreSQL query.
+pub(crate) fn observe_db_query(failed: bool, elapsed: Duration) {
+    DB_QUERY_DURATION
+        .with_label_values(&[if failed { "true" } else { "false" }])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Records a Redis command.
+pub(crate) fn observe_redis_command(command: &str, elapsed: Duration) {
+    REDIS_COMMAND_DURATION
+        .with_label_values(&[command])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Records a cache lookup.
+pub(crate) fn count_cache_request(cache: &str, hit: bool) {
+    CACHE_REQUESTS
+        .with_label_values(&[cache, if hit { "hit" } else { "miss" }])
+        .inc();
+}
+
+/// Records the result (`sent`, `failed`, or `unsubscribed`) of a web push notification.
+pub(crate) fn count_push_notification(result: &str) {
+    PUSH_NOTIFICATIONS.with_label_values(&[result]).inc();
+}
+
+/// Records the time taken to check a new note against all antennas and the number of hits.
+pub(crate) fn observe_antenna_evaluation(hits: usize, elapsed: Duration) {
+    ANTENNA_EVALUATION_DURATION.observe(elapsed.as_secs_f64());
+    ANTENNA_HITS.inc_by(hits as u64);
+}
+
+/// Records stream messages that a listener has missed.
+pub(crate) fn count_dropped_stream_messages(count: u64) {
+    STREAM_MESSAGES_DROPPED.inc_by(count);
+}
+
+#[cfg(test)]
+mod unit_test {
+    use super::{count_cache_request, record_http_request, render_metrics, set_queue_jobs};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn render() {
+        record_http_request("GET", 200, 12.5);
+        set_queue_jobs("deliver", "waiting", 3);
+        count_cache_request("redis", true);
+
+        let metrics = render_metrics().unwrap();
+
+        assert!(metrics.contains(r#"firefish_http_requests_total{method="GET",status="200"} 1"#));
+        assert!(metrics.contains(r#"firefish_http_request_duration_seconds_count{method="GET"} 1"#));
+        assert!(metrics.contains(r#"firefish_queue_jobs{queue="deliver",state="waiting"} 3"#));
+        assert!(metrics.contains(r#"firefish_cache_requests_total{cache="redis",result="hit"} 1"#));
+    }
+
+    #[test]
+    fn normalize_method() {
+        assert_eq!(super::normalize_method("GET"), "GET");
+        assert_eq!(super::normalize_method("patch"), "PATCH");
+        assert_eq!(super::normalize_method("PROPFIND"), "OTHER");
+        assert_eq!(super::normalize_method("x".repeat(100).as_str()), "OTHER");
+    }
+}
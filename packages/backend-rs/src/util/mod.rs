@@ -3,4 +3,5 @@
 pub mod error_chain;
 pub mod http_client;
 pub mod id;
+pub mod metrics;
 pub mod random;
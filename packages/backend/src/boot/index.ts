@@ -2,16 +2,21 @@ import cluster from "node:cluster";
 import chalk from "chalk";
 import Xev from "xev";
 
-import Logger from "@/services/logger.js";
+import Logger, { updateMaxLogLevel } from "@/services/logger.js";
 import { inspect } from "node:util";
 
 // for typeorm
 import "reflect-metadata";
-import { masterMain } from "./master.js";
+import { masterMain, respawnWorker } from "./master.js";
 import { workerMain } from "./worker.js";
 import os from "node:os";
 
-import { initializeRustLogger } from "backend-rs";
+import { beforeShutdown } from "@/misc/before-shutdown.js";
+import {
+	initializeRustLogger,
+	reloadConfig,
+	shutdownTelemetry,
+} from "backend-rs";
 
 const logger = new Logger("core", "cyan");
 const clusterLogger = logger.createSubLogger("cluster", "orange", false);
@@ -22,6 +27,7 @@ const ev = new Xev();
  */
 export default async function () {
 	initializeRustLogger();
+	beforeShutdown(async () => shutdownTelemetry());
 
 	const mode =
 		process.env.mode && ["web", "queue"].includes(process.env.mode)
@@ -60,6 +66,23 @@ export default async function () {
 
 //#region Events
 
+// Reload the config (the master process forwards the signal to the workers)
+process.on("SIGHUP", () => {
+	logger.info("Reloading the config (received SIGHUP)");
+	try {
+		reloadConfig();
+		updateMaxLogLevel();
+	} catch (e) {
+		logger.error(`Failed to reload the config: ${inspect(e)}`);
+	}
+
+	if (cluster.isPrimary) {
+		for (const worker of Object.values(cluster.workers ?? {})) {
+			worker?.process.kill("SIGHUP");
+		}
+	}
+});
+
 // Listen new workers
 cluster.on("fork", (worker) => {
 	clusterLogger.debug(`Process forked: [${worker.id}]`);
@@ -75,7 +98,7 @@ cluster.on("exit", (worker) => {
 	// Replace the dead worker,
 	// we're not sentimental
 	clusterLogger.error(chalk.red(`[${worker.id}] died :(`));
-	cluster.fork();
+	respawnWorker(worker);
 });
 
 // Display detail of unhandled promise rejection
@@ -1,5 +1,5 @@
 import * as os from "node:os";
-import cluster from "node:cluster";
+import cluster, { type Worker } from "node:cluster";
 import semver from "semver";
 
 import Logger from "@/services/logger.js";
@@ -7,6 +7,7 @@ import {
 	greet,
 	removeOldAttestationChallenges,
 	showServerInfo,
+	takeAntennaDigests,
 	type Config,
 } from "backend-rs";
 import { config } from "@/config.js";
@@ -50,6 +51,21 @@ export async function masterMain() {
 	import("../daemons/queue-stats.js").then((x) => x.default());
 	// Remove old attestation challenges
 	setInterval(() => removeOldAttestationChallenges(), 1000 * 60 * 30);
+	// Send antenna notification digests
+	setInterval(() => sendAntennaDigests(), 1000 * 60 * 60);
+}
+
+async function sendAntennaDigests() {
+	try {
+		const { createAntennaNotification } = await import(
+			"@/services/create-notification.js"
+		);
+		for (const notification of await takeAntennaDigests()) {
+			await createAntennaNotification(notification);
+		}
+	} catch (e) {
+		logger.warn(`Failed to send antenna digests: ${inspect(e)}`);
+	}
 }
 
 function showEnvironment(): void {
@@ -122,13 +138,32 @@ async function spawnWorkers(
 	bootLogger.info(
 		`Starting ${clusterLimits.web} web workers and ${clusterLimits.queue} queue workers (total ${total})...`,
 	);
-	await Promise.all(workers.map((mode) => spawnWorker(mode)));
+	await Promise.all(workers.map((mode, index) => spawnWorker(mode, index)));
 	bootLogger.info("All workers started");
 }
 
-function spawnWorker(mode: "web" | "queue"): Promise<void> {
+/** Environment variables of the worker processes (keyed by worker ID) */
+const workerEnvs = new Map<number, Record<string, string>>();
+
+function forkWorker(env: Record<string, string>): Worker {
+	const worker = cluster.fork(env);
+	workerEnvs.set(worker.id, env);
+	return worker;
+}
+
+/**
+ * Replaces the dead worker with a new one in the same mode
+ * (and with the same index, which determines the metrics port)
+ */
+export function respawnWorker(worker: Worker): Worker {
+	const env = workerEnvs.get(worker.id) ?? {};
+	workerEnvs.delete(worker.id);
+	return forkWorker(env);
+}
+
+function spawnWorker(mode: "web" | "queue", index: number): Promise<void> {
 	return new Promise((res) => {
-		const worker = cluster.fork({ mode });
+		const worker = forkWorker({ mode, workerIndex: index.toString() });
 		worker.on("message", (message) => {
 			if (message === "listenFailed") {
 				bootLogger.error("The server listen failed due to the previous error.");
@@ -1,6 +1,7 @@
 import cluster from "node:cluster";
 import { initDb } from "@/db/postgre.js";
 import os from "node:os";
+import { config } from "@/config.js";
 
 /**
  * Init worker process
@@ -23,6 +24,11 @@ export async function workerMain() {
 		}
 	}
 
+	const metricsPort = config.telemetry?.metricsPort;
+	if (metricsPort != null) {
+		import("../server/metrics.js").then((x) => x.default(metricsPort));
+	}
+
 	if (cluster.isWorker) {
 		// Send a 'ready' message to parent process
 		process.send?.("ready");
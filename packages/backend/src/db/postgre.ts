@@ -2,7 +2,7 @@
 import pg from "pg";
 pg.types.setTypeParser(20, Number);
 
-import type { Logger } from "typeorm";
+import type { Logger, ObjectLiteral, SelectQueryBuilder } from "typeorm";
 import { DataSource } from "typeorm";
 import * as highlight from "cli-highlight";
 import { config } from "@/config.js";
@@ -194,6 +194,28 @@ export const db = new DataSource({
 	username: config.db.user,
 	password: config.db.pass,
 	database: config.db.db,
+	// SELECT queries are sent to replicas only if explicitly requested
+	// (see getManyFromReplica)
+	replication:
+		config.db.replicas != null && config.db.replicas.length > 0
+			? {
+					defaultMode: "master",
+					master: {
+						host: config.db.host,
+						port: config.db.port,
+						username: config.db.user,
+						password: config.db.pass,
+						database: config.db.db,
+					},
+					slaves: config.db.replicas.map((replica) => ({
+						host: replica.host,
+						port: replica.port,
+						username: replica.user ?? config.db.user,
+						password: replica.pass ?? config.db.pass,
+						database: replica.db ?? config.db.db,
+					})),
+				}
+			: undefined,
 	extra: {
 		statement_timeout: 1000 * 30,
 		...config.db.extra,
@@ -222,6 +244,37 @@ export const db = new DataSource({
 	migrations: ["../../migration/*.js"],
 });
 
+/**
+ * Runs the SELECT query on a read-only replica (if any) and returns the entities.
+ *
+ * This falls back to the primary server if the replica is unavailable.
+ * Note that replicas may lag behind the primary server, so don't use this
+ * to read rows that have just been written.
+ */
+export async function getManyFromReplica<Entity extends ObjectLiteral>(
+	query: SelectQueryBuilder<Entity>,
+): Promise<Entity[]> {
+	if (config.db.replicas == null || config.db.replicas.length === 0) {
+		return await query.getMany();
+	}
+
+	const replica = db.createQueryRunner("slave");
+	try {
+		return await query.clone().setQueryRunner(replica).getMany();
+	} catch (e) {
+		dbLogger.warn(`Replica is unavailable, using the primary server: ${e}`);
+	} finally {
+		await replica.release();
+	}
+
+	const primary = db.createQueryRunner("master");
+	try {
+		return await query.clone().setQueryRunner(primary).getMany();
+	} finally {
+		await primary.release();
+	}
+}
+
 export async function initDb(force = false) {
 	if (force) {
 		if (db.isInitialized) {
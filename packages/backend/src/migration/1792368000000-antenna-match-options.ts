@@ -0,0 +1,21 @@
+import type { MigrationInterface, QueryRunner } from "typeorm";
+
+export class AntennaMatchOptions1792368000000 implements MigrationInterface {
+	public async up(queryRunner: QueryRunner): Promise<void> {
+		await queryRunner.query(
+			`ALTER TABLE "antenna" ADD "wholeWord" boolean NOT NULL DEFAULT false`,
+		);
+		await queryRunner.query(
+			`ALTER TABLE "antenna" ADD "langs" character varying(32)[] NOT NULL DEFAULT '{}'`,
+		);
+		await queryRunner.query(
+			`ALTER TABLE "antenna" ADD "excludeLangs" character varying(32)[] NOT NULL DEFAULT '{}'`,
+		);
+	}
+
+	public async down(queryRunner: QueryRunner): Promise<void> {
+		await queryRunner.query(`ALTER TABLE "antenna" DROP COLUMN "excludeLangs"`);
+		await queryRunner.query(`ALTER TABLE "antenna" DROP COLUMN "langs"`);
+		await queryRunner.query(`ALTER TABLE "antenna" DROP COLUMN "wholeWord"`);
+	}
+}
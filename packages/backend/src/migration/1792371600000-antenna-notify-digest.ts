@@ -0,0 +1,13 @@
+import type { MigrationInterface, QueryRunner } from "typeorm";
+
+export class AntennaNotifyDigest1792371600000 implements MigrationInterface {
+	public async up(queryRunner: QueryRunner): Promise<void> {
+		await queryRunner.query(
+			`ALTER TABLE "antenna" ADD "notifyDigest" boolean NOT NULL DEFAULT false`,
+		);
+	}
+
+	public async down(queryRunner: QueryRunner): Promise<void> {
+		await queryRunner.query(`ALTER TABLE "antenna" DROP COLUMN "notifyDigest"`);
+	}
+}
@@ -0,0 +1,15 @@
+import type { MigrationInterface, QueryRunner } from "typeorm";
+
+export class NotificationNoteCount1792375200000 implements MigrationInterface {
+	public async up(queryRunner: QueryRunner): Promise<void> {
+		await queryRunner.query(
+			`ALTER TABLE "notification" ADD "noteCount" integer`,
+		);
+	}
+
+	public async down(queryRunner: QueryRunner): Promise<void> {
+		await queryRunner.query(
+			`ALTER TABLE "notification" DROP COLUMN "noteCount"`,
+		);
+	}
+}
@@ -5,7 +5,7 @@ import CacheableLookup from "cacheable-lookup";
 import fetch, { type RequestRedirect } from "node-fetch";
 import { HttpProxyAgent, HttpsProxyAgent } from "hpagent";
 import { config } from "@/config.js";
-import { isSafeUrl } from "backend-rs";
+import { getRuntimeConfig, isSafeUrl } from "backend-rs";
 
 export async function getJson(
 	url: string,
@@ -163,7 +163,7 @@ export const httpsAgent = config.proxy
  * @param bypassProxy Allways bypass proxy
  */
 export function getAgentByUrl(url: URL, bypassProxy = false) {
-	if (bypassProxy || (config.proxyBypassHosts || []).includes(url.hostname)) {
+	if (bypassProxy || (getRuntimeConfig().proxyBypassHosts || []).includes(url.hostname)) {
 		return url.protocol === "http:" ? _http : _https;
 	} else {
 		return url.protocol === "http:" ? httpAgent : httpsAgent;
@@ -176,7 +176,7 @@ export function getAgentByUrl(url: URL, bypassProxy = false) {
  * @param bypassProxy Allways bypass proxy
  */
 export function getAgentByHostname(hostname: string, bypassProxy = false) {
-	if (bypassProxy || (config.proxyBypassHosts || []).includes(hostname)) {
+	if (bypassProxy || (getRuntimeConfig().proxyBypassHosts || []).includes(hostname)) {
 		return {
 			http: _http,
 			https: _https,
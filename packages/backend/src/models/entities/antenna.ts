@@ -71,6 +71,7 @@ export class Antenna {
 	// e.g., ["alpha beta", "gamma"]
 	//   does match     "alpha beta", "beta alpha alpha", "gamma alpha", "gamma epsilon"
 	//   does not match "alpha", "beta gamma", "alpha alpha", "eplison"
+	// an item in the form of "/pattern/flags" is a JavaScript-style regular expression
 	@Column("text", {
 		array: true,
 		default: "{}",
@@ -97,6 +98,25 @@ export class Antenna {
 	@Column("boolean")
 	public withFile: boolean;
 
+	@Column("boolean", {
+		default: false,
+	})
+	public wholeWord: boolean;
+
+	@Column("varchar", {
+		length: 32,
+		array: true,
+		default: "{}",
+	})
+	public langs: string[];
+
+	@Column("varchar", {
+		length: 32,
+		array: true,
+		default: "{}",
+	})
+	public excludeLangs: string[];
+
 	@Column("varchar", {
 		length: 2048,
 		nullable: true,
@@ -106,6 +126,12 @@ export class Antenna {
 	@Column("boolean")
 	public notify: boolean;
 
+	// notify once an hour about all notes added in the hour instead of each note
+	@Column("boolean", {
+		default: false,
+	})
+	public notifyDigest: boolean;
+
 	//#region Relations
 	@ManyToOne(() => User, {
 		onDelete: "CASCADE",
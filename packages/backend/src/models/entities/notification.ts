@@ -136,6 +136,14 @@ export class Notification {
 	})
 	public customIcon: string | null;
 
+	/**
+	 * Number of notes in the app notification (e.g., an antenna digest)
+	 */
+	@Column("integer", {
+		nullable: true,
+	})
+	public noteCount: number | null;
+
 	/**
 	 * App notification app (token for)
 	 */
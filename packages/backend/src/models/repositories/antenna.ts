@@ -2,6 +2,7 @@ import { db } from "@/db/postgre.js";
 import { Antenna } from "@/models/entities/antenna.js";
 import type { Packed } from "@/misc/schema.js";
 import { UserGroupJoinings } from "@/models/index.js";
+import { hasUnreadAntennaNotes } from "backend-rs";
 
 export const AntennaRepository = db.getRepository(Antenna).extend({
 	async pack(src: Antenna["id"] | Antenna): Promise<Packed<"Antenna">> {
@@ -25,11 +26,16 @@ export const AntennaRepository = db.getRepository(Antenna).extend({
 			userGroupId: userGroupJoining ? userGroupJoining.userGroupId : null,
 			users: antenna.users,
 			instances: antenna.instances,
+			expression: antenna.expression,
 			caseSensitive: antenna.caseSensitive,
+			wholeWord: antenna.wholeWord,
+			langs: antenna.langs,
+			excludeLangs: antenna.excludeLangs,
 			notify: antenna.notify,
+			notifyDigest: antenna.notifyDigest,
 			withReplies: antenna.withReplies,
 			withFile: antenna.withFile,
-			hasUnreadNote: false,
+			hasUnreadNote: await hasUnreadAntennaNotes(antenna.id).catch(() => false),
 		};
 	},
 });
@@ -139,6 +139,20 @@ export const NotificationRepository = db.getRepository(Notification).extend({
 						body: notification.customBody,
 						header: notification.customHeader || token?.name,
 						icon: notification.customIcon || token?.iconUrl,
+						noteCount: notification.noteCount,
+						// e.g., the note added to an antenna
+						...(notification.noteId != null
+							? {
+									note: Notes.pack(
+										notification.note || notification.noteId,
+										{ id: notification.notifieeId },
+										{
+											detail: true,
+											_hint_: options._hintForEachNotes_,
+										},
+									),
+								}
+							: {}),
 					}
 				: {}),
 		});
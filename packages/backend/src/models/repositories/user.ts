@@ -14,6 +14,7 @@ import { isActor, getApId } from "@/remote/activitypub/type.js";
 import DbResolver from "@/remote/activitypub/db-resolver.js";
 import Resolver from "@/remote/activitypub/resolver.js";
 import { createPerson } from "@/remote/activitypub/models/person.js";
+import { hasUnreadAntenna } from "backend-rs";
 import {
 	AnnouncementReads,
 	Announcements,
@@ -264,24 +265,11 @@ export const UserRepository = db.getRepository(User).extend({
 	},
 
 	async getHasUnreadAntenna(userId: User["id"]): Promise<boolean> {
-		// try {
-		// 	const myAntennas = (await getAntennas()).filter(
-		// 		(a) => a.userId === userId,
-		// 	);
-
-		// 	const unread =
-		// 		myAntennas.length > 0
-		// 			? await AntennaNotes.findOneBy({
-		// 					antennaId: In(myAntennas.map((x) => x.id)),
-		// 					read: false,
-		// 			  })
-		// 			: null;
-
-		// 	return unread != null;
-		// } catch (e) {
-		// 	return false;
-		// }
-		return false; // TODO
+		try {
+			return await hasUnreadAntenna(userId);
+		} catch (e) {
+			return false;
+		}
 	},
 
 	async getHasUnreadChannel(userId: User["id"]): Promise<boolean> {
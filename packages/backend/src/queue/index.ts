@@ -485,6 +485,22 @@ export function createScheduledNoteJob(
 	});
 }
 
+export function createBackfillAntennaJob(antennaId: string, hours: number) {
+	return dbQueue.add(
+		"backfillAntenna",
+		{
+			antennaId,
+			hours,
+		},
+		{
+			// one job per antenna
+			jobId: `backfillAntenna:${antennaId}`,
+			removeOnComplete: true,
+			removeOnFail: true,
+		},
+	);
+}
+
 export function createDeleteObjectStorageFileJob(key: string) {
 	return objectStorageQueue.add(
 		"deleteFile",
@@ -0,0 +1,17 @@
+import type Bull from "bull";
+import { backfillAntenna as backfill } from "backend-rs";
+import type { DbBackfillAntennaJobData } from "@/queue/types.js";
+import { queueLogger } from "../../logger.js";
+
+const logger = queueLogger.createSubLogger("backfill-antenna");
+
+export async function backfillAntenna(
+	job: Bull.Job<DbBackfillAntennaJobData>,
+): Promise<string> {
+	const { antennaId, hours } = job.data;
+	logger.info(`Backfilling ${hours} hours of antenna ${antennaId}...`);
+
+	const count = await backfill(antennaId, hours);
+
+	return `Added ${count} notes to antenna ${antennaId}`;
+}
@@ -6,7 +6,13 @@ import { downloadTextFile } from "@/misc/download-text-file.js";
 import { Users, DriveFiles, Mutings } from "@/models/index.js";
 import type { DbUserImportJobData } from "@/queue/types.js";
 import type { User } from "@/models/entities/user.js";
-import { genIdAt, isSelfHost, stringToAcct, toPuny } from "backend-rs";
+import {
+	genIdAt,
+	invalidateMuteCache,
+	isSelfHost,
+	stringToAcct,
+	toPuny,
+} from "backend-rs";
 import { IsNull } from "typeorm";
 import { inspect } from "node:util";
 
@@ -88,4 +94,5 @@ async function mute(user: User, target: User) {
 		muterId: user.id,
 		muteeId: target.id,
 	});
+	await invalidateMuteCache(user.id);
 }
@@ -18,6 +18,7 @@ import { importCkPost } from "./import-firefish-post.js";
 import { importBlocking } from "./import-blocking.js";
 import { importCustomEmojis } from "./import-custom-emojis.js";
 import { scheduledNote } from "./scheduled-note.js";
+import { backfillAntenna } from "./backfill-antenna.js";
 
 const jobs = {
 	deleteDriveFiles,
@@ -38,6 +39,7 @@ const jobs = {
 	importCustomEmojis,
 	deleteAccount,
 	scheduledNote,
+	backfillAntenna,
 } as Record<
 	string,
 	| Bull.ProcessCallbackFunction<DbJobData>
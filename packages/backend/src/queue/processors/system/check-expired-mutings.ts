@@ -2,7 +2,11 @@ import type Bull from "bull";
 import { In } from "typeorm";
 import { Mutings } from "@/models/index.js";
 import { queueLogger } from "../../logger.js";
-import { publishToUserStream, UserEvent } from "backend-rs";
+import {
+	invalidateMuteCache,
+	publishToUserStream,
+	UserEvent,
+} from "backend-rs";
 
 const logger = queueLogger.createSubLogger("check-expired-mutings");
 
@@ -23,6 +27,12 @@ export async function checkExpiredMutings(
 			id: In(expired.map((m) => m.id)),
 		});
 
+		await Promise.all(
+			[...new Set(expired.map((m) => m.muterId))].map((muterId) =>
+				invalidateMuteCache(muterId),
+			),
+		);
+
 		await Promise.all(
 			expired.map((m) =>
 				publishToUserStream(m.muterId, UserEvent.Unmute, m.mutee),
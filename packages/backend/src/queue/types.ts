@@ -26,7 +26,8 @@ export type DbJobData =
 	| DbUserImportJobData
 	| DbUserDeleteJobData
 	| DbUserImportMastoPostJobData
-	| DbUserScheduledNoteData;
+	| DbUserScheduledNoteData
+	| DbBackfillAntennaJobData;
 
 export type DbUserJobData = {
 	user: ThinUser;
@@ -57,6 +58,11 @@ export type DbUserImportMastoPostJobData = {
 	parent: Note | null;
 };
 
+export type DbBackfillAntennaJobData = {
+	antennaId: string;
+	hours: number;
+};
+
 export type DbUserScheduledNoteData = {
 	user: ThinUser;
 	option: {
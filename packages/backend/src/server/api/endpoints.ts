@@ -292,6 +292,7 @@ import * as ep___requestResetPassword from "./endpoints/request-reset-password.j
 import * as ep___resetPassword from "./endpoints/reset-password.js";
 import * as ep___serverInfo from "./endpoints/server-info.js";
 import * as ep___stats from "./endpoints/stats.js";
+import * as ep___streaming_replay from "./endpoints/streaming/replay.js";
 import * as ep___sw_show_registration from "./endpoints/sw/show-registration.js";
 import * as ep___sw_update_registration from "./endpoints/sw/update-registration.js";
 import * as ep___sw_register from "./endpoints/sw/register.js";
@@ -641,6 +642,7 @@ const eps = [
 	["reset-password", ep___resetPassword],
 	["server-info", ep___serverInfo],
 	["stats", ep___stats],
+	["streaming/replay", ep___streaming_replay],
 	["sw/register", ep___sw_register],
 	["sw/unregister", ep___sw_unregister],
 	["sw/show-registration", ep___sw_show_registration],
@@ -4,10 +4,13 @@ import {
 	genIdAt,
 	InternalEvent,
 	publishToInternalStream,
+	reserveAntennaBackfill,
 	updateAntennaCache,
+	validateAntennaExpression,
 } from "backend-rs";
 import { Antennas, UserLists, UserGroupJoinings } from "@/models/index.js";
 import { ApiError } from "@/server/api/error.js";
+import { createBackfillAntennaJob } from "@/queue/index.js";
 
 export const meta = {
 	tags: ["antennas"],
@@ -39,6 +42,16 @@ export const meta = {
 			code: "NO_KEYWORDS",
 			id: "aa975b74-1ddb-11ee-be56-0242ac120002",
 		},
+		invalidExpression: {
+			message: "Invalid expression.",
+			code: "INVALID_EXPRESSION",
+			id: "5f6b8b0e-6a4d-4b8a-9c1e-3d2f7a9e4c51",
+		},
+		tooManyBackfills: {
+			message: "Too many backfills. Try again later.",
+			code: "TOO_MANY_BACKFILLS",
+			id: "414ffeed-643f-4cce-8d91-b834982e9d7a",
+		},
 	},
 
 	res: {
@@ -89,10 +102,24 @@ export const paramDef = {
 				type: "string",
 			},
 		},
+		expression: { type: "string", nullable: true, maxLength: 2048 },
 		caseSensitive: { type: "boolean" },
+		wholeWord: { type: "boolean", default: false },
+		langs: {
+			type: "array",
+			items: { type: "string", maxLength: 32 },
+			default: [],
+		},
+		excludeLangs: {
+			type: "array",
+			items: { type: "string", maxLength: 32 },
+			default: [],
+		},
 		withReplies: { type: "boolean" },
 		withFile: { type: "boolean" },
 		notify: { type: "boolean" },
+		notifyDigest: { type: "boolean", default: false },
+		backfillHours: { type: "integer", minimum: 0, maximum: 24, default: 0 },
 	},
 	required: [
 		"name",
@@ -122,9 +149,24 @@ export default define(meta, paramDef, async (ps, user) => {
 			row.filter((word) => word.trim().length > 0),
 		),
 	);
+	const expression =
+		ps.expression != null && ps.expression.trim().length > 0
+			? ps.expression
+			: null;
+
+	if (expression != null) {
+		try {
+			validateAntennaExpression(expression);
+		} catch (err) {
+			throw new ApiError(meta.errors.invalidExpression, {
+				reason: err instanceof Error ? err.message : String(err),
+			});
+		}
+	}
 
 	if (user.movedToUri != null) throw new ApiError(meta.errors.noSuchUserGroup);
-	if (keywords.length === 0) throw new ApiError(meta.errors.noKeywords);
+	if (keywords.length === 0 && expression == null)
+		throw new ApiError(meta.errors.noKeywords);
 	let userList;
 	let userGroupJoining;
 
@@ -157,6 +199,10 @@ export default define(meta, paramDef, async (ps, user) => {
 		}
 	}
 
+	if (ps.backfillHours > 0 && !(await reserveAntennaBackfill(user.id))) {
+		throw new ApiError(meta.errors.tooManyBackfills);
+	}
+
 	const now = new Date();
 
 	const antenna = await Antennas.insert({
@@ -169,16 +215,25 @@ export default define(meta, paramDef, async (ps, user) => {
 		userGroupJoiningId: userGroupJoining ? userGroupJoining.id : null,
 		keywords: keywords,
 		excludeKeywords: excludedWords,
+		expression: expression,
 		users: ps.users,
 		instances: ps.instances.filter((instance) => instance.trim().length > 0),
 		caseSensitive: ps.caseSensitive,
+		wholeWord: ps.wholeWord,
+		langs: ps.langs?.filter((lang) => lang.trim().length > 0),
+		excludeLangs: ps.excludeLangs?.filter((lang) => lang.trim().length > 0),
 		withReplies: ps.withReplies,
 		withFile: ps.withFile,
 		notify: ps.notify,
+		notifyDigest: ps.notifyDigest,
 	}).then((x) => Antennas.findOneByOrFail(x.identifiers[0]));
 
 	await publishToInternalStream(InternalEvent.AntennaCreated, antenna);
 	await updateAntennaCache();
 
+	if (ps.backfillHours > 0) {
+		createBackfillAntennaJob(antenna.id, ps.backfillHours);
+	}
+
 	return await Antennas.pack(antenna);
 });
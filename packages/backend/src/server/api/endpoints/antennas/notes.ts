@@ -1,8 +1,7 @@
 import define from "@/server/api/define.js";
 import readNote from "@/services/note/read.js";
 import { Antennas, Notes } from "@/models/index.js";
-import { redisClient } from "@/db/redis.js";
-import { getTimestamp } from "backend-rs";
+import { getAntennaNoteIds, markAntennaRead, maxIdAt, minIdAt } from "backend-rs";
 import { makePaginationQuery } from "@/server/api/common/make-pagination-query.js";
 import { generateVisibilityQuery } from "@/server/api/common/generate-visibility-query.js";
 import { generateMutedUserQuery } from "@/server/api/common/generate-muted-user-query.js";
@@ -60,35 +59,26 @@ export default define(meta, paramDef, async (ps, user) => {
 		throw new ApiError(meta.errors.noSuchAntenna);
 	}
 
-	const limit = ps.limit + (ps.untilId ? 1 : 0) + (ps.sinceId ? 1 : 0); // untilIdに指定したものも含まれるため+1
-	let end = "+";
-	if (ps.untilDate) {
-		end = ps.untilDate.toString();
-	} else if (ps.untilId) {
-		end = getTimestamp(ps.untilId).toString();
+	let untilId = ps.untilId;
+	if (untilId == null && ps.untilDate) {
+		untilId = maxIdAt(new Date(ps.untilDate));
 	}
-	let start = "-";
-	if (ps.sinceDate) {
-		start = ps.sinceDate.toString();
-	} else if (ps.sinceId) {
-		start = getTimestamp(ps.sinceId).toString();
+	let sinceId = ps.sinceId;
+	if (sinceId == null && ps.sinceDate) {
+		sinceId = minIdAt(new Date(ps.sinceDate));
 	}
-	const noteIdsRes = await redisClient.xrevrange(
-		`antennaTimeline:${antenna.id}`,
-		end,
-		start,
-		"COUNT",
-		limit,
+	const noteIds = await getAntennaNoteIds(
+		antenna.id,
+		ps.limit,
+		sinceId,
+		untilId,
 	);
 
-	if (noteIdsRes.length === 0) {
-		return [];
+	if (untilId == null) {
+		// the latest notes are being viewed
+		await markAntennaRead(antenna.id, null);
 	}
 
-	const noteIds = noteIdsRes
-		.map((x) => x[1][1])
-		.filter((x) => x !== ps.untilId && x !== ps.sinceId);
-
 	if (noteIds.length === 0) {
 		return [];
 	}
@@ -117,7 +107,7 @@ export default define(meta, paramDef, async (ps, user) => {
 	generateMutedUserQuery(query, user);
 	generateBlockedUserQuery(query, user);
 
-	const notes = await query.take(limit).getMany();
+	const notes = await query.take(ps.limit).getMany();
 
 	if (notes.length > 0) {
 		readNote(user.id, notes);
@@ -1,10 +1,13 @@
 import define from "@/server/api/define.js";
 import { ApiError } from "@/server/api/error.js";
+import { createBackfillAntennaJob } from "@/queue/index.js";
 import { Antennas, UserLists, UserGroupJoinings } from "@/models/index.js";
 import {
 	InternalEvent,
 	publishToInternalStream,
+	reserveAntennaBackfill,
 	updateAntennaCache,
+	validateAntennaExpression,
 } from "backend-rs";
 
 export const meta = {
@@ -32,6 +35,17 @@ export const meta = {
 			code: "NO_SUCH_USER_GROUP",
 			id: "109ed789-b6eb-456e-b8a9-6059d567d385",
 		},
+
+		invalidExpression: {
+			message: "Invalid expression.",
+			code: "INVALID_EXPRESSION",
+			id: "8e4d2b7a-1c3f-4a5e-9b6d-7f0c2e1a3b48",
+		},
+		tooManyBackfills: {
+			message: "Too many backfills. Try again later.",
+			code: "TOO_MANY_BACKFILLS",
+			id: "5a110c16-906b-480b-a8e3-77d427a856be",
+		},
 	},
 
 	res: {
@@ -83,10 +97,22 @@ export const paramDef = {
 				type: "string",
 			},
 		},
+		expression: { type: "string", nullable: true, maxLength: 2048 },
 		caseSensitive: { type: "boolean" },
+		wholeWord: { type: "boolean" },
+		langs: {
+			type: "array",
+			items: { type: "string", maxLength: 32 },
+		},
+		excludeLangs: {
+			type: "array",
+			items: { type: "string", maxLength: 32 },
+		},
 		withReplies: { type: "boolean" },
 		withFile: { type: "boolean" },
 		notify: { type: "boolean" },
+		notifyDigest: { type: "boolean" },
+		backfillHours: { type: "integer", minimum: 0, maximum: 24, default: 0 },
 	},
 	required: [
 		"antennaId",
@@ -117,6 +143,20 @@ export default define(meta, paramDef, async (ps, user) => {
 			row.filter((word) => word.trim().length > 0),
 		),
 	);
+	const expression =
+		ps.expression != null && ps.expression.trim().length > 0
+			? ps.expression
+			: null;
+
+	if (expression != null) {
+		try {
+			validateAntennaExpression(expression);
+		} catch (err) {
+			throw new ApiError(meta.errors.invalidExpression, {
+				reason: err instanceof Error ? err.message : String(err),
+			});
+		}
+	}
 
 	// Fetch the antenna
 	const antenna = await Antennas.findOneBy({
@@ -151,6 +191,10 @@ export default define(meta, paramDef, async (ps, user) => {
 		}
 	}
 
+	if (ps.backfillHours > 0 && !(await reserveAntennaBackfill(user.id))) {
+		throw new ApiError(meta.errors.tooManyBackfills);
+	}
+
 	await Antennas.update(antenna.id, {
 		name: ps.name,
 		src: ps.src,
@@ -158,12 +202,17 @@ export default define(meta, paramDef, async (ps, user) => {
 		userGroupJoiningId: userGroupJoining ? userGroupJoining.id : null,
 		keywords: keywords,
 		excludeKeywords: excludedWords,
+		expression: ps.expression === undefined ? undefined : expression,
 		users: ps.users,
 		instances: ps.instances.filter((instance) => instance.trim().length > 0),
 		caseSensitive: ps.caseSensitive,
+		wholeWord: ps.wholeWord,
+		langs: ps.langs?.filter((lang) => lang.trim().length > 0),
+		excludeLangs: ps.excludeLangs?.filter((lang) => lang.trim().length > 0),
 		withReplies: ps.withReplies,
 		withFile: ps.withFile,
 		notify: ps.notify,
+		notifyDigest: ps.notifyDigest,
 	});
 
 	await publishToInternalStream(
@@ -172,5 +221,9 @@ export default define(meta, paramDef, async (ps, user) => {
 	);
 	await updateAntennaCache();
 
+	if (ps.backfillHours > 0) {
+		createBackfillAntennaJob(antenna.id, ps.backfillHours);
+	}
+
 	return await Antennas.pack(antenna.id);
 });
@@ -1,9 +1,11 @@
 import * as mfm from "mfm-js";
 import {
 	Event,
+	invalidateMuteCache,
 	publishToMainStream,
 	publishToUserStream,
 	UserEvent,
+	validateMutedPattern,
 } from "backend-rs";
 import acceptAllFollowRequests from "@/services/following/requests/accept-all.js";
 import { publishToFollowers } from "@/services/i/update.js";
@@ -170,13 +172,13 @@ export default define(meta, paramDef, async (ps, _user, token) => {
 	if (ps.bannerId !== undefined) updates.bannerId = ps.bannerId;
 	if (ps.mutedPatterns !== undefined) {
 		for (const item of ps.mutedPatterns) {
-			const regexp = item.match(/^\/(.+)\/(.*)$/);
-			if (!regexp) throw new ApiError(meta.errors.invalidRegexp);
-
 			try {
-				new RegExp(regexp[1], regexp[2]);
+				validateMutedPattern(item);
 			} catch (err) {
-				throw new ApiError(meta.errors.invalidRegexp);
+				throw new ApiError(meta.errors.invalidRegexp, {
+					pattern: item,
+					reason: err instanceof Error ? err.message : String(err),
+				});
 			}
 
 			profileUpdates.mutedPatterns = profileUpdates.mutedPatterns ?? [];
@@ -343,6 +345,12 @@ export default define(meta, paramDef, async (ps, _user, token) => {
 	if (Object.keys(updates).length > 0) await Users.update(user.id, updates);
 	if (Object.keys(profileUpdates).length > 0)
 		await UserProfiles.update(user.id, profileUpdates);
+	if (
+		ps.mutedWords !== undefined ||
+		ps.mutedPatterns !== undefined ||
+		ps.mutedInstances !== undefined
+	)
+		await invalidateMuteCache(user.id);
 
 	const iObj = await Users.pack<true, true>(user.id, user, {
 		detail: true,
@@ -1,7 +1,12 @@
 import define from "@/server/api/define.js";
 import { ApiError } from "@/server/api/error.js";
 import { getUser } from "@/server/api/common/getters.js";
-import { genIdAt, publishToUserStream, UserEvent } from "backend-rs";
+import {
+	genIdAt,
+	invalidateMuteCache,
+	publishToUserStream,
+	UserEvent,
+} from "backend-rs";
 import { Mutings, NoteWatchings } from "@/models/index.js";
 import type { Muting } from "@/models/entities/muting.js";
 
@@ -86,6 +91,7 @@ export default define(meta, paramDef, async (ps, user) => {
 		muterId: muter.id,
 		muteeId: mutee.id,
 	} as Muting);
+	await invalidateMuteCache(muter.id);
 
 	await publishToUserStream(user.id, UserEvent.Mute, mutee);
 
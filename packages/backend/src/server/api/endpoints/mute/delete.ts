@@ -2,7 +2,11 @@ import define from "@/server/api/define.js";
 import { ApiError } from "@/server/api/error.js";
 import { getUser } from "@/server/api/common/getters.js";
 import { Mutings } from "@/models/index.js";
-import { publishToUserStream, UserEvent } from "backend-rs";
+import {
+	invalidateMuteCache,
+	publishToUserStream,
+	UserEvent,
+} from "backend-rs";
 
 export const meta = {
 	tags: ["account"],
@@ -69,6 +73,7 @@ export default define(meta, paramDef, async (ps, user) => {
 	await Mutings.delete({
 		id: muting.id,
 	});
+	await invalidateMuteCache(muter.id);
 
 	await publishToUserStream(user.id, UserEvent.Unmute, mutee);
 });
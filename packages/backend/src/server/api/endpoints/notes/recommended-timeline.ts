@@ -1,6 +1,7 @@
 import { Brackets } from "typeorm";
 import { fetchMeta } from "backend-rs";
 import { Notes } from "@/models/index.js";
+import { getManyFromReplica } from "@/db/postgre.js";
 import { activeUsersChart } from "@/services/chart/index.js";
 import define from "@/server/api/define.js";
 import { ApiError } from "@/server/api/error.js";
@@ -156,7 +157,7 @@ export default define(meta, paramDef, async (ps, user) => {
 	let skip = 0;
 	try {
 		while (found.length < ps.limit) {
-			const notes = await query.take(take).skip(skip).getMany();
+			const notes = await getManyFromReplica(query.take(take).skip(skip));
 			found.push(...(await Notes.packMany(notes, user)));
 			skip += take;
 			if (notes.length < take) break;
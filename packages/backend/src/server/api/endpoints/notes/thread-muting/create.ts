@@ -1,5 +1,5 @@
 import { Notes, NoteThreadMutings } from "@/models/index.js";
-import { genIdAt } from "backend-rs";
+import { genIdAt, invalidateMuteCache } from "backend-rs";
 import readNote from "@/services/note/read.js";
 import define from "@/server/api/define.js";
 import { getNote } from "@/server/api/common/getters.js";
@@ -57,4 +57,5 @@ export default define(meta, paramDef, async (ps, user) => {
 		threadId: note.threadId || note.id,
 		userId: user.id,
 	});
+	await invalidateMuteCache(user.id);
 });
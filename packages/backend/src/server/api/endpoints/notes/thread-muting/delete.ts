@@ -1,3 +1,4 @@
+import { invalidateMuteCache } from "backend-rs";
 import { NoteThreadMutings } from "@/models/index.js";
 import define from "@/server/api/define.js";
 import { getNote } from "@/server/api/common/getters.js";
@@ -38,4 +39,5 @@ export default define(meta, paramDef, async (ps, user) => {
 		threadId: note.threadId || note.id,
 		userId: user.id,
 	});
+	await invalidateMuteCache(user.id);
 });
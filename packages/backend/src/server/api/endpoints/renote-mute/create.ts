@@ -1,4 +1,4 @@
-import { genIdAt } from "backend-rs";
+import { genIdAt, invalidateMuteCache } from "backend-rs";
 import { RenoteMutings } from "@/models/index.js";
 import type { RenoteMuting } from "@/models/entities/renote-muting.js";
 import define from "@/server/api/define.js";
@@ -65,6 +65,7 @@ export default define(meta, paramDef, async (ps, user) => {
 		muterId: muter.id,
 		muteeId: mutee.id,
 	} as RenoteMuting);
+	await invalidateMuteCache(muter.id);
 
 	// await publishToUserStream(user.id, UserEvent.RenoteMute, mutee);
 });
@@ -1,3 +1,4 @@
+import { invalidateMuteCache } from "backend-rs";
 import { RenoteMutings } from "@/models/index.js";
 import define from "@/server/api/define.js";
 import { ApiError } from "@/server/api/error.js";
@@ -58,6 +59,7 @@ export default define(meta, paramDef, async (ps, user) => {
 	await RenoteMutings.delete({
 		id: muting.id,
 	});
+	await invalidateMuteCache(muter.id);
 
 	// await publishToUserStream(user.id, UserEvent.RenoteUnMute, mutee);
 });
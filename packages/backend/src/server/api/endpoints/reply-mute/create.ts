@@ -1,4 +1,4 @@
-import { genIdAt } from "backend-rs";
+import { genIdAt, invalidateMuteCache } from "backend-rs";
 import { ReplyMutings } from "@/models/index.js";
 import type { ReplyMuting } from "@/models/entities/reply-muting.js";
 import define from "@/server/api/define.js";
@@ -65,6 +65,7 @@ export default define(meta, paramDef, async (ps, user) => {
 		muterId: muter.id,
 		muteeId: mutee.id,
 	} as ReplyMuting);
+	await invalidateMuteCache(muter.id);
 
 	// await publishToUserStream(user.id, UserEvent.ReplyMute, mutee);
 });
@@ -0,0 +1,86 @@
+import define from "@/server/api/define.js";
+import { ApiError } from "@/server/api/error.js";
+import { replayStream } from "backend-rs";
+
+export const meta = {
+	tags: ["account"],
+
+	requireCredential: true,
+
+	kind: "read:account",
+
+	description:
+		"Returns the messages published to the stream channel after the given entry, which is sent as `entryId` with the channel messages.",
+
+	errors: {
+		unknownChannel: {
+			message: "Unknown stream channel.",
+			code: "UNKNOWN_CHANNEL",
+			id: "cb591d83-1dda-4a72-b1c7-f7dc5e041228",
+		},
+		forbidden: {
+			message: "You are not allowed to replay this channel.",
+			code: "FORBIDDEN",
+			id: "605158b2-fe6d-4337-9fca-3acaba59483e",
+		},
+		invalidEntryId: {
+			message: "Invalid stream entry ID.",
+			code: "INVALID_ENTRY_ID",
+			id: "b3a338bd-eefb-478b-b2fb-8fabf1ba4489",
+		},
+	},
+
+	res: {
+		type: "object",
+		optional: false,
+		nullable: false,
+		properties: {
+			events: {
+				type: "array",
+				optional: false,
+				nullable: false,
+				items: {
+					type: "object",
+					optional: false,
+					nullable: false,
+					properties: {
+						id: { type: "string", optional: false, nullable: false },
+						message: { type: "object", optional: false, nullable: false },
+					},
+				},
+			},
+			missed: { type: "boolean", optional: false, nullable: false },
+		},
+	},
+} as const;
+
+export const paramDef = {
+	type: "object",
+	properties: {
+		channel: { type: "string" },
+		sinceId: { type: "string" },
+		limit: { type: "integer", minimum: 1, maximum: 100, default: 100 },
+	},
+	required: ["channel", "sinceId"],
+} as const;
+
+// the variant of the error enum is shown as `raw: Forbidden("...")`
+const errorKind = (err: unknown) =>
+	err instanceof Error ? err.message.match(/raw: (\w+)/)?.[1] : undefined;
+
+export default define(meta, paramDef, async (ps, user) => {
+	try {
+		return await replayStream(user.id, ps.channel, ps.sinceId, ps.limit);
+	} catch (err) {
+		switch (errorKind(err)) {
+			case "UnknownChannel":
+				throw new ApiError(meta.errors.unknownChannel);
+			case "Forbidden":
+				throw new ApiError(meta.errors.forbidden);
+			case "InvalidEntryId":
+				throw new ApiError(meta.errors.invalidEntryId);
+			default:
+				throw err;
+		}
+	}
+});
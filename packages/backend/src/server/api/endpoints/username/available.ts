@@ -1,6 +1,6 @@
 import { IsNull } from "typeorm";
 import { Users, UsedUsernames } from "@/models/index.js";
-import { config } from "@/config.js";
+import { getRuntimeConfig } from "backend-rs";
 import define from "@/server/api/define.js";
 
 export const meta = {
@@ -41,7 +41,7 @@ export default define(meta, paramDef, async (ps) => {
 		username: ps.username.toLowerCase(),
 	});
 
-	const reserved = config.reservedUsernames?.includes(
+	const reserved = getRuntimeConfig().reservedUsernames?.includes(
 		ps.username.toLowerCase(),
 	);
 
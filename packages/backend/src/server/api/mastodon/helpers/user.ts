@@ -27,7 +27,12 @@ import deleteFollowing from "@/services/following/delete.js";
 import cancelFollowRequest from "@/services/following/requests/cancel.js";
 import createBlocking from "@/services/blocking/create.js";
 import deleteBlocking from "@/services/blocking/delete.js";
-import { genId, publishToUserStream, UserEvent } from "backend-rs";
+import {
+	genId,
+	invalidateMuteCache,
+	publishToUserStream,
+	UserEvent,
+} from "backend-rs";
 import type { Muting } from "@/models/entities/muting.js";
 import { UserConverter } from "@/server/api/mastodon/converters/user.js";
 import acceptFollowRequest from "@/services/following/requests/accept.js";
@@ -150,6 +155,7 @@ export class UserHelpers {
 				muterId: localUser.id,
 				muteeId: target.id,
 			} as Muting);
+			await invalidateMuteCache(localUser.id);
 
 			await publishToUserStream(localUser.id, UserEvent.Mute, target);
 
@@ -175,6 +181,7 @@ export class UserHelpers {
 			await Mutings.delete({
 				id: muting.id,
 			});
+			await invalidateMuteCache(localUser.id);
 
 			await publishToUserStream(localUser.id, UserEvent.Unmute, target);
 		}
@@ -5,7 +5,12 @@ import { Users, RegistrationTickets, UserPendings } from "@/models/index.js";
 import { signup } from "@/server/api/common/signup.js";
 import { config } from "@/config.js";
 import { sendEmail } from "@/services/send-email.js";
-import { fetchMeta, genIdAt, hashPassword } from "backend-rs";
+import {
+	fetchMeta,
+	genIdAt,
+	getRuntimeConfig,
+	hashPassword,
+} from "backend-rs";
 import { validateEmailForAccount } from "@/services/validate-email-for-account.js";
 
 export default async (ctx: Koa.Context) => {
@@ -42,7 +47,9 @@ export default async (ctx: Koa.Context) => {
 	const invitationCode = body["invitationCode"];
 	const emailAddress = body["emailAddress"];
 
-	if (config.reservedUsernames?.includes(username.toLowerCase())) {
+	if (
+		getRuntimeConfig().reservedUsernames?.includes(username.toLowerCase())
+	) {
 		ctx.status = 400;
 		return;
 	}
@@ -55,7 +55,7 @@ export default abstract class Channel {
 		this.connection = connection;
 	}
 
-	public send(typeOrPayload: any, payload?: any) {
+	public send(typeOrPayload: any, payload?: any, entryId?: string) {
 		const type = payload === undefined ? typeOrPayload.type : typeOrPayload;
 		const body = payload === undefined ? typeOrPayload.body : payload;
 
@@ -63,6 +63,8 @@ export default abstract class Channel {
 			id: this.id,
 			type: type,
 			body: body,
+			// entry ID in the event log, which is omitted if the message is not logged
+			entryId: entryId,
 		});
 	}
 
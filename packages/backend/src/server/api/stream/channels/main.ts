@@ -11,7 +11,7 @@ export default class extends Channel {
 
 	public async init(params: any) {
 		// Subscribe main stream channel
-		this.subscriber.on(`mainStream:${this.user!.id}`, async (data) => {
+		this.subscriber.on(`mainStream:${this.user!.id}`, async (data, entryId) => {
 			switch (data.type) {
 				case "notification": {
 					// Ignore notifications from instances the user has muted
@@ -40,7 +40,7 @@ export default class extends Channel {
 				}
 			}
 
-			this.send(data.type, data.body);
+			this.send(data.type, data.body, entryId);
 		});
 	}
 }
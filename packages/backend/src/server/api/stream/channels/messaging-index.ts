@@ -7,8 +7,11 @@ export default class extends Channel {
 
 	public async init(params: any) {
 		// Subscribe messaging index stream
-		this.subscriber.on(`messagingIndexStream:${this.user!.id}`, (data) => {
-			this.send(data);
-		});
+		this.subscriber.on(
+			`messagingIndexStream:${this.user!.id}`,
+			(data, entryId) => {
+				this.send(data.type, data.body, entryId);
+			},
+		);
 	}
 }
@@ -63,6 +63,7 @@ export default class extends Channel {
 		data:
 			| StreamMessages["messaging"]["payload"]
 			| StreamMessages["groupMessaging"]["payload"],
+		entryId?: string,
 	) {
 		if (data.type === "typing") {
 			const id = data.body;
@@ -72,7 +73,7 @@ export default class extends Channel {
 				this.emitTypers();
 			}
 		} else {
-			this.send(data);
+			this.send(data.type, data.body, entryId);
 		}
 	}
 
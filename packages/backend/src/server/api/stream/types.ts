@@ -273,6 +273,8 @@ type EventEmitterDictionary = {
 		{
 			[y in StreamMessages[x]["name"]]: (
 				e: StreamMessages[x]["payload"],
+				// entry ID in the event log of the channel (durable mode only)
+				entryId?: string,
 			) => void;
 		}
 	>;
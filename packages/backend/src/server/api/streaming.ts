@@ -66,7 +66,9 @@ export const initializeStreamingServer = (server: http.Server) => {
 
 		async function onRedisMessage(_: string, data: string) {
 			const parsed = JSON.parse(data);
-			ev.emit(parsed.channel, parsed.message);
+			// `id` is the entry ID in the event log of the channel if the message was
+			// published in the durable mode, which clients pass to `streaming/replay`
+			ev.emit(parsed.channel, parsed.message, parsed.id);
 		}
 
 		redisClient.on("message", onRedisMessage);
@@ -15,7 +15,12 @@ import { IsNull } from "typeorm";
 import { config } from "@/config.js";
 import Logger from "@/services/logger.js";
 import { Users } from "@/models/index.js";
-import { fetchMeta, genIdenticon, stringToAcct } from "backend-rs";
+import {
+	fetchMeta,
+	genIdenticon,
+	recordHttpRequest,
+	stringToAcct,
+} from "backend-rs";
 import { createTemp } from "@/misc/create-temp.js";
 import activityPub from "./activitypub.js";
 import nodeinfo from "./nodeinfo.js";
@@ -63,6 +68,18 @@ if (config.url.startsWith("https") && !config.disableHsts) {
 	});
 }
 
+// Prometheus metrics (served by server/metrics.ts)
+if (config.telemetry?.metricsPort != null) {
+	app.use(async (ctx, next) => {
+		const start = performance.now();
+		try {
+			await next();
+		} finally {
+			recordHttpRequest(ctx.method, ctx.status, performance.now() - start);
+		}
+	});
+}
+
 app.use(mount("/api", apiServer));
 app.use(mount("/files", fileServer));
 app.use(mount("/proxy", proxyServer));
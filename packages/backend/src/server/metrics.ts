@@ -0,0 +1,54 @@
+import * as http from "node:http";
+import { inspect } from "node:util";
+import { renderMetrics, setQueueJobs } from "backend-rs";
+import { config } from "@/config.js";
+import Logger from "@/services/logger.js";
+import { queues } from "@/queue/queues.js";
+
+const logger = new Logger("metrics", "gray", false);
+
+async function handle(req: http.IncomingMessage, res: http.ServerResponse) {
+	if (req.method !== "GET" || req.url !== "/metrics") {
+		res.writeHead(404).end();
+		return;
+	}
+
+	for (const queue of queues) {
+		const counts = await queue.getJobCounts();
+		for (const [state, count] of Object.entries(counts)) {
+			setQueueJobs(queue.name, state, count);
+		}
+	}
+
+	res
+		.writeHead(200, {
+			"Content-Type": "text/plain; version=0.0.4; charset=utf-8",
+		})
+		.end(renderMetrics());
+}
+
+/**
+ * Serves the Prometheus metrics of this process on a separate port
+ * (`telemetry.metricsPort` + the index of the worker process),
+ * so that the metrics are neither exposed to the public nor mixed up between workers
+ */
+export default function serveMetrics(basePort: number) {
+	const port = basePort + Number(process.env.workerIndex ?? 0);
+	const host = config.telemetry?.metricsBind ?? "127.0.0.1";
+
+	const server = http.createServer((req, res) => {
+		handle(req, res).catch((e) => {
+			logger.error(inspect(e));
+			if (!res.headersSent) res.writeHead(500);
+			res.end();
+		});
+	});
+
+	server.on("error", (e) => {
+		logger.error(`Failed to serve metrics on ${host}:${port}: ${inspect(e)}`);
+	});
+
+	server.listen({ port, host }, () => {
+		logger.info(`Serving metrics on http://${host}:${port}/metrics`);
+	});
+}
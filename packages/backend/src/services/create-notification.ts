@@ -1,13 +1,14 @@
 import {
 	Notifications,
 	Mutings,
-	NoteThreadMutings,
 	UserProfiles,
 	Users,
 	Followings,
 } from "@/models/index.js";
 import {
+	type AntennaNotification,
 	Event,
+	filterNote,
 	genIdAt,
 	isSilencedServer,
 	publishToMainStream,
@@ -52,15 +53,12 @@ export async function createNotification(
 
 	const isMuted = profile?.mutingNotificationTypes.includes(type);
 
-	if (data.note != null) {
-		const threadMute = await NoteThreadMutings.findOneBy({
-			userId: notifieeId,
-			threadId: data.note.threadId || data.note.id,
-		});
-
-		if (threadMute) {
-			return null;
-		}
+	// suppress if the note is muted by the notifiee (e.g., muted words, instances, or threads)
+	if (
+		data.note != null &&
+		(await filterNote(notifieeId, data.note)).verdict !== "visible"
+	) {
+		return null;
 	}
 
 	const now = new Date();
@@ -132,3 +130,12 @@ export async function createNotification(
 
 	return notification;
 }
+
+export function createAntennaNotification(notification: AntennaNotification) {
+	return createNotification(notification.notifieeId, "app", {
+		customHeader: notification.header,
+		customBody: notification.body,
+		noteCount: notification.noteCount,
+		noteId: notification.noteId,
+	});
+}
@@ -3,6 +3,7 @@ import chalk from "chalk";
 import { default as convertColor } from "color-convert";
 import { format as dateFormat } from "date-fns";
 import { config } from "@/config.js";
+import { getRuntimeConfig } from "backend-rs";
 
 import * as SyslogPro from "syslog-pro";
 
@@ -13,6 +14,15 @@ type Domain = {
 
 type Level = "error" | "warning" | "debug" | "info" | "trace";
 
+let maxLogLevel = config.maxLogLevel;
+
+/**
+ * Applies the reloaded `maxLogLevel` (see `reloadConfig`)
+ */
+export function updateMaxLogLevel() {
+	maxLogLevel = getRuntimeConfig().maxLogLevel;
+}
+
 export default class Logger {
 	private domain: Domain;
 	private parentLogger: Logger | null = null;
@@ -51,6 +61,7 @@ export default class Logger {
 		switch (configMaxLevel) {
 			case "error":
 				return ["error"].includes(logLevel);
+			case "warn":
 			case "warning":
 				return ["error", "warning"].includes(logLevel);
 			case "info":
@@ -73,10 +84,9 @@ export default class Logger {
 		store = true,
 	): void {
 		if (
-			(config.maxLogLevel != null &&
-				!this.showThisLog(level, config.maxLogLevel)) ||
+			(maxLogLevel != null && !this.showThisLog(level, maxLogLevel)) ||
 			(config.logLevel != null && !config.logLevel.includes(level)) ||
-			(config.maxLogLevel == null &&
+			(maxLogLevel == null &&
 				config.logLevel == null &&
 				!this.showThisLog(level, "info"))
 		)
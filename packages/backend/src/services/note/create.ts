@@ -35,7 +35,10 @@ import type { User, ILocalUser, IRemoteUser } from "@/models/entities/user.js";
 import { activeUsersChart } from "@/services/chart/index.js";
 import type { IPoll } from "@/models/entities/poll.js";
 import { Poll } from "@/models/entities/poll.js";
-import { createNotification } from "@/services/create-notification.js";
+import {
+	createAntennaNotification,
+	createNotification,
+} from "@/services/create-notification.js";
 import { isDuplicateKeyValueError } from "@/misc/is-duplicate-key-value-error.js";
 import {
 	updateAntennasOnNewNote,
@@ -395,7 +398,14 @@ export default async (
 
 			const _note = toRustObject(note);
 			if (note.renoteId == null || isQuote(_note)) {
-				await updateAntennasOnNewNote(_note, user, thisNoteIsMutedBy);
+				const notifications = await updateAntennasOnNewNote(
+					_note,
+					user,
+					thisNoteIsMutedBy,
+				);
+				for (const notification of notifications) {
+					createAntennaNotification(notification);
+				}
 			}
 
 			// Channel
@@ -985,6 +985,22 @@ export type Endpoints = {
 			  };
 	};
 
+	// streaming
+	"streaming/replay": {
+		req: {
+			channel: string;
+			sinceId: string;
+			limit?: number;
+		};
+		res: {
+			events: {
+				id: string;
+				message: { type: string; body: any };
+			}[];
+			missed: boolean;
+		};
+	};
+
 	// sw
 	"sw/register": { req: TODO; res: TODO };
 	"sw/unregister": {
@@ -297,8 +297,11 @@ export interface GroupInvitedNotification extends BaseNotification {
 export interface AppNotification extends BaseNotification {
 	type: "app";
 	header?: string | null;
-	body: string;
+	body: string | null;
 	icon?: string | null;
+	// number of notes in the notification (e.g., an antenna digest)
+	noteCount?: number | null;
+	note?: Note;
 }
 
 export type Notification =
@@ -500,8 +503,13 @@ export type Antenna = {
 	userGroupId: ID | null; // TODO
 	users: string[]; // TODO
 	instances: string[];
+	expression: string | null;
 	caseSensitive: boolean;
+	wholeWord: boolean;
+	langs: string[];
+	excludeLangs: string[];
 	notify: boolean;
+	notifyDigest: boolean;
 	withReplies: boolean;
 	withFile: boolean;
 	hasUnreadNote: boolean;
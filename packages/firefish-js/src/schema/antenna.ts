@@ -86,17 +86,54 @@ export const packedAntennaSchema = {
 				nullable: false,
 			},
 		},
+		expression: {
+			type: "string",
+			optional: false,
+			nullable: true,
+		},
 		caseSensitive: {
 			type: "boolean",
 			optional: false,
 			nullable: false,
 			default: false,
 		},
+		wholeWord: {
+			type: "boolean",
+			optional: false,
+			nullable: false,
+			default: false,
+		},
+		langs: {
+			type: "array",
+			optional: false,
+			nullable: false,
+			items: {
+				type: "string",
+				optional: false,
+				nullable: false,
+			},
+		},
+		excludeLangs: {
+			type: "array",
+			optional: false,
+			nullable: false,
+			items: {
+				type: "string",
+				optional: false,
+				nullable: false,
+			},
+		},
 		notify: {
 			type: "boolean",
 			optional: false,
 			nullable: false,
 		},
+		notifyDigest: {
+			type: "boolean",
+			optional: false,
+			nullable: false,
+			default: false,
+		},
 		withReplies: {
 			type: "boolean",
 			optional: false,
@@ -75,5 +75,10 @@ export const packedNotificationSchema = {
 			optional: true,
 			nullable: true,
 		},
+		noteCount: {
+			type: "number",
+			optional: true,
+			nullable: true,
+		},
 	},
 } as const;
@@ -202,6 +202,7 @@ export default class Stream extends EventEmitter<StreamEvents> {
 			}
 
 			for (const c of connections) {
+				if (body.entryId != null) c.lastEntryId = body.entryId;
 				c.emit(body.type, body.body);
 				c.inCount++;
 			}
@@ -308,6 +309,11 @@ export abstract class Connection<
 	public name?: string; // for debug
 	public inCount = 0; // for debug
 	public outCount = 0; // for debug
+	/**
+	 * ID of the last received entry in the event log of the channel, which can be
+	 * passed to `streaming/replay` to fetch the messages missed while disconnected
+	 */
+	public lastEntryId?: string;
 
 	constructor(stream: Stream, channel: keyof Channels, name?: string) {
 		super();
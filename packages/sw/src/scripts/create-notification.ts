@@ -286,15 +286,21 @@ async function composeNotification(
 						},
 					];
 
-				case "app":
+				case "app": {
+					const body =
+						data.body.body ??
+						(data.body.noteCount != null
+							? t("_notification.newNotes", { count: data.body.noteCount })
+							: null);
 					return [
-						data.body.header || data.body.body,
+						data.body.header || body || "",
 						{
-							body: data.body.header && data.body.body,
+							body: data.body.header && body,
 							icon: data.body.icon,
 							data,
 						},
 					];
+				}
 
 				default:
 					return null;